@@ -1,19 +1,209 @@
-use clap::{Parser, Subcommand};
-use jsoncodegen::{codegen, schema};
+mod watch;
+
+use anyhow::Context;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use jsoncodegen::{codegen, jsonc, openapi, schema, yaml};
 use serde_json::Value;
-use std::{fs::File, io::BufReader};
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+};
 
 #[derive(Parser, Debug)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct JSONCodeGen {
-    /// json filepath
-    #[arg(short, long)]
-    filepath: String,
-
     #[command(subcommand)]
-    lang: Lang,
+    command: Option<Command>,
+
+    #[command(flatten)]
+    input: Input,
+
+    /// languages to generate code for, comma separated.
+    /// on stdout, the code of each language starts with a `// ---- {lang} ----` line
+    #[arg(short, long, value_delimiter = ',', required = true)]
+    lang: Vec<Lang>,
+
+    /// write the generated code to this file instead of stdout
+    #[arg(short, long, conflicts_with = "output_dir")]
+    output: Option<String>,
+
+    /// write the generated code into this directory, one or more files per language.
+    /// rust goes to root.rs and every java class to its own {ClassName}.java.
+    /// with --options '{"manifest": true}', manifest.json maps every type to its json path
+    #[arg(long)]
+    output_dir: Option<String>,
+
+    /// generator options as a json object. each language picks the keys it understands.
+    /// eg: '{"package": "com.example", "indent": "tabs"}'
+    #[arg(long)]
+    options: Option<String>,
+
+    /// name of the type generated for the top level value instead of Root.
+    /// same as the "root_name" key of --options
+    #[arg(long)]
+    root_name: Option<String>,
+
+    /// package of the generated java classes. eg: com.example.api
+    /// same as the "package" key of --options
+    #[arg(long)]
+    java_package: Option<String>,
+
+    /// visibility of the generated rust types: pub, pub(crate), pub(super), pub(in path) or private.
+    /// same as the "type_visibility" key of --options
+    #[arg(long)]
+    type_visibility: Option<codegen::Visibility>,
+
+    /// visibility of the fields of the generated rust structs. see --type-visibility
+    #[arg(long)]
+    field_visibility: Option<codegen::Visibility>,
+
+    /// pointer from a rust type to itself, eg: through the $refs of an openapi spec: box, arc or rc.
+    /// same as the "recursive_wrapper" key of --options
+    #[arg(long)]
+    recursive_wrapper: Option<codegen::RecursiveWrapper>,
+
+    /// regenerate whenever the json file changes
+    #[arg(short, long, conflicts_with_all = ["url", "schema"])]
+    watch: bool,
 }
 
 #[derive(Subcommand, Debug)]
+enum Command {
+    /// print the inferred schema without generating any code. eg: {id:int,tags:[str]?}
+    Schema {
+        #[command(flatten)]
+        input: Input,
+
+        /// print the schema as json, eg: to be cached or read by other tools
+        #[arg(long)]
+        json: bool,
+    },
+    /// compare the schemas of two json files, eg: yesterday's and today's response of an api.
+    /// exits with 1 when fields that were always there are gone, may be missing or have another type
+    #[command(
+        mut_arg("filepath", |arg| arg.required_unless_present("old").hide(true)),
+        mut_arg("url", |arg| arg.hide(true)),
+        mut_arg("schema", |arg| arg.hide(true))
+    )]
+    Diff {
+        /// json file (or directory) the old schema is inferred from
+        #[arg(conflicts_with_all = ["filepath", "url", "schema"])]
+        old: String,
+
+        /// json file (or directory) the new schema is inferred from
+        new: String,
+
+        #[command(flatten)]
+        input: Input,
+    },
+}
+
+// where the json comes from and how its schema is inferred.
+#[derive(Args, Debug, Clone)]
+struct Input {
+    /// json filepath. repeat it to merge several files as if they were the elements of one array,
+    /// eg: example responses that each have only some of the optional fields.
+    /// a directory stands for all the .json files in it (and .jsonc with --input-format jsonc)
+    // only empty for the arguments of the main command when a subcommand is used,
+    // with `--url` or `--schema`, or for the `diff` subcommand.
+    #[arg(short, long, required_unless_present_any = ["url", "schema"])]
+    filepath: Vec<String>,
+
+    /// fetch the json with a GET request instead of reading a file.
+    /// eg: https://api.example.com/users
+    #[arg(long, conflicts_with = "filepath")]
+    url: Option<String>,
+
+    /// generate from a schema in the notation printed by the `schema` subcommand
+    /// instead of inferring it from json. eg: '{id:int,tags:[str]?}'
+    #[arg(long, conflicts_with_all = ["filepath", "url"])]
+    schema: Option<String>,
+
+    /// syntax of the input file. jsonc allows comments and trailing commas.
+    /// ndjson (json lines) has one document per line, merged as the elements of a root array.
+    /// the documents of a yaml stream are merged like several files.
+    /// openapi is a json or yaml spec with a class or struct for every schema in components/schemas
+    /// (or a single schema object), instead of sample json
+    #[arg(long, value_enum, default_value_t = InputFormat::Json)]
+    input_format: InputFormat,
+
+    /// allow comments and trailing commas, as in samples copied from docs. same as --input-format jsonc
+    #[arg(long, conflicts_with = "input_format")]
+    lenient: bool,
+
+    /// dotted path of a field to leave out. eg: user._links or *.internal_debug
+    /// patterns in a .jsoncodegenignore file in the current directory are also honored
+    #[arg(short, long)]
+    exclude: Vec<String>,
+
+    /// keep fields in the order they appear in the json instead of sorting them by name
+    #[arg(long)]
+    preserve_order: bool,
+
+    /// objects of different shapes mixed with other types stay separate union members
+    /// instead of being merged into one object with optional fields
+    #[arg(long)]
+    keep_distinct_objects: bool,
+
+    /// fields with the same value in every sample (eg: "version": "1.0" in every element)
+    /// are emitted as constants
+    #[arg(long)]
+    detect_constants: bool,
+
+    /// use sibling "{key}_description" or "{key}.$description" strings as doc comments of "key"
+    #[arg(long)]
+    extract_descriptions: bool,
+
+    /// objects whose keys are all data (eg: dates, numeric ids or uuids) become maps
+    #[arg(long)]
+    detect_maps: bool,
+
+    /// keep the first value of every field as an example for the generated docs.
+    /// eg: --options '{"javadoc": true}'
+    #[arg(long)]
+    examples: bool,
+
+    /// strings that are always date-times, dates or uuids get their own type.
+    /// eg: --options '{"string_formats": true}' for java.time.Instant fields
+    #[arg(long)]
+    detect_formats: bool,
+
+    /// strings that are always base64 (at least 16 characters) become binary data.
+    /// eg: Vec<u8> with --options '{"use_serde_with": true}' or byte[] with '{"string_formats": true}'
+    #[arg(long)]
+    detect_base64: bool,
+
+    /// short arrays whose positions keep their own types across at least two samples become tuples. eg: [12.34, 56.78] coordinates
+    #[arg(long)]
+    detect_tuples: bool,
+
+    /// json file with the definitions that {"$ref": "#/definitions/Address"} objects point to.
+    /// the references are replaced by the definitions, except the ones back to an enclosing definition,
+    /// which stay references (eg: &Node in the schema)
+    #[arg(long, value_name = "FILE")]
+    resolve_refs: Option<PathBuf>,
+
+    /// reject json with objects and arrays nested deeper than this (the top level value is at depth 1).
+    /// json is never parsed deeper than 128 levels
+    #[arg(long)]
+    max_depth: Option<usize>,
+}
+
+/// json files larger than this (in bytes) are streamed instead of being read into memory.
+/// see `Input::infer`
+const STREAMING_THRESHOLD: u64 = 64 * 1024 * 1024;
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum InputFormat {
+    Json,
+    Jsonc,
+    Ndjson,
+    Yaml,
+    Openapi,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
 enum Lang {
     Java,
     Rust,
@@ -22,17 +212,363 @@ enum Lang {
 fn main() -> anyhow::Result<()> {
     let args = JSONCodeGen::parse();
 
-    let file = File::open(args.filepath)?;
-    let reader = BufReader::new(file);
+    match &args.command {
+        Some(Command::Schema { input, json }) => {
+            let schema = input.infer()?.0;
+            match json {
+                true => println!("{}", serde_json::to_string_pretty(&schema)?),
+                false => println!("{schema}"),
+            }
+            return Ok(());
+        }
+        Some(Command::Diff { old, new, input }) => {
+            let schema = |filepath: &String| {
+                let input = Input {
+                    filepath: vec![filepath.clone()],
+                    ..input.clone()
+                };
+                Ok::<_, anyhow::Error>(input.infer()?.0)
+            };
+            let changes = schema::diff(&schema(old)?, &schema(new)?);
+            for change in &changes {
+                match change.is_breaking() {
+                    true => println!("{change} (breaking)"),
+                    false => println!("{change}"),
+                }
+            }
+            if changes.is_empty() {
+                println!("no changes");
+            }
+            if changes.iter().any(|change| change.is_breaking()) {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        None => {}
+    }
 
-    let json: Value = serde_json::from_reader(reader)?;
-    let schema = schema::extract(json);
-    let mut stdout = std::io::stdout().lock();
+    if args.output.is_some() && args.lang.len() > 1 {
+        anyhow::bail!("--output takes a single --lang. use --output-dir for multiple languages");
+    }
 
-    match args.lang {
-        Lang::Java => codegen::java(schema, &mut stdout)?,
-        Lang::Rust => codegen::rust(schema, &mut stdout)?,
+    generate(&args)?;
+    if args.watch {
+        watch::watch(&args.input.files()?, |path| {
+            // the regenerated code follows the earlier output on stdout.
+            if args.output.is_none() && args.output_dir.is_none() {
+                println!("// ---- {} ----", path.display());
+            }
+            generate(&args)
+        })?;
     }
 
     Ok(())
 }
+
+impl Input {
+    fn input_format(&self) -> InputFormat {
+        match self.lenient {
+            true => InputFormat::Jsonc,
+            false => self.input_format,
+        }
+    }
+
+    /// the `--filepath`s, with directories replaced by the json files in them (sorted by name).
+    fn files(&self) -> anyhow::Result<Vec<PathBuf>> {
+        let extensions: &[&str] = match self.input_format() {
+            InputFormat::Json => &["json"],
+            InputFormat::Jsonc => &["json", "jsonc"],
+            InputFormat::Ndjson => &["ndjson", "jsonl"],
+            InputFormat::Yaml => &["yaml", "yml"],
+            InputFormat::Openapi => &["json", "yaml", "yml"],
+        };
+
+        let mut files = vec![];
+        for filepath in &self.filepath {
+            let path = PathBuf::from(filepath);
+            if !path.is_dir() {
+                files.push(path);
+                continue;
+            }
+
+            let mut entries = vec![];
+            for entry in fs::read_dir(&path).with_context(|| format!("cannot read {filepath}"))? {
+                let entry = entry?.path();
+                let json = entry
+                    .extension()
+                    .is_some_and(|ext| extensions.iter().any(|e| ext == *e));
+                if json && entry.is_file() {
+                    entries.push(entry);
+                }
+            }
+            if entries.is_empty() {
+                anyhow::bail!("{filepath} has no json files");
+            }
+            entries.sort();
+            files.extend(entries);
+        }
+
+        Ok(files)
+    }
+
+    /// `(where it came from, contents)` of every file or of the body of the `--url` response.
+    fn read(&self) -> anyhow::Result<Vec<(String, String)>> {
+        match &self.url {
+            Some(url) => Ok(vec![(format!("the response of {url}"), fetch(url)?)]),
+            None => self
+                .files()?
+                .into_iter()
+                .map(|file| {
+                    let source = file.display().to_string();
+                    let text = fs::read_to_string(&file)
+                        .with_context(|| format!("cannot read {source}"))?;
+                    Ok((source, text))
+                })
+                .collect(),
+        }
+    }
+
+    /// the merged schema of the input and the documents it was inferred from.
+    /// a single json file larger than `STREAMING_THRESHOLD` is never read into memory as a whole.
+    /// its schema is inferred while it is read, and no documents are returned.
+    /// a schema given with `--schema` is parsed instead, without any documents.
+    fn infer(&self) -> anyhow::Result<(schema::Schema, Vec<(String, String)>)> {
+        if let Some(schema) = &self.schema {
+            let schema = schema
+                .parse()
+                .with_context(|| format!("{schema} is not a valid schema"))?;
+            return Ok((schema, vec![]));
+        }
+
+        let files = match (&self.url, self.input_format()) {
+            (None, InputFormat::Json | InputFormat::Ndjson) => self.files()?,
+            _ => vec![],
+        };
+        if let [file] = files.as_slice() {
+            if fs::metadata(file)?.len() > STREAMING_THRESHOLD {
+                let reader =
+                    File::open(file).with_context(|| format!("cannot read {}", file.display()))?;
+                let schema = match self.input_format() {
+                    InputFormat::Ndjson => {
+                        schema::Schema::from_ndjson_with(reader, &self.options()?)
+                    }
+                    _ => schema::Schema::from_reader_with(reader, &self.options()?),
+                }
+                .with_context(|| format!("{} is not valid json", file.display()))?;
+                return Ok((schema, vec![]));
+            }
+        }
+
+        let documents = self.read()?;
+        let schema = self.schema(&documents)?;
+        Ok((schema, documents))
+    }
+
+    /// parse the `documents` read from the input and infer their merged schema.
+    fn schema(&self, documents: &[(String, String)]) -> anyhow::Result<schema::Schema> {
+        if let InputFormat::Openapi = self.input_format() {
+            let [(source, text)] = documents else {
+                anyhow::bail!("--input-format openapi reads a single spec");
+            };
+            // yaml is a superset of json
+            let document = yaml(source, text)?.into_iter().next().unwrap_or_default();
+            let schema = match document.get("openapi") {
+                Some(_) => openapi::from_openapi(&document),
+                None => openapi::from_openapi_schema(&document),
+            };
+            return schema.with_context(|| format!("{source} is not a valid openapi spec"));
+        }
+
+        let mut values = vec![];
+        for (source, text) in documents {
+            let parsed = match self.input_format() {
+                InputFormat::Json => serde_json::from_str(text).map(|value| vec![value]),
+                InputFormat::Jsonc => jsonc::from_str(text).map(|value| vec![value]),
+                InputFormat::Ndjson => ndjson(text).collect(),
+                InputFormat::Yaml => {
+                    values.extend(yaml(source, text)?);
+                    continue;
+                }
+                InputFormat::Openapi => unreachable!(),
+            };
+            values.extend(parsed.with_context(|| format!("{source} is not valid json"))?);
+        }
+        // the lines of every file are elements of the same root array.
+        if let InputFormat::Ndjson = self.input_format() {
+            values = vec![Value::Array(values)];
+        }
+        Ok(schema::Schema::try_from_values_with(
+            values,
+            &self.options()?,
+        )?)
+    }
+
+    /// plain json of the `text` of a document, for the generated round trip test.
+    /// json lines become an array. of a yaml stream, only the first document is kept.
+    fn plain_json(&self, text: &str) -> anyhow::Result<String> {
+        Ok(match self.input_format() {
+            InputFormat::Json | InputFormat::Jsonc => jsonc::strip(text),
+            InputFormat::Ndjson => {
+                serde_json::to_string(&ndjson(text).collect::<Result<Vec<Value>, _>>()?)?
+            }
+            InputFormat::Yaml => serde_json::to_string(
+                yaml::from_str(text)?
+                    .documents
+                    .first()
+                    .unwrap_or(&Value::Null),
+            )?,
+            InputFormat::Openapi => {
+                anyhow::bail!("the generated tests need json, not an openapi spec")
+            }
+        })
+    }
+
+    fn options(&self) -> anyhow::Result<schema::SchemaInferenceOptions> {
+        let mut exclude = ignore_file()?;
+        exclude.extend(self.exclude.iter().cloned());
+        let field_order = match self.preserve_order {
+            true => schema::FieldOrder::Insertion,
+            false => schema::FieldOrder::Alphabetical,
+        };
+        let resolve_refs = match &self.resolve_refs {
+            Some(path) => {
+                let text = fs::read_to_string(path)
+                    .with_context(|| format!("cannot read {}", path.display()))?;
+                let definitions = serde_json::from_str(&text)
+                    .with_context(|| format!("{} is not valid json", path.display()))?;
+                Some(definitions)
+            }
+            None => None,
+        };
+        Ok(schema::SchemaInferenceOptions {
+            exclude,
+            field_order,
+            keep_distinct_objects: self.keep_distinct_objects,
+            detect_constants: self.detect_constants,
+            extract_descriptions: self.extract_descriptions,
+            detect_maps: self.detect_maps,
+            examples: self.examples,
+            detect_formats: self.detect_formats,
+            detect_base64: self.detect_base64,
+            detect_tuples: self.detect_tuples,
+            resolve_refs,
+            max_depth: self.max_depth,
+        })
+    }
+}
+
+fn generate(args: &JSONCodeGen) -> anyhow::Result<()> {
+    let (schema, documents) = args.input.infer()?;
+    let options: Value = match &args.options {
+        Some(options) => serde_json::from_str(options)?,
+        None => Value::Object(Default::default()),
+    };
+    let mut java_options: codegen::JavaOptions = serde_json::from_value(options.clone())?;
+    let mut rust_options: codegen::RustOptions = serde_json::from_value(options)?;
+    if let Some(root_name) = &args.root_name {
+        java_options.root_name = Some(root_name.clone());
+        rust_options.root_name = Some(root_name.clone());
+    }
+    if let Some(package) = &args.java_package {
+        java_options.package = Some(package.clone());
+    }
+    if let Some(visibility) = &args.type_visibility {
+        rust_options.type_visibility = visibility.clone();
+    }
+    if let Some(visibility) = &args.field_visibility {
+        rust_options.field_visibility = visibility.clone();
+    }
+    if let Some(recursive_wrapper) = args.recursive_wrapper {
+        rust_options.recursive_wrapper = recursive_wrapper;
+    }
+
+    for lang in &args.lang {
+        let schema = schema.clone();
+        // the code of every language follows the one before it on stdout.
+        if args.lang.len() > 1 && args.output.is_none() && args.output_dir.is_none() {
+            let lang = lang.to_possible_value().expect("no skipped languages");
+            println!("// ---- {} ----", lang.get_name());
+        }
+        match (lang, &args.output_dir) {
+            (Lang::Java, Some(output_dir)) => codegen::java(
+                schema,
+                &java_options,
+                &mut codegen::DirectorySink::new(output_dir),
+            )?,
+            (Lang::Java, None) => codegen::java(schema, &java_options, &mut output(args)?)?,
+            (Lang::Rust, Some(output_dir)) => {
+                codegen::rust_files(
+                    schema,
+                    &rust_options,
+                    &mut codegen::DirectorySink::new(output_dir),
+                )?;
+                // read by the generated round trip test. jsonc is stripped down to plain json.
+                // any one of several inputs fits the merged types, so the first one is used.
+                if rust_options.generate_tests {
+                    let input = Path::new(output_dir).join("input.json");
+                    let text = match documents.first() {
+                        Some((_, text)) => text.clone(),
+                        // streamed from a single large json file
+                        None => match args.input.files()?.first() {
+                            Some(file) => fs::read_to_string(file)?,
+                            None => anyhow::bail!("the generated tests need json, not --schema"),
+                        },
+                    };
+                    fs::write(input, args.input.plain_json(&text)?)?;
+                }
+            }
+            (Lang::Rust, None) => match rust_options.manifest {
+                true => codegen::rust_files(schema, &rust_options, &mut output(args)?)?,
+                false => codegen::rust(schema, &rust_options, &mut output(args)?)?,
+            },
+        }
+    }
+
+    Ok(())
+}
+
+/// body of a GET request to `url`. responses without a 2xx status are errors.
+fn fetch(url: &str) -> anyhow::Result<String> {
+    let mut response = ureq::get(url)
+        .call()
+        .with_context(|| format!("GET {url} failed"))?;
+    Ok(response.body_mut().read_to_string()?)
+}
+
+/// the documents of newline delimited json. blank lines are skipped.
+fn ndjson(text: &str) -> impl Iterator<Item = serde_json::Result<Value>> + '_ {
+    serde_json::Deserializer::from_str(text).into_iter::<Value>()
+}
+
+/// the documents of a yaml stream. values that have no json equivalent are reported on stderr.
+fn yaml(source: &str, text: &str) -> anyhow::Result<Vec<Value>> {
+    let yaml = yaml::from_str(text).with_context(|| format!("{source} is not valid yaml"))?;
+    for warning in yaml.warnings {
+        eprintln!("warning: {source}: {warning}");
+    }
+    Ok(yaml.documents)
+}
+
+/// patterns in `.jsoncodegenignore`, one per line. blank lines and `#` comments are skipped.
+fn ignore_file() -> anyhow::Result<Vec<String>> {
+    let text = match fs::read_to_string(".jsoncodegenignore") {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok(text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect())
+}
+
+/// `--output` file or stdout
+fn output(args: &JSONCodeGen) -> anyhow::Result<Box<dyn Write>> {
+    Ok(match &args.output {
+        Some(output) => Box::new(File::create(output)?),
+        None => Box::new(std::io::stdout().lock()),
+    })
+}
@@ -0,0 +1,146 @@
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// editors often write a file in several steps (truncate, write, rename).
+/// events closer together than this are treated as a single change.
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// calls `on_change` with the changed file every time one of the files at `paths` changes, until Ctrl-C.
+/// errors from `on_change` are reported and watching continues.
+pub fn watch(
+    paths: &[PathBuf],
+    mut on_change: impl FnMut(&Path) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let paths = paths
+        .iter()
+        .map(|path| path.canonicalize())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let running = Arc::new(AtomicBool::new(true));
+    {
+        let running = running.clone();
+        ctrlc::set_handler(move || running.store(false, Ordering::SeqCst))?;
+    }
+
+    // watch the parent directories instead of the files themselves
+    // because saving by rename replaces the watched inode.
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    let mut dirs: Vec<PathBuf> = paths
+        .iter()
+        .map(|path| path.parent().unwrap_or(path).to_path_buf())
+        .collect();
+    dirs.sort();
+    dirs.dedup();
+    for dir in &dirs {
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    }
+
+    let mut changed = None;
+
+    let mut debouncer = Debouncer::new(DEBOUNCE);
+    while running.load(Ordering::SeqCst) {
+        match rx.recv_timeout(Duration::from_millis(20)) {
+            Ok(Ok(event)) => {
+                let modified = matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_));
+                match event.paths.iter().find(|path| paths.contains(path)) {
+                    Some(path) if modified => {
+                        changed = Some(path.clone());
+                        debouncer.event(Instant::now());
+                    }
+                    _ => {}
+                }
+            }
+            Ok(Err(e)) => return Err(e.into()),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        if debouncer.ready(Instant::now()) {
+            let path = changed.take().unwrap_or_default();
+            eprintln!(
+                "[{}] {}",
+                humantime::format_rfc3339_seconds(std::time::SystemTime::now()),
+                path.display()
+            );
+            if let Err(e) = on_change(&path) {
+                eprintln!("error: {}", e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+struct Debouncer {
+    delay: Duration,
+    last_event: Option<Instant>,
+}
+
+impl Debouncer {
+    fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            last_event: None,
+        }
+    }
+
+    fn event(&mut self, at: Instant) {
+        self.last_event = Some(at);
+    }
+
+    /// true once, after `delay` has passed without any new events.
+    fn ready(&mut self, now: Instant) -> bool {
+        match self.last_event {
+            Some(last_event) if now.duration_since(last_event) >= self.delay => {
+                self.last_event = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    #[test]
+    fn no_events() {
+        let mut debouncer = Debouncer::new(100 * MS);
+        assert!(!debouncer.ready(Instant::now() + 1000 * MS));
+    }
+
+    #[test]
+    fn single_event() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(100 * MS);
+
+        debouncer.event(start);
+        assert!(!debouncer.ready(start + 50 * MS));
+        assert!(debouncer.ready(start + 100 * MS));
+        assert!(!debouncer.ready(start + 200 * MS));
+    }
+
+    #[test]
+    fn burst_of_events() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(100 * MS);
+
+        debouncer.event(start);
+        debouncer.event(start + 60 * MS);
+        debouncer.event(start + 120 * MS);
+        assert!(!debouncer.ready(start + 150 * MS));
+        assert!(debouncer.ready(start + 220 * MS));
+    }
+}
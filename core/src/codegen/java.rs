@@ -1,271 +1,2022 @@
-use super::{to_camel_case_or_unknown, to_pascal_case_or_unknown, Iota};
-use crate::schema::{Field, FieldType, Schema};
+use super::{
+    root_element_name, singular, to_camel_case_or_unknown, to_pascal_case_or_unknown, CodegenError,
+    Definitions, IndentStyle, Iota, SharedObjects, Sink,
+};
+use crate::schema::{path_segment, Field, FieldType, FieldTypeAggregator, Schema, StringFormat};
+use convert_case::{Case, Casing};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
 use std::io::{Error, Write};
 
-pub fn java<W: Write>(schema: Schema, out: &mut W) -> Result<(), Error> {
-    let mut ctx = Context::new();
+/// can be deserialized from json (eg: `{"package": "com.example", "indent": "tabs"}`).
+/// missing keys keep their defaults and unknown keys are ignored.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct JavaOptions {
+    /// `package` declaration at the top of every generated file.
+    /// classes are put in the default package when `None`.
+    pub package: Option<String>,
+    pub indent: IndentStyle,
+    /// generate a single class for structurally identical objects.
+    /// a shape reached through different field names gets a neutral name (eg: `FromOrTo`)
+    pub reuse_identical_objects: bool,
+    pub optional_fields: OptionalFields,
+    /// `equals` and `hashCode` comparing all the fields.
+    pub generate_equals_hashcode: bool,
+    /// `toString` listing all the fields.
+    pub generate_to_string: bool,
+    pub collections: Collections,
+    pub floats: Floats,
+    pub library: JsonLibrary,
+    /// `private final` fields set by a single constructor, getters without setters.
+    /// the constructor is annotated with `@JsonCreator` for jackson.
+    pub immutable: bool,
+    /// javadoc with the json path on every class (eg: `/** Derived from JSON path $.cart[].metadata */`)
+    /// and with the example value on every getter (see `SchemaInferenceOptions::examples`).
+    pub javadoc: bool,
+    /// name of the class generated for the top level value. `Root` when `None`.
+    pub root_name: Option<String>,
+    /// `long`, `double` and `boolean` fields for values that are never missing or `null`.
+    /// the getters, setters and constructor parameters keep the boxed types.
+    pub primitives: bool,
+    pub unions: UnionStyle,
+    /// `jakarta.validation` annotations on the fields: `@NotNull` unless optional,
+    /// `@Valid` when generated classes are nested and `@Size(min = 0)` on collections.
+    pub bean_validation: bool,
+    /// `java.time.Instant`, `java.time.LocalDate` and `java.util.UUID` fields for strings in those formats
+    /// (see `SchemaInferenceOptions::detect_formats`), and `byte[]` for base64 with jackson,
+    /// which reads and writes them as base64 strings. plain `String` fields otherwise.
+    /// jackson needs the `JavaTimeModule` of `jackson-datatype-jsr310` to be registered on the `ObjectMapper`,
+    /// and `WRITE_DATES_AS_TIMESTAMPS` disabled for the dates in unions.
+    pub string_formats: bool,
+    /// a `manifest.json` file mapping the name of every generated class to the json path it was derived from.
+    /// eg: `{"Item": "$.cart[]", "Root": "$"}`
+    pub manifest: bool,
+    /// setters return `this`, so that calls can be chained (eg: `new Point().setX(1L).setY(2L)`).
+    pub fluent_setters: bool,
+    /// `withX` methods returning a copy with one field replaced. only with `immutable`.
+    pub withers: bool,
+    /// jackson classes are annotated with `@JsonIgnoreProperties(ignoreUnknown = true)`
+    /// so that fields added to the json later don't break deserialization.
+    /// `strict` leaves the annotation out and unknown fields fail with the `ObjectMapper`'s
+    /// `FAIL_ON_UNKNOWN_PROPERTIES` (enabled by default). gson always ignores unknown fields.
+    pub strict: bool,
+    /// static `fromJson(String)` and `toJson(Root)` methods on the root class, with a shared `ObjectMapper`
+    /// already configured for the other options (eg: the modules needed by `string_formats`),
+    /// so that callers don't have to know which settings the generated classes expect.
+    pub json_facade: bool,
+    pub layout: Layout,
+    /// jackson classes are annotated with `@JsonInclude(JsonInclude.Include.NON_NULL)`, so that fields
+    /// that were missing from the json aren't written back as `null`.
+    /// `NON_ABSENT` with `OptionalFields::JavaOptional`, to also leave out `Optional.empty()`.
+    pub exclude_null_on_serialize: bool,
+    pub null_field_policy: NullFieldPolicy,
+}
+
+/// how fields that were `null` in every value (so their type is unknown) are declared.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NullFieldPolicy {
+    /// `Object` fields, which hold whatever the json turns out to have.
+    #[default]
+    KeepAsObject,
+    /// no field at all, so they are neither read nor written.
+    /// with `strict`, jackson classes list them in `@JsonIgnoreProperties` to still accept their keys.
+    Skip,
+}
+
+/// how the classes of values with more than one type are generated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnionStyle {
+    /// a class with one nullable field per variant. only the field of the json value is set.
+    #[default]
+    Wrapper,
+    /// a sealed interface (java 17) with one record per variant
+    /// and an `accept(Visitor)` method to handle every variant.
+    /// jackson only. gson unions stay wrapper classes.
+    SealedInterface,
+}
+
+/// json library the generated classes are annotated for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JsonLibrary {
+    /// `com.fasterxml.jackson`
+    #[default]
+    Jackson,
+    /// `com.google.gson`. unions get a `TypeAdapter` registered with `@JsonAdapter`.
+    Gson,
+}
+
+/// how json arrays are declared.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Collections {
+    /// `java.util.List<T>`
+    #[default]
+    List,
+    /// `T[]`
+    Arrays,
+}
+
+/// how json numbers with a fraction or exponent are declared.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Floats {
+    /// `Double`
+    #[default]
+    Double,
+    /// `java.math.BigDecimal`, read without going through a `double` so decimals round trip exactly.
+    /// untyped values (eg: `Object` fields) also need jackson's `USE_BIG_DECIMAL_FOR_FLOATS`
+    BigDecimal,
+}
+
+/// how fields that are missing or `null` in some of the values are declared.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OptionalFields {
+    /// same declaration as a required field. absent values are left as `null`.
+    #[default]
+    Plain,
+    /// `java.util.Optional<T>`, initialized to `Optional.empty()`.
+    /// needs jackson's `Jdk8Module` to be registered on the `ObjectMapper`.
+    JavaOptional,
+    /// `@Nullable` (jetbrains annotations) on the getter and the setter parameter.
+    Nullable,
+}
+
+/// which files the classes are written to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Layout {
+    /// every class is a top level `public class` in its own `{ClassName}.java` file.
+    #[default]
+    TopLevel,
+    /// a single `{Root}.java` file. the other classes are `public static class`es nested in the root class.
+    Nested,
+}
+
+/// each class is written to its own `{ClassName}.java` sink (see `JavaOptions::layout`).
+pub fn java<S: Sink>(
+    schema: Schema,
+    options: &JavaOptions,
+    sink: &mut S,
+) -> Result<(), CodegenError> {
+    match options.layout {
+        Layout::TopLevel => {
+            java_files(schema, options, sink)?;
+        }
+        Layout::Nested => {
+            let mut files = Files::default();
+            let root = java_files(schema, options, &mut files)?;
+            nest(files, &root, options, sink)?;
+        }
+    }
+    Ok(())
+}
+
+/// the generated files, in the order they were written. see `nest`
+#[derive(Default)]
+struct Files(Vec<(String, Vec<u8>)>);
+
+impl Sink for Files {
+    fn sink(&mut self, filename: &str) -> Result<Box<dyn Write + '_>, Error> {
+        self.0.push((filename.into(), vec![]));
+        let (_, buf) = self.0.last_mut().expect("just pushed");
+        Ok(Box::new(buf))
+    }
+}
+
+/// merges the `.java` files into the file of the `root` class, which the other classes are nested in.
+/// the package and imports of every file are written once, at the top.
+fn nest<S: Sink>(
+    files: Files,
+    root: &str,
+    options: &JavaOptions,
+    sink: &mut S,
+) -> Result<(), Error> {
+    let i1 = options.indent.level(1);
+    let root_file = format!("{root}.java");
+
+    let mut imports: Vec<String> = vec![];
+    let mut root_body: Vec<String> = vec![];
+    let mut nested: Vec<String> = vec![];
+    let mut others = vec![];
+    for (filename, buf) in files.0 {
+        if !filename.ends_with(".java") {
+            others.push((filename, buf));
+            continue;
+        }
+        for line in String::from_utf8_lossy(&buf).lines() {
+            if line.starts_with("package ") {
+                continue;
+            }
+            if line.starts_with("import ") {
+                if !imports.iter().any(|import| import == line) {
+                    imports.push(line.into());
+                }
+                continue;
+            }
+            match (filename == root_file, line.strip_prefix("public class ")) {
+                (true, _) => root_body.push(line.into()),
+                (false, Some(rest)) => nested.push(format!("{i1}public static class {rest}")),
+                (false, None) => nested.push(format!("{i1}{line}")),
+            }
+        }
+    }
+
+    let mut out = sink.sink(&root_file)?;
+    if let Some(package) = &options.package {
+        writeln!(out, "package {};", package)?;
+    }
+    for import in imports {
+        writeln!(out, "{import}")?;
+    }
+    // the nested classes go before the closing brace of the root class.
+    let close = match root_body.pop() {
+        Some(last) if last == "}" => last,
+        // `public class Root extends ... {}`
+        Some(last) => {
+            root_body.push(last.trim_end_matches('}').into());
+            "}".into()
+        }
+        None => "}".into(),
+    };
+    for line in root_body.iter().chain(&nested) {
+        writeln!(out, "{line}")?;
+    }
+    writeln!(out, "{close}")?;
+    drop(out);
 
-    match schema {
-        Schema::Object(fields) => ctx.add_class("Root".into(), fields),
+    for (filename, buf) in others {
+        sink.sink(&filename)?.write_all(&buf)?;
+    }
+    Ok(())
+}
+
+/// writes every class to its own sink and returns the name of the root class.
+fn java_files<S: Sink>(
+    schema: Schema,
+    options: &JavaOptions,
+    sink: &mut S,
+) -> Result<String, CodegenError> {
+    let mut ctx = Context::new(
+        options
+            .reuse_identical_objects
+            .then(|| SharedObjects::new(&schema, options.root_name.as_deref())),
+        Definitions::new(&schema),
+        options.collections,
+        options.floats,
+        options.string_formats,
+        options.string_formats && options.library == JsonLibrary::Jackson,
+        options.null_field_policy,
+    );
+    let root = ctx.class_name(options.root_name.as_deref().unwrap_or("Root"))?;
+    let (i1, i2) = (options.indent.level(1), options.indent.level(2));
+    let modifiers = match options.immutable {
+        true => "private final",
+        false => "private",
+    };
+
+    // java has no type aliases, so a root array or `{}` gets a `Root` list or map subclass.
+    let root_superclass = match schema {
+        Schema::Object(fields) if fields.is_empty() => {
+            Some("java.util.HashMap<String, Object>".to_string())
+        }
+        Schema::Object(fields) => {
+            let fields = ctx.definitions.refer_to(fields);
+            ctx.add_class(root.clone(), fields)?;
+            None
+        }
         Schema::Array(ty) => {
-            ctx.process_field(Field {
-                name: "Item".into(),
+            ctx.path.push_str("[]");
+            let member_var = ctx.process_field(Field {
+                name: root_element_name(options.root_name.as_deref()),
                 ty,
-            });
+                description: None,
+                example: None,
+            })?;
+            Some(format!("java.util.ArrayList<{}>", member_var.type_name))
         }
     };
 
+    // classes whose fields may have constraints, for `@Valid`.
+    let generated: Vec<String> = ctx
+        .classes
+        .iter()
+        .map(|class| class.name.clone())
+        .chain(ctx.unions.iter().map(|union| union.name.clone()))
+        .collect();
+
+    // by name rather than in the order they were found, so that a small change in the json
+    // doesn't move the classes around in the combined output.
+    ctx.classes.sort_by(|a, b| a.name.cmp(&b.name));
+    ctx.unions.sort_by(|a, b| a.name.cmp(&b.name));
+
+    // sorted like the classes, whatever the order of the keys of a `serde_json::Map`.
+    let manifest: BTreeMap<String, String> = ctx
+        .classes
+        .iter()
+        .map(|class| (class.name.clone(), class.path.clone()))
+        .chain(
+            ctx.unions
+                .iter()
+                .map(|union| (union.name.clone(), union.path.clone())),
+        )
+        .chain(
+            root_superclass
+                .is_some()
+                .then(|| (root.clone(), "$".to_string())),
+        )
+        .collect();
+
+    // json keys of every class, to tell apart the objects of a union. see `jackson_bind_node`
+    let keys: HashMap<String, Vec<String>> = ctx
+        .classes
+        .iter()
+        .map(|class| {
+            let keys = class
+                .vars
+                .iter()
+                .map(|var| var.original_name.clone())
+                .chain(class.skipped.iter().cloned())
+                .collect();
+            (class.name.clone(), keys)
+        })
+        .collect();
+
     for class in ctx.classes {
-        writeln!(out, "// {}.java", class.name)?;
-        writeln!(out, "import com.fasterxml.jackson.annotation.*;")?;
+        let mut out = sink.sink(&format!("{}.java", class.name))?;
+        if let Some(package) = &options.package {
+            writeln!(out, "package {};", package)?;
+        }
+        if options.generate_equals_hashcode {
+            writeln!(out, "import java.util.Objects;")?;
+        }
+        match options.library {
+            JsonLibrary::Jackson => writeln!(out, "import com.fasterxml.jackson.annotation.*;")?,
+            JsonLibrary::Gson => writeln!(out, "import com.google.gson.annotations.*;")?,
+        }
+        if options.bean_validation {
+            writeln!(out, "import jakarta.validation.constraints.*;")?;
+            writeln!(out, "import jakarta.validation.Valid;")?;
+        }
+
+        if class.name == root {
+            strict_comment(&mut out, options)?;
+        }
 
+        if options.javadoc {
+            javadoc(
+                &mut out,
+                "",
+                &format!("Derived from JSON path {}", class.path),
+            )?;
+        }
+        if options.library == JsonLibrary::Jackson && !options.strict {
+            writeln!(out, "@JsonIgnoreProperties(ignoreUnknown = true)")?;
+        }
+        if options.library == JsonLibrary::Jackson && options.strict && !class.skipped.is_empty() {
+            let keys: Vec<String> = class.skipped.iter().map(|key| format!("{key:?}")).collect();
+            writeln!(out, "@JsonIgnoreProperties({{{}}})", keys.join(", "))?;
+        }
+        if options.library == JsonLibrary::Jackson && options.exclude_null_on_serialize {
+            match options.optional_fields {
+                OptionalFields::JavaOptional => {
+                    writeln!(out, "@JsonInclude(JsonInclude.Include.NON_ABSENT)")?
+                }
+                _ => writeln!(out, "@JsonInclude(JsonInclude.Include.NON_NULL)")?,
+            }
+        }
         writeln!(out, "public class {} {{", class.name)?;
         for member_var in &class.vars {
-            writeln!(
-                out,
-                "    private {} {};",
-                member_var.type_name, member_var.var_name
-            )?;
+            if let Some(constant) = &member_var.constant {
+                writeln!(
+                    out,
+                    "{i1}public static final {} {} = {};",
+                    member_var.type_name,
+                    member_var.var_name.to_case(Case::UpperSnake),
+                    constant
+                )?;
+            }
+        }
+        for member_var in &class.vars {
+            if let Some(description) = &member_var.description {
+                javadoc(&mut out, &i1, description)?;
+            }
+            // gson binds fields directly, so the key goes on the field instead of the accessors.
+            if options.library == JsonLibrary::Gson
+                && member_var.original_name != member_var.var_name
+            {
+                writeln!(
+                    out,
+                    "{i1}@SerializedName({})",
+                    string_literal(&member_var.original_name)
+                )?;
+            }
+            if options.bean_validation {
+                // `Object` fields were `null` in every sample.
+                if !member_var.optional && member_var.type_name != "Object" {
+                    writeln!(out, "{i1}@NotNull")?;
+                }
+                if generated
+                    .iter()
+                    .any(|name| name == element_type(&member_var.type_name))
+                {
+                    writeln!(out, "{i1}@Valid")?;
+                }
+                if is_collection(&member_var.type_name) {
+                    writeln!(out, "{i1}@Size(min = 0)")?;
+                }
+            }
+            // written as iso strings even without disabling `WRITE_DATES_AS_TIMESTAMPS`.
+            if options.library == JsonLibrary::Jackson
+                && matches!(
+                    member_var.type_name.as_str(),
+                    "java.time.Instant" | "java.time.LocalDate"
+                )
+            {
+                writeln!(out, "{i1}@JsonFormat(shape = JsonFormat.Shape.STRING)")?;
+            }
+            match (
+                member_var.optional && options.optional_fields == OptionalFields::JavaOptional,
+                options.immutable,
+            ) {
+                (true, false) => writeln!(
+                    out,
+                    "{i1}private java.util.Optional<{}> {} = java.util.Optional.empty();",
+                    member_var.type_name, member_var.var_name
+                )?,
+                (true, true) => writeln!(
+                    out,
+                    "{i1}{modifiers} java.util.Optional<{}> {};",
+                    member_var.type_name, member_var.var_name
+                )?,
+                (false, _) => writeln!(
+                    out,
+                    "{i1}{modifiers} {} {};",
+                    match options.primitives && !member_var.optional {
+                        true => primitive(&member_var.type_name),
+                        false => &member_var.type_name,
+                    },
+                    member_var.var_name
+                )?,
+            }
+        }
+
+        if options.immutable {
+            // optional values are passed as plain (possibly null) values.
+            let params: Vec<String> = class
+                .vars
+                .iter()
+                .map(|var| {
+                    let json_property = match options.library {
+                        JsonLibrary::Jackson => {
+                            format!("@JsonProperty({}) ", string_literal(&var.original_name))
+                        }
+                        JsonLibrary::Gson => "".into(),
+                    };
+                    let nullable =
+                        match var.optional && options.optional_fields == OptionalFields::Nullable {
+                            true => "@org.jetbrains.annotations.Nullable ",
+                            false => "",
+                        };
+                    format!(
+                        "{json_property}{nullable}{} {}",
+                        var.type_name, var.var_name
+                    )
+                })
+                .collect();
+
+            if options.library == JsonLibrary::Jackson {
+                writeln!(out, "{i1}@JsonCreator")?;
+            }
+            writeln!(out, "{i1}public {}({}) {{", class.name, params.join(", "))?;
+            for var in &class.vars {
+                match var.optional && options.optional_fields == OptionalFields::JavaOptional {
+                    true => writeln!(
+                        out,
+                        "{i2}this.{0} = java.util.Optional.ofNullable({0});",
+                        var.var_name
+                    )?,
+                    false => writeln!(out, "{i2}this.{0} = {0};", var.var_name)?,
+                }
+            }
+            writeln!(out, "{i1}}}")?;
         }
 
         for member_var in &class.vars {
-            let add_json_property = member_var.original_name != member_var.var_name;
+            let add_json_property = options.library == JsonLibrary::Jackson
+                && member_var.original_name != member_var.var_name;
+            let (type_name, nullable) = match (member_var.optional, options.optional_fields) {
+                (true, OptionalFields::JavaOptional) => {
+                    (format!("java.util.Optional<{}>", member_var.type_name), "")
+                }
+                (true, OptionalFields::Nullable) => (
+                    member_var.type_name.clone(),
+                    "@org.jetbrains.annotations.Nullable ",
+                ),
+                _ => (member_var.type_name.clone(), ""),
+            };
+            if let (true, Some(example)) = (options.javadoc, &member_var.example) {
+                javadoc(&mut out, &i1, &format!("Example: {example}"))?;
+            }
             if add_json_property {
-                writeln!(out, "    @JsonProperty(\"{}\")", member_var.original_name)?;
+                writeln!(
+                    out,
+                    "{i1}@JsonProperty({})",
+                    string_literal(&member_var.original_name)
+                )?;
             }
             writeln!(
                 out,
-                "    public {} get{}() {{ return {}; }}",
-                member_var.type_name,
-                to_pascal_case_or_unknown(&member_var.var_name, &mut ctx.iota),
+                "{i1}{nullable}public {} get{}() {{ return {}; }}",
+                type_name,
+                to_pascal_case_or_unknown(&member_var.var_name, &mut ctx.iota)?,
                 member_var.var_name
             )?;
+            if options.immutable {
+                if options.withers {
+                    let args: Vec<String> = class
+                        .vars
+                        .iter()
+                        .map(|var| match var.var_name == member_var.var_name {
+                            true => "value".into(),
+                            false => match var.optional
+                                && options.optional_fields == OptionalFields::JavaOptional
+                            {
+                                true => format!("this.{}.orElse(null)", var.var_name),
+                                false => format!("this.{}", var.var_name),
+                            },
+                        })
+                        .collect();
+                    writeln!(
+                        out,
+                        "{i1}public {} with{}({nullable}{} value) {{ return new {}({}); }}",
+                        class.name,
+                        to_pascal_case_or_unknown(&member_var.var_name, &mut ctx.iota)?,
+                        member_var.type_name,
+                        class.name,
+                        args.join(", ")
+                    )?;
+                }
+                continue;
+            }
             if add_json_property {
-                writeln!(out, "    @JsonProperty(\"{}\")", member_var.original_name)?;
+                writeln!(
+                    out,
+                    "{i1}@JsonProperty({})",
+                    string_literal(&member_var.original_name)
+                )?;
+            }
+            match options.fluent_setters {
+                true => writeln!(
+                    out,
+                    "{i1}public {} set{}({nullable}{} value) {{ this.{} = value; return this; }}",
+                    class.name,
+                    to_pascal_case_or_unknown(&member_var.var_name, &mut ctx.iota)?,
+                    type_name,
+                    member_var.var_name
+                )?,
+                false => writeln!(
+                    out,
+                    "{i1}public void set{}({nullable}{} value) {{ this.{} = value; }}",
+                    to_pascal_case_or_unknown(&member_var.var_name, &mut ctx.iota)?,
+                    type_name,
+                    member_var.var_name
+                )?,
+            }
+        }
+
+        let vars: Vec<(&str, bool)> = class
+            .vars
+            .iter()
+            .map(|var| {
+                let wrapped =
+                    var.optional && options.optional_fields == OptionalFields::JavaOptional;
+                (
+                    var.var_name.as_str(),
+                    !wrapped && var.type_name.ends_with("[]"),
+                )
+            })
+            .collect();
+        value_methods(&mut out, &class.name, &vars, options)?;
+        if options.json_facade && class.name == root {
+            json_facade(&mut out, &root, options)?;
+        }
+
+        writeln!(out, "}}")?;
+    }
+
+    for union in ctx.unions {
+        let mut out = sink.sink(&format!("{}.java", union.name))?;
+        if let Some(package) = &options.package {
+            writeln!(out, "package {};", package)?;
+        }
+        match options.library {
+            JsonLibrary::Jackson => match options.unions {
+                UnionStyle::Wrapper => jackson_union(&mut out, &union, &keys, options)?,
+                UnionStyle::SealedInterface => {
+                    jackson_sealed_union(&mut out, &union, &keys, options)?
+                }
+            },
+            JsonLibrary::Gson => gson_union(&mut out, &union, options)?,
+        }
+    }
+
+    if let Some(superclass) = root_superclass {
+        let mut out = sink.sink(&format!("{root}.java"))?;
+        if let Some(package) = &options.package {
+            writeln!(out, "package {};", package)?;
+        }
+        strict_comment(&mut out, options)?;
+        if options.javadoc {
+            javadoc(&mut out, "", "Derived from JSON path $")?;
+        }
+        match options.json_facade {
+            true => {
+                writeln!(out, "public class {root} extends {superclass} {{")?;
+                json_facade(&mut out, &root, options)?;
+                writeln!(out, "}}")?;
+            }
+            false => writeln!(out, "public class {root} extends {superclass} {{}}")?,
+        }
+    }
+
+    if options.manifest {
+        let mut out = sink.sink("manifest.json")?;
+        serde_json::to_writer_pretty(&mut out, &manifest).map_err(Error::from)?;
+        writeln!(out)?;
+    }
+
+    Ok(root)
+}
+
+/// `fromJson` and `toJson` of the `root` class. see `JavaOptions::json_facade`
+fn json_facade(out: &mut dyn Write, root: &str, options: &JavaOptions) -> Result<(), Error> {
+    let (i1, i2) = (options.indent.level(1), options.indent.level(2));
+
+    match options.library {
+        JsonLibrary::Jackson => {
+            let mut config = vec![];
+            if options.optional_fields == OptionalFields::JavaOptional {
+                config
+                    .push(".registerModule(new com.fasterxml.jackson.datatype.jdk8.Jdk8Module())");
+            }
+            if options.string_formats {
+                config.push(
+                    ".registerModule(new com.fasterxml.jackson.datatype.jsr310.JavaTimeModule())",
+                );
+                config.push(
+                    ".disable(com.fasterxml.jackson.databind.SerializationFeature.WRITE_DATES_AS_TIMESTAMPS)",
+                );
             }
+            if options.floats == Floats::BigDecimal {
+                config.push(
+                    ".enable(com.fasterxml.jackson.databind.DeserializationFeature.USE_BIG_DECIMAL_FOR_FLOATS)",
+                );
+            }
+
+            write!(
+                out,
+                "{i1}private static final com.fasterxml.jackson.databind.ObjectMapper MAPPER = new com.fasterxml.jackson.databind.ObjectMapper()"
+            )?;
+            for line in config {
+                write!(out, "\n{i2}{line}")?;
+            }
+            writeln!(out, ";")?;
             writeln!(
                 out,
-                "    public void set{}({} value) {{ this.{} = value; }}",
-                to_pascal_case_or_unknown(&member_var.var_name, &mut ctx.iota),
-                member_var.type_name,
-                member_var.var_name
+                "{i1}public static {root} fromJson(String json) throws com.fasterxml.jackson.core.JsonProcessingException {{ return MAPPER.readValue(json, {root}.class); }}"
+            )?;
+            writeln!(
+                out,
+                "{i1}public static String toJson({root} value) throws com.fasterxml.jackson.core.JsonProcessingException {{ return MAPPER.writeValueAsString(value); }}"
+            )?;
+        }
+        JsonLibrary::Gson => {
+            writeln!(
+                out,
+                "{i1}private static final com.google.gson.Gson GSON = new com.google.gson.Gson();"
+            )?;
+            writeln!(
+                out,
+                "{i1}public static {root} fromJson(String json) {{ return GSON.fromJson(json, {root}.class); }}"
+            )?;
+            writeln!(
+                out,
+                "{i1}public static String toJson({root} value) {{ return GSON.toJson(value); }}"
             )?;
         }
+    }
 
-        writeln!(out, "}}")?;
+    Ok(())
+}
+
+/// `/** text */` or one ` * line` per line of a multi line `text`.
+fn javadoc(out: &mut dyn Write, indent: &str, text: &str) -> Result<(), Error> {
+    // javadoc is html, and the comment would end early at `*/`.
+    let text = text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace("*/", "*&#47;");
+    let lines: Vec<&str> = text.lines().collect();
+    match lines.as_slice() {
+        [line] => writeln!(out, "{indent}/** {line} */"),
+        _ => {
+            writeln!(out, "{indent}/**")?;
+            for line in lines {
+                writeln!(out, "{}", format!("{indent} * {line}").trim_end())?;
+            }
+            writeln!(out, "{indent} */")
+        }
     }
+}
 
-    for union in ctx.unions {
-        writeln!(out, "// {}.java", union.name)?;
-        writeln!(out, "import java.io.IOException;")?;
-        writeln!(out, "import com.fasterxml.jackson.core.*;")?;
-        writeln!(out, "import com.fasterxml.jackson.databind.*;")?;
-        writeln!(out, "import com.fasterxml.jackson.databind.annotation.*;")?;
+/// `"text"` with quotes, backslashes and control characters escaped.
+fn string_literal(text: &str) -> String {
+    let mut literal = String::with_capacity(text.len() + 2);
+    literal.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            '\u{8}' => literal.push_str("\\b"),
+            '\u{c}' => literal.push_str("\\f"),
+            // control characters are all in the basic multilingual plane.
+            c if c.is_control() => literal.push_str(&format!("\\u{:04x}", c as u32)),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+/// `long` for `Long` etc. other types are returned as they are.
+fn primitive(type_name: &str) -> &str {
+    match type_name {
+        "Long" => "long",
+        "Double" => "double",
+        "Boolean" => "boolean",
+        type_name => type_name,
+    }
+}
 
+/// a single time, in the file of the root class. see `JavaOptions::strict`
+fn strict_comment(out: &mut dyn Write, options: &JavaOptions) -> Result<(), Error> {
+    if options.library == JsonLibrary::Jackson && options.strict {
         writeln!(
             out,
-            "@JsonSerialize(using = {}.Serializer.class)",
-            union.name
+            "// strict: unknown properties are rejected while DeserializationFeature.FAIL_ON_UNKNOWN_PROPERTIES is enabled"
         )?;
+    }
+    Ok(())
+}
+
+/// custom serializer and deserializer registered with `@JsonSerialize` and `@JsonDeserialize`.
+/// `keys` are the json keys of every class. see `jackson_bind_node`
+fn jackson_union(
+    out: &mut dyn Write,
+    union: &Union,
+    keys: &HashMap<String, Vec<String>>,
+    options: &JavaOptions,
+) -> Result<(), Error> {
+    let (i1, i2, i3, i4) = (
+        options.indent.level(1),
+        options.indent.level(2),
+        options.indent.level(3),
+        options.indent.level(4),
+    );
+
+    writeln!(out, "import java.io.IOException;")?;
+    if options.generate_equals_hashcode {
+        writeln!(out, "import java.util.Objects;")?;
+    }
+    writeln!(out, "import com.fasterxml.jackson.core.*;")?;
+    writeln!(out, "import com.fasterxml.jackson.databind.*;")?;
+    writeln!(out, "import com.fasterxml.jackson.databind.annotation.*;")?;
+
+    if options.javadoc {
+        javadoc(out, "", &format!("Derived from JSON path {}", union.path))?;
+    }
+    writeln!(
+        out,
+        "@JsonSerialize(using = {}.Serializer.class)",
+        union.name
+    )?;
+    writeln!(
+        out,
+        "@JsonDeserialize(using = {}.Deserializer.class)",
+        union.name
+    )?;
+    writeln!(out, "public class {} {{", union.name)?;
+
+    for union_var in &union.vars {
         writeln!(
             out,
-            "@JsonDeserialize(using = {}.Deserializer.class)",
-            union.name
+            "{i1}public {} {};",
+            union_var.type_name, union_var.var_name
         )?;
-        writeln!(out, "public class {} {{", union.name)?;
-
-        for union_var in &union.vars {
-            writeln!(
-                out,
-                "    public {} {};",
-                union_var.type_name, union_var.var_name
-            )?;
-        }
+    }
 
-        // Serializer
+    // Serializer
+    writeln!(
+        out,
+        "{i1}static class Serializer extends JsonSerializer<{}> {{",
+        union.name
+    )?;
+    writeln!(out, "{i2}@Override public void serialize({} value, JsonGenerator generator, SerializerProvider serializer) throws IOException {{", union.name)?;
+    for union_var in &union.vars {
         writeln!(
             out,
-            "    static class Serializer extends JsonSerializer<{}> {{",
-            union.name
+            "{i3}if (value.{} != null) {{ generator.writeObject(value.{}); return; }}",
+            union_var.var_name, union_var.var_name
         )?;
-        writeln!(out, "        @Override public void serialize({} value, JsonGenerator generator, SerializerProvider serializer) throws IOException {{", union.name)?;
-        for union_var in &union.vars {
-            writeln!(
+    }
+    writeln!(out, "{i3}generator.writeNull();")?;
+    writeln!(out, "{i2}}}")?;
+    writeln!(out, "{i1}}}")?;
+
+    // Deserializer
+    writeln!(
+        out,
+        "{i1}static class Deserializer extends JsonDeserializer<{}> {{",
+        union.name
+    )?;
+    writeln!(out, "{i2}@Override public {} deserialize(JsonParser parser, DeserializationContext ctx) throws IOException {{", union.name)?;
+    writeln!(out, "{i3}{} value = new {}();", union.name, union.name)?;
+    writeln!(out, "{i3}switch (parser.currentToken()) {{")?;
+
+    writeln!(out, "{i3}case VALUE_NULL: break;")?;
+    for (token, union_vars) in union_cases(&union.vars) {
+        let case = token.jackson_case();
+        match union_vars.as_slice() {
+            [union_var] => writeln!(
                 out,
-                "            if (value.{} != null) {{ generator.writeObject(value.{}); return; }}",
-                union_var.var_name, union_var.var_name
-            )?;
+                "{i3}{case} value.{} = {}; break;",
+                union_var.var_name,
+                jackson_read(union_var)
+            )?,
+            // several variants start with the same token (eg: two different object shapes).
+            // buffer the value and bind the first variant that accepts it.
+            _ => {
+                writeln!(out, "{i3}{case} {{")?;
+                jackson_bind_node(out, &union_vars, keys, options, &|union_var| {
+                    format!(
+                        "value.{} = {}; break;",
+                        union_var.var_name,
+                        jackson_tree_to_value(union_var)
+                    )
+                })?;
+                writeln!(
+                    out,
+                    "{i4}throw new IOException(\"Cannot deserialize {}\");",
+                    union.name
+                )?;
+                writeln!(out, "{i3}}}")?;
+            }
         }
-        writeln!(out, "            generator.writeNull();")?;
-        writeln!(out, "        }}")?;
-        writeln!(out, "    }}")?;
+    }
+    writeln!(
+        out,
+        "{i3}default: throw new IOException(\"Cannot deserialize {}\");",
+        union.name
+    )?;
+    writeln!(out, "{i3}}}")?;
+    writeln!(out, "{i3}return value;")?;
+    writeln!(out, "{i2}}}")?;
+    writeln!(out, "{i1}}}")?;
 
-        // Deserializer
+    let vars: Vec<(&str, bool)> = union
+        .vars
+        .iter()
+        .map(|var| (var.var_name.as_str(), var.type_name.ends_with("[]")))
+        .collect();
+    value_methods(out, &union.name, &vars, options)?;
+
+    writeln!(out, "}}")?;
+
+    Ok(())
+}
+
+/// expression reading the current token of the jackson `parser` as the variant.
+fn jackson_read(union_var: &UnionMemberVar) -> String {
+    match union_var.type_name.as_str() {
+        "Double" => "parser.getDoubleValue()".into(),
+        "java.math.BigDecimal" => "parser.getDecimalValue()".into(),
+        // `List.class` and `Map.class` would lose the element type.
+        type_name if is_generic(type_name) => format!(
+            "parser.readValueAs(new com.fasterxml.jackson.core.type.TypeReference<{type_name}>() {{}})"
+        ),
+        type_name => format!("parser.readValueAs({type_name}.class)"),
+    }
+}
+
+/// reads the value into a `node` and runs the `bind` statement of the first of the `union_vars`
+/// that accepts it. classes ignore unknown keys unless `strict`, so that any of them would accept
+/// any object: they are only tried if they have every key of the node (by their json `keys`),
+/// or else at least one of them.
+fn jackson_bind_node(
+    out: &mut dyn Write,
+    union_vars: &[&UnionMemberVar],
+    keys: &HashMap<String, Vec<String>>,
+    options: &JavaOptions,
+    bind: &dyn Fn(&UnionMemberVar) -> String,
+) -> Result<(), Error> {
+    let i4 = options.indent.level(4);
+    let classes: Vec<(&UnionMemberVar, &Vec<String>)> = match options.strict {
+        true => vec![],
+        false => union_vars
+            .iter()
+            .filter_map(|var| Some((*var, keys.get(&var.type_name)?)))
+            .collect(),
+    };
+    let class_keys = |class_keys: &[String]| {
+        let class_keys: Vec<String> = class_keys.iter().map(|key| format!("{key:?}")).collect();
+        format!("java.util.Arrays.asList({})", class_keys.join(", "))
+    };
+
+    writeln!(out, "{i4}JsonNode node = parser.readValueAsTree();")?;
+    if !classes.is_empty() {
         writeln!(
             out,
-            "    static class Deserializer extends JsonDeserializer<{}> {{",
-            union.name
+            "{i4}java.util.List<String> keys = new java.util.ArrayList<>();"
         )?;
-        writeln!(out, "        @Override public {} deserialize(JsonParser parser, DeserializationContext ctx) throws IOException {{", union.name)?;
+        writeln!(out, "{i4}node.fieldNames().forEachRemaining(keys::add);")?;
+    }
+    for union_var in union_vars {
+        let condition = match classes
+            .iter()
+            .find(|(var, _)| var.var_name == union_var.var_name)
+        {
+            Some((_, keys)) => format!("if ({}.containsAll(keys)) ", class_keys(keys)),
+            None => String::new(),
+        };
         writeln!(
             out,
-            "            {} value = new {}();",
-            union.name, union.name
+            "{i4}{condition}try {{ {} }} catch (JsonProcessingException e) {{}}",
+            bind(union_var)
         )?;
-        writeln!(out, "            switch (parser.currentToken()) {{")?;
-
-        writeln!(out, "            case VALUE_NULL: break;")?;
-        for union_var in &union.vars {
-            match union_var.type_name.as_str() {
-                "String" => writeln!(out, "            case VALUE_STRING: value.{} = parser.readValueAs(String.class); break;", union_var.var_name)?,
-                "Long" => writeln!(out, "            case VALUE_NUMBER_INT: value.{} = parser.readValueAs(Long.class); break;", union_var.var_name)?,
-                "Double" => writeln!(out, "            case VALUE_NUMBER_FLOAT: value.{} = parser.readValueAs(Double.class); break;", union_var.var_name)?,
-                "Boolean" => writeln!(out, "            case VALUE_TRUE: case VALUE_FALSE: value.{} = parser.readValueAs(Boolean.class); break;", union_var.var_name)?,
-                _ if union_var.type_name.starts_with("List") => writeln!(out, "            case START_ARRAY: value.{} = parser.readValueAs({}.class); break;", union_var.var_name, union_var.type_name)?,
-                _ => writeln!(out, "            case START_OBJECT: value.{} = parser.readValueAs({}.class); break;", union_var.var_name, union_var.type_name)?,
-            };
-        }
+    }
+    // the json has keys that were added later.
+    for (union_var, keys) in &classes {
         writeln!(
             out,
-            "            default: throw new IOException(\"Cannot deserialize {}\");",
-            union.name
+            "{i4}if (!java.util.Collections.disjoint({}, keys)) try {{ {} }} catch (JsonProcessingException e) {{}}",
+            class_keys(keys),
+            bind(union_var)
         )?;
-        writeln!(out, "            }}")?;
-        writeln!(out, "            return value;")?;
-        writeln!(out, "        }}")?;
-        writeln!(out, "    }}")?;
-        writeln!(out, "}}")?;
     }
-
     Ok(())
 }
 
-struct Context {
-    classes: Vec<Class>,
-    unions: Vec<Union>,
-    iota: Iota,
+/// expression binding the buffered `node` to the variant.
+fn jackson_tree_to_value(union_var: &UnionMemberVar) -> String {
+    match is_generic(&union_var.type_name) {
+        // `treeToValue` only takes a class, which would lose the element type.
+        true => format!(
+            "parser.getCodec().readValue(parser.getCodec().treeAsTokens(node), new com.fasterxml.jackson.core.type.TypeReference<{}>() {{}})",
+            union_var.type_name
+        ),
+        false => format!(
+            "parser.getCodec().treeToValue(node, {}.class)",
+            union_var.type_name
+        ),
+    }
 }
 
-struct Class {
-    name: String,
-    vars: Vec<MemberVar>,
-}
+/// `UnionStyle::SealedInterface`. a json `null` is read as a `null` reference.
+fn jackson_sealed_union(
+    out: &mut dyn Write,
+    union: &Union,
+    keys: &HashMap<String, Vec<String>>,
+    options: &JavaOptions,
+) -> Result<(), Error> {
+    let (i1, i2, i3, i4) = (
+        options.indent.level(1),
+        options.indent.level(2),
+        options.indent.level(3),
+        options.indent.level(4),
+    );
+    let name = &union.name;
 
-struct MemberVar {
-    original_name: String,
-    var_name: String,
-    type_name: String,
-}
+    writeln!(out, "import java.io.IOException;")?;
+    writeln!(out, "import com.fasterxml.jackson.core.*;")?;
+    writeln!(out, "import com.fasterxml.jackson.databind.*;")?;
+    writeln!(out, "import com.fasterxml.jackson.databind.annotation.*;")?;
 
-struct Union {
-    name: String,
-    vars: Vec<UnionMemberVar>,
-}
+    if options.javadoc {
+        javadoc(out, "", &format!("Derived from JSON path {}", union.path))?;
+    }
+    writeln!(out, "@JsonSerialize(using = {name}.Serializer.class)")?;
+    writeln!(out, "@JsonDeserialize(using = {name}.Deserializer.class)")?;
+    // the records are in the same file, so the `permits` clause can be left out.
+    writeln!(out, "public sealed interface {name} {{")?;
+    writeln!(out, "{i1}Object value();")?;
+    writeln!(out, "{i1}<R> R accept(Visitor<R> visitor);")?;
 
-struct UnionMemberVar {
-    var_name: String,
-    type_name: String,
-}
+    for union_var in &union.vars {
+        let variant = sealed_variant_name(&union_var.var_name);
+        writeln!(
+            out,
+            "{i1}record {variant}Variant({} value) implements {name} {{",
+            union_var.type_name
+        )?;
+        writeln!(
+            out,
+            "{i2}@Override public <R> R accept(Visitor<R> visitor) {{ return visitor.visit{variant}(value); }}"
+        )?;
+        writeln!(out, "{i1}}}")?;
+    }
 
-impl Context {
-    fn new() -> Self {
-        Self {
-            classes: vec![],
-            unions: vec![],
-            iota: Iota::new(),
-        }
+    writeln!(out, "{i1}interface Visitor<R> {{")?;
+    for union_var in &union.vars {
+        writeln!(
+            out,
+            "{i2}R visit{}({} value);",
+            sealed_variant_name(&union_var.var_name),
+            union_var.type_name
+        )?;
     }
+    writeln!(out, "{i1}}}")?;
 
-    fn add_class(&mut self, name: String, fields: Vec<Field>) {
-        let mut class = Class {
-            name: name.clone(),
-            vars: vec![],
-        };
+    // Serializer
+    writeln!(
+        out,
+        "{i1}class Serializer extends JsonSerializer<{name}> {{"
+    )?;
+    writeln!(out, "{i2}@Override public void serialize({name} value, JsonGenerator generator, SerializerProvider serializer) throws IOException {{")?;
+    writeln!(out, "{i3}generator.writeObject(value.value());")?;
+    writeln!(out, "{i2}}}")?;
+    writeln!(out, "{i1}}}")?;
 
-        for field in fields {
-            class.vars.push(self.process_field(field));
+    // Deserializer
+    writeln!(
+        out,
+        "{i1}class Deserializer extends JsonDeserializer<{name}> {{"
+    )?;
+    writeln!(out, "{i2}@Override public {name} deserialize(JsonParser parser, DeserializationContext ctx) throws IOException {{")?;
+    writeln!(out, "{i3}switch (parser.currentToken()) {{")?;
+    writeln!(out, "{i3}case VALUE_NULL: return null;")?;
+    for (token, union_vars) in union_cases(&union.vars) {
+        let case = token.jackson_case();
+        match union_vars.as_slice() {
+            [union_var] => writeln!(
+                out,
+                "{i3}{case} return new {}Variant({});",
+                sealed_variant_name(&union_var.var_name),
+                jackson_read(union_var)
+            )?,
+            // several variants start with the same token.
+            // buffer the value and bind the first variant that accepts it.
+            _ => {
+                writeln!(out, "{i3}{case} {{")?;
+                jackson_bind_node(out, &union_vars, keys, options, &|union_var| {
+                    format!(
+                        "return new {}Variant({});",
+                        sealed_variant_name(&union_var.var_name),
+                        jackson_tree_to_value(union_var)
+                    )
+                })?;
+                writeln!(
+                    out,
+                    "{i4}throw new IOException(\"Cannot deserialize {name}\");"
+                )?;
+                writeln!(out, "{i3}}}")?;
+            }
         }
+    }
+    writeln!(
+        out,
+        "{i3}default: throw new IOException(\"Cannot deserialize {name}\");"
+    )?;
+    writeln!(out, "{i3}}}")?;
+    writeln!(out, "{i2}}}")?;
+    writeln!(out, "{i1}}}")?;
 
-        self.classes.push(class);
+    writeln!(out, "}}")?;
+
+    Ok(())
+}
+
+/// `strVal` -> `Str`, `itemClazz` -> `ItemClazz`.
+/// the records get a `Variant` suffix so they don't shadow the classes they wrap.
+fn sealed_variant_name(var_name: &str) -> String {
+    let name = var_name.strip_suffix("Val").unwrap_or(var_name);
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
     }
+}
 
-    fn add_union_class(&mut self, name: String, variants: Vec<FieldType>) {
-        let mut union = Union {
-            name: name.clone(),
-            vars: vec![],
-        };
+/// `TypeAdapter` registered with `@JsonAdapter`. gson has no support for untagged unions.
+/// the variants are (de)serialized with a default `Gson` instance.
+fn gson_union(out: &mut dyn Write, union: &Union, options: &JavaOptions) -> Result<(), Error> {
+    let (i1, i2, i3, i4) = (
+        options.indent.level(1),
+        options.indent.level(2),
+        options.indent.level(3),
+        options.indent.level(4),
+    );
 
-        for variant in variants {
-            union
-                .vars
-                .push(self.process_union_field(name.clone(), variant));
-        }
+    writeln!(out, "import java.io.IOException;")?;
+    if options.generate_equals_hashcode {
+        writeln!(out, "import java.util.Objects;")?;
+    }
+    writeln!(out, "import com.google.gson.*;")?;
+    writeln!(out, "import com.google.gson.annotations.*;")?;
+    writeln!(out, "import com.google.gson.reflect.*;")?;
+    writeln!(out, "import com.google.gson.stream.*;")?;
 
-        self.unions.push(union);
+    if options.javadoc {
+        javadoc(out, "", &format!("Derived from JSON path {}", union.path))?;
     }
+    writeln!(out, "@JsonAdapter({}.Adapter.class)", union.name)?;
+    writeln!(out, "public class {} {{", union.name)?;
 
-    fn process_field(&mut self, field: Field) -> MemberVar {
-        match field.ty {
+    for union_var in &union.vars {
+        writeln!(
+            out,
+            "{i1}public {} {};",
+            union_var.type_name, union_var.var_name
+        )?;
+    }
+
+    writeln!(
+        out,
+        "{i1}static class Adapter extends TypeAdapter<{}> {{",
+        union.name
+    )?;
+    writeln!(out, "{i2}private static final Gson GSON = new Gson();")?;
+
+    writeln!(
+        out,
+        "{i2}@Override public void write(JsonWriter writer, {} value) throws IOException {{",
+        union.name
+    )?;
+    for union_var in &union.vars {
+        writeln!(
+            out,
+            "{i3}if (value.{} != null) {{ GSON.toJson(value.{}, value.{}.getClass(), writer); return; }}",
+            union_var.var_name, union_var.var_name, union_var.var_name
+        )?;
+    }
+    writeln!(out, "{i3}writer.nullValue();")?;
+    writeln!(out, "{i2}}}")?;
+
+    let cases = union_cases(&union.vars);
+    writeln!(
+        out,
+        "{i2}@Override public {} read(JsonReader reader) throws IOException {{",
+        union.name
+    )?;
+    writeln!(
+        out,
+        "{i3}JsonElement json = JsonParser.parseReader(reader);"
+    )?;
+    writeln!(out, "{i3}{} value = new {}();", union.name, union.name)?;
+    for (token, union_vars) in &cases {
+        let condition = token.gson_condition();
+        match union_vars.as_slice() {
+            [union_var] if union_var.type_name == "Double" => writeln!(
+                out,
+                "{i3}if ({condition}) {{ value.{} = json.getAsDouble(); return value; }}",
+                union_var.var_name
+            )?,
+            [union_var] if union_var.type_name == "java.math.BigDecimal" => writeln!(
+                out,
+                "{i3}if ({condition}) {{ value.{} = json.getAsBigDecimal(); return value; }}",
+                union_var.var_name
+            )?,
+            [union_var] => writeln!(
+                out,
+                "{i3}if ({condition}) {{ value.{} = GSON.fromJson(json, {}); return value; }}",
+                union_var.var_name,
+                gson_type(&union_var.type_name)
+            )?,
+            // several variants start with the same token (eg: two different object shapes).
+            // bind the first variant that accepts it.
+            _ => {
+                writeln!(out, "{i3}if ({condition}) {{")?;
+                for union_var in union_vars {
+                    writeln!(
+                        out,
+                        "{i4}try {{ value.{} = GSON.fromJson(json, {}); return value; }} catch (JsonParseException e) {{}}",
+                        union_var.var_name,
+                        gson_type(&union_var.type_name)
+                    )?;
+                }
+                writeln!(out, "{i3}}}")?;
+            }
+        }
+    }
+    writeln!(
+        out,
+        "{i3}throw new JsonParseException(\"Cannot deserialize {}\");",
+        union.name
+    )?;
+    writeln!(out, "{i2}}}")?;
+
+    let integral = cases
+        .iter()
+        .any(|(token, _)| matches!(token, Token::Integer | Token::Float));
+    if integral {
+        writeln!(
+            out,
+            "{i2}private static boolean isIntegral(JsonElement json) {{"
+        )?;
+        writeln!(out, "{i3}String text = json.getAsString();")?;
+        writeln!(
+            out,
+            "{i3}return !(text.contains(\".\") || text.contains(\"e\") || text.contains(\"E\"));"
+        )?;
+        writeln!(out, "{i2}}}")?;
+    }
+    writeln!(out, "{i1}}}")?;
+
+    let vars: Vec<(&str, bool)> = union
+        .vars
+        .iter()
+        .map(|var| (var.var_name.as_str(), var.type_name.ends_with("[]")))
+        .collect();
+    value_methods(out, &union.name, &vars, options)?;
+
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+/// `Class` or `Type` argument for `Gson::fromJson`. `List.class` would lose the element type.
+fn gson_type(type_name: &str) -> String {
+    match is_generic(type_name) {
+        true => format!("new TypeToken<{}>() {{}}.getType()", type_name),
+        false => format!("{}.class", type_name),
+    }
+}
+
+/// `equals`, `hashCode` and `toString` as enabled in the options.
+/// fields are always accessed through `this.` so that they can't be shadowed by the locals.
+/// `vars` are the field names, and whether the field is a java array (`Collections::Arrays`).
+/// arrays don't override `equals`, `hashCode` and `toString`, so they go through `java.util.Arrays`.
+fn value_methods(
+    out: &mut dyn Write,
+    class_name: &str,
+    vars: &[(&str, bool)],
+    options: &JavaOptions,
+) -> Result<(), Error> {
+    let (i1, i2) = (options.indent.level(1), options.indent.level(2));
+
+    if options.generate_equals_hashcode {
+        writeln!(out, "{i1}@Override public boolean equals(Object o) {{")?;
+        writeln!(out, "{i2}if (this == o) return true;")?;
+        writeln!(
+            out,
+            "{i2}if (o == null || getClass() != o.getClass()) return false;"
+        )?;
+        match vars.is_empty() {
+            true => writeln!(out, "{i2}return true;")?,
+            false => {
+                writeln!(out, "{i2}{class_name} that = ({class_name}) o;")?;
+                let comparisons: Vec<String> = vars
+                    .iter()
+                    .map(|(var, array)| match array {
+                        true => format!("java.util.Arrays.deepEquals(this.{var}, that.{var})"),
+                        false => format!("Objects.equals(this.{var}, that.{var})"),
+                    })
+                    .collect();
+                writeln!(out, "{i2}return {};", comparisons.join(" && "))?;
+            }
+        }
+        writeln!(out, "{i1}}}")?;
+
+        let hashed: Vec<String> = vars
+            .iter()
+            .map(|(var, array)| match array {
+                true => format!("java.util.Arrays.deepHashCode(this.{var})"),
+                false => format!("this.{var}"),
+            })
+            .collect();
+        writeln!(
+            out,
+            "{i1}@Override public int hashCode() {{ return Objects.hash({}); }}",
+            hashed.join(", ")
+        )?;
+    }
+
+    if options.generate_to_string {
+        writeln!(out, "{i1}@Override public String toString() {{")?;
+        writeln!(
+            out,
+            "{i2}StringBuilder sb = new StringBuilder(\"{class_name}{{\");"
+        )?;
+        for (idx, (var, array)) in vars.iter().enumerate() {
+            let separator = match idx {
+                0 => "",
+                _ => ", ",
+            };
+            let value = match array {
+                true => format!("java.util.Arrays.deepToString(this.{var})"),
+                false => format!("this.{var}"),
+            };
+            writeln!(out, "{i2}sb.append(\"{separator}{var}=\").append({value});")?;
+        }
+        writeln!(out, "{i2}return sb.append(\"}}\").toString();")?;
+        writeln!(out, "{i1}}}")?;
+    }
+
+    Ok(())
+}
+
+/// `java.util.List<T>` or `java.util.Map<String, T>`, whose type arguments are erased in `.class`
+fn is_generic(type_name: &str) -> bool {
+    type_name.starts_with("java.util.List<") || type_name.starts_with("java.util.Map<")
+}
+
+/// the types of all the positions of a tuple merged into one.
+fn tuple_element(tys: Vec<FieldType>) -> FieldType {
+    let mut agg = FieldTypeAggregator::new();
+    for ty in tys {
+        agg.add(ty);
+    }
+    agg.finalize()
+}
+
+fn format_type(format: StringFormat) -> &'static str {
+    match format {
+        StringFormat::DateTime => "java.time.Instant",
+        StringFormat::Date => "java.time.LocalDate",
+        StringFormat::Uuid => "java.util.UUID",
+        StringFormat::Base64 => "byte[]",
+    }
+}
+
+/// type of the innermost elements of a list, array or map type. eg: `java.util.List<Item[]>` -> `Item`
+fn element_type(type_name: &str) -> &str {
+    if let Some(ty) = type_name.strip_suffix("[]") {
+        return element_type(ty);
+    }
+    match type_name
+        .strip_prefix("java.util.List<")
+        .or_else(|| type_name.strip_prefix("java.util.Map<String, "))
+        .and_then(|ty| ty.strip_suffix('>'))
+    {
+        Some(ty) => element_type(ty),
+        None => type_name,
+    }
+}
+
+fn is_collection(type_name: &str) -> bool {
+    type_name.starts_with("java.util.List<") || type_name.ends_with("[]")
+}
+
+/// kind of json value a union variant is read from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token {
+    String,
+    Integer,
+    /// integer or floating point
+    Number,
+    Float,
+    Boolean,
+    Array,
+    Object,
+}
+
+impl Token {
+    fn jackson_case(self) -> &'static str {
+        match self {
+            Token::String => "case VALUE_STRING:",
+            Token::Integer => "case VALUE_NUMBER_INT:",
+            Token::Number => "case VALUE_NUMBER_INT: case VALUE_NUMBER_FLOAT:",
+            Token::Float => "case VALUE_NUMBER_FLOAT:",
+            Token::Boolean => "case VALUE_TRUE: case VALUE_FALSE:",
+            Token::Array => "case START_ARRAY:",
+            Token::Object => "case START_OBJECT:",
+        }
+    }
+
+    /// condition on the `JsonElement json`.
+    fn gson_condition(self) -> &'static str {
+        match self {
+            Token::String => "json.isJsonPrimitive() && json.getAsJsonPrimitive().isString()",
+            Token::Integer => {
+                "json.isJsonPrimitive() && json.getAsJsonPrimitive().isNumber() && isIntegral(json)"
+            }
+            Token::Number => "json.isJsonPrimitive() && json.getAsJsonPrimitive().isNumber()",
+            Token::Float => {
+                "json.isJsonPrimitive() && json.getAsJsonPrimitive().isNumber() && !isIntegral(json)"
+            }
+            Token::Boolean => "json.isJsonPrimitive() && json.getAsJsonPrimitive().isBoolean()",
+            Token::Array => "json.isJsonArray()",
+            Token::Object => "json.isJsonObject()",
+        }
+    }
+}
+
+/// reserved words that can't be used as variable names.
+const KEYWORDS: &[&str] = &[
+    "_",
+    "abstract",
+    "assert",
+    "boolean",
+    "break",
+    "byte",
+    "case",
+    "catch",
+    "char",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extends",
+    "false",
+    "final",
+    "finally",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "implements",
+    "import",
+    "instanceof",
+    "int",
+    "interface",
+    "long",
+    "native",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "short",
+    "static",
+    "strictfp",
+    "super",
+    "switch",
+    "synchronized",
+    "this",
+    "throw",
+    "throws",
+    "transient",
+    "true",
+    "try",
+    "void",
+    "volatile",
+    "while",
+];
+
+/// `java.lang` classes that the generated code refers to by their simple name.
+/// a generated class with the same name would shadow them.
+const JAVA_LANG: &[&str] = &[
+    "Boolean",
+    "Class",
+    "Double",
+    "Integer",
+    "Long",
+    "Number",
+    "Object",
+    "Override",
+    "String",
+    "StringBuilder",
+];
+
+/// classes nested in the generated unions. a variant class with the same name
+/// would be shadowed by them inside the union.
+const NESTED_CLASSES: &[&str] = &["Adapter", "Deserializer", "Serializer", "Visitor"];
+
+/// union variants grouped by the json token they start with, in order of first appearance.
+fn union_cases(vars: &[UnionMemberVar]) -> Vec<(Token, Vec<&UnionMemberVar>)> {
+    let mut cases: Vec<(Token, Vec<&UnionMemberVar>)> = vec![];
+
+    // without an integer variant, integer tokens are read into the floating point variant.
+    let has_integer = vars
+        .iter()
+        .any(|var| matches!(var.type_name.as_str(), "Long" | "java.math.BigInteger"));
+
+    for union_var in vars {
+        let token = match union_var.type_name.as_str() {
+            "String"
+            | "java.time.Instant"
+            | "java.time.LocalDate"
+            | "java.util.UUID"
+            | "byte[]" => Token::String,
+            "Long" | "java.math.BigInteger" => Token::Integer,
+            "Double" | "java.math.BigDecimal" if !has_integer => Token::Number,
+            "Double" | "java.math.BigDecimal" => Token::Float,
+            "Boolean" => Token::Boolean,
+            _ if is_collection(&union_var.type_name) => Token::Array,
+            _ => Token::Object,
+        };
+
+        match cases.iter_mut().find(|(t, _)| *t == token) {
+            Some((_, union_vars)) => union_vars.push(union_var),
+            None => cases.push((token, vec![union_var])),
+        }
+    }
+
+    // a map accepts any object, so the other object variants are tried first.
+    for (_, union_vars) in &mut cases {
+        union_vars.sort_by_key(|var| var.type_name.starts_with("java.util.Map<"));
+    }
+
+    cases
+}
+
+struct Context {
+    classes: Vec<Class>,
+    unions: Vec<Union>,
+    iota: Iota,
+    shared_objects: Option<SharedObjects>,
+    definitions: Definitions,
+    class_names: Vec<String>,
+    collections: Collections,
+    floats: Floats,
+    string_formats: bool,
+    /// `byte[]` for base64 strings. gson would write them as arrays of numbers.
+    base64: bool,
+    null_field_policy: NullFieldPolicy,
+    /// json path of the value being processed. eg: `$.cart[].metadata`
+    path: String,
+}
+
+struct Class {
+    name: String,
+    /// json path of the (first) object the class is generated for.
+    path: String,
+    vars: Vec<MemberVar>,
+    /// json keys of the fields left out. see `NullFieldPolicy::Skip`
+    skipped: Vec<String>,
+}
+
+struct MemberVar {
+    original_name: String,
+    var_name: String,
+    type_name: String,
+    /// the json key is missing or `null` in some of the values.
+    optional: bool,
+    /// java literal of the value the field has in every sample.
+    constant: Option<String>,
+    description: Option<String>,
+    example: Option<Value>,
+}
+
+struct Union {
+    name: String,
+    path: String,
+    vars: Vec<UnionMemberVar>,
+}
+
+struct UnionMemberVar {
+    var_name: String,
+    type_name: String,
+}
+
+impl Context {
+    fn new(
+        shared_objects: Option<SharedObjects>,
+        definitions: Definitions,
+        collections: Collections,
+        floats: Floats,
+        string_formats: bool,
+        base64: bool,
+        null_field_policy: NullFieldPolicy,
+    ) -> Self {
+        Self {
+            classes: vec![],
+            unions: vec![],
+            iota: Iota::new(),
+            shared_objects,
+            definitions,
+            class_names: JAVA_LANG
+                .iter()
+                .chain(NESTED_CLASSES)
+                .copied()
+                .map(String::from)
+                .collect(),
+            collections,
+            floats,
+            string_formats,
+            base64,
+            null_field_policy,
+            path: "$".into(),
+        }
+    }
+
+    fn add_class(&mut self, name: String, fields: Vec<Field>) -> Result<(), CodegenError> {
+        let mut class = Class {
+            name: name.clone(),
+            path: self.path.clone(),
+            vars: vec![],
+            skipped: vec![],
+        };
+
+        for field in fields {
+            if self.null_field_policy == NullFieldPolicy::Skip && field.ty == FieldType::Unknown {
+                class.skipped.push(field.name);
+                continue;
+            }
+            let (description, example) = (field.description.clone(), field.example.clone());
+            let len = self.path.len();
+            self.path.push_str(&path_segment(&field.name));
+            let mut member_var = self.process_field(field)?;
+            self.path.truncate(len);
+            member_var.description = description;
+            member_var.example = example;
+            member_var.var_name = self.unique_var_name(&class.vars, member_var.var_name)?;
+            class.vars.push(member_var);
+        }
+
+        self.classes.push(class);
+        Ok(())
+    }
+
+    /// name of the class generated for the nested object.
+    fn add_object_class(&mut self, name: &str, fields: Vec<Field>) -> Result<String, CodegenError> {
+        if self.definitions.defines(name, &fields) {
+            return self.add_definition_class(name, fields);
+        }
+
+        let Some(shared_objects) = &self.shared_objects else {
+            let class_name = self.class_name(name)?;
+            self.add_class(class_name.clone(), fields)?;
+            return Ok(class_name);
+        };
+
+        if let Some(class_name) = shared_objects.generated(&fields) {
+            return Ok(class_name);
+        }
+
+        let class_name = self.class_name(&shared_objects.name(&fields, name))?;
+        if let Some(shared_objects) = &mut self.shared_objects {
+            shared_objects.insert(fields.clone(), class_name.clone());
+        }
+        self.add_class(class_name.clone(), fields)?;
+        Ok(class_name)
+    }
+
+    /// class of a top level object that `FieldType::Ref`s refer to, named after it.
+    /// the name is known before the fields are processed, so that they can refer to the class itself.
+    fn add_definition_class(
+        &mut self,
+        name: &str,
+        fields: Vec<Field>,
+    ) -> Result<String, CodegenError> {
+        if let Some(class_name) = self.definitions.generated(name) {
+            return Ok(class_name);
+        }
+        if let Some(class_name) = self
+            .shared_objects
+            .as_ref()
+            .and_then(|shared_objects| shared_objects.generated(&fields))
+        {
+            self.definitions.insert(name, class_name.clone());
+            return Ok(class_name);
+        }
+
+        let class_name = self.class_name(name)?;
+        self.definitions.insert(name, class_name.clone());
+        if let Some(shared_objects) = &mut self.shared_objects {
+            shared_objects.insert(fields.clone(), class_name.clone());
+        }
+        let path = std::mem::replace(&mut self.path, format!("${}", path_segment(name)));
+        self.definitions.enter(name);
+        self.add_class(class_name.clone(), fields)?;
+        self.definitions.exit();
+        self.path = path;
+        Ok(class_name)
+    }
+
+    /// name of the type generated for the top level field `name`. see `FieldType::Ref`
+    fn ref_type_name(&mut self, name: &str) -> Result<String, CodegenError> {
+        if let Some(type_name) = self.definitions.generated(name) {
+            return Ok(type_name);
+        }
+        Ok(match self.definitions.get(name).cloned() {
+            Some(FieldType::Object(fields)) => self.add_definition_class(name, fields)?,
+            // other types (eg: a union) are generated like a field named after the definition.
+            Some(ty) if !self.definitions.resolving(name) => {
+                let path = std::mem::replace(&mut self.path, format!("${}", path_segment(name)));
+                self.definitions.enter(name);
+                let member_var = self.process_field(Field {
+                    name: name.into(),
+                    ty,
+                    description: None,
+                    example: None,
+                })?;
+                self.definitions.exit();
+                self.path = path;
+                self.definitions.insert(name, member_var.type_name.clone());
+                member_var.type_name
+            }
+            // no such definition, or one that is only made of itself. eg: `{Tree:[&Tree]}`
+            _ => "Object".into(),
+        })
+    }
+
+    /// PascalCase class name that isn't used by any other generated class.
+    /// the same name in different places (eg: "address" in two objects) gets a numeric suffix.
+    fn class_name(&mut self, text: &str) -> Result<String, CodegenError> {
+        let name = to_pascal_case_or_unknown(text, &mut self.iota)?;
+
+        // the names are compared ignoring case because `Root.java` and `ROOT.java`
+        // are the same file on case insensitive file systems.
+        let mut class_name = name.clone();
+        let mut n = 2;
+        while self
+            .class_names
+            .iter()
+            .any(|existing| existing.eq_ignore_ascii_case(&class_name))
+        {
+            class_name = format!("{}{}", name, n);
+            n += 1;
+        }
+
+        self.class_names.push(class_name.clone());
+        Ok(class_name)
+    }
+
+    /// camelCase variable name. keywords get a trailing underscore (eg: "class_").
+    fn var_name(&mut self, text: &str) -> Result<String, CodegenError> {
+        let name = to_camel_case_or_unknown(text, &mut self.iota)?;
+        match KEYWORDS.contains(&name.as_str()) {
+            true => Ok(format!("{}_", name)),
+            false => Ok(name),
+        }
+    }
+
+    /// different json keys can end up with the same variable name (eg: "user_name" and "userName")
+    /// or the same getter/setter name (eg: "a" and "A").
+    /// colliding names get a numeric suffix (eg: "userName2").
+    /// `@JsonProperty` is always emitted for them because the name no longer matches the key.
+    /// `getClass` is also taken because it is final in `Object`.
+    fn unique_var_name(
+        &mut self,
+        vars: &[MemberVar],
+        var_name: String,
+    ) -> Result<String, CodegenError> {
+        let mut taken = |name: &str| -> Result<bool, CodegenError> {
+            let accessor = to_pascal_case_or_unknown(name, &mut self.iota)?;
+            if accessor == "Class" {
+                return Ok(true);
+            }
+            for var in vars {
+                if var.var_name == name
+                    || to_pascal_case_or_unknown(&var.var_name, &mut self.iota)? == accessor
+                {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        };
+
+        if !taken(&var_name)? {
+            return Ok(var_name);
+        }
+
+        let mut n = 2;
+        while taken(&format!("{}{}", var_name, n))? {
+            n += 1;
+        }
+        Ok(format!("{}{}", var_name, n))
+    }
+
+    fn add_union_class(
+        &mut self,
+        name: String,
+        variants: Vec<FieldType>,
+    ) -> Result<(), CodegenError> {
+        let mut union = Union {
+            name: name.clone(),
+            path: self.path.clone(),
+            vars: vec![],
+        };
+
+        for variant in variants {
+            let mut union_var = self.process_union_field(name.clone(), variant)?;
+            let var_name = union_var.var_name.clone();
+            let mut n = 2;
+            while union
+                .vars
+                .iter()
+                .any(|var| var.var_name == union_var.var_name)
+            {
+                union_var.var_name = format!("{}{}", var_name, n);
+                n += 1;
+            }
+            union.vars.push(union_var);
+        }
+
+        self.unions.push(union);
+        Ok(())
+    }
+
+    fn process_field(&mut self, field: Field) -> Result<MemberVar, CodegenError> {
+        Ok(match field.ty {
             FieldType::String => MemberVar {
-                var_name: to_camel_case_or_unknown(&field.name, &mut self.iota),
+                var_name: self.var_name(&field.name)?,
                 original_name: field.name,
                 type_name: "String".into(),
+                optional: false,
+                constant: None,
+                description: None,
+                example: None,
             },
             FieldType::Integer => MemberVar {
-                var_name: to_camel_case_or_unknown(&field.name, &mut self.iota),
+                var_name: self.var_name(&field.name)?,
                 original_name: field.name,
                 type_name: "Long".into(),
+                optional: false,
+                constant: None,
+                description: None,
+                example: None,
+            },
+            FieldType::BigInteger => MemberVar {
+                var_name: self.var_name(&field.name)?,
+                original_name: field.name,
+                type_name: "java.math.BigInteger".into(),
+                optional: false,
+                constant: None,
+                description: None,
+                example: None,
             },
             FieldType::Float => MemberVar {
-                var_name: to_camel_case_or_unknown(&field.name, &mut self.iota),
+                var_name: self.var_name(&field.name)?,
                 original_name: field.name,
-                type_name: "Double".into(),
+                type_name: match self.floats {
+                    Floats::Double => "Double".into(),
+                    Floats::BigDecimal => "java.math.BigDecimal".into(),
+                },
+                optional: false,
+                constant: None,
+                description: None,
+                example: None,
             },
             FieldType::Boolean => MemberVar {
-                var_name: to_camel_case_or_unknown(&field.name, &mut self.iota),
+                var_name: self.var_name(&field.name)?,
                 original_name: field.name,
                 type_name: "Boolean".into(),
+                optional: false,
+                constant: None,
+                description: None,
+                example: None,
             },
             FieldType::Unknown => MemberVar {
-                var_name: to_camel_case_or_unknown(&field.name, &mut self.iota),
+                var_name: self.var_name(&field.name)?,
                 original_name: field.name,
                 type_name: "Object".into(),
+                optional: false,
+                constant: None,
+                description: None,
+                example: None,
             },
             FieldType::Object(nested_fields) => {
-                let nested_class_name = to_pascal_case_or_unknown(&field.name, &mut self.iota);
-                self.add_class(nested_class_name.clone(), nested_fields);
+                let nested_class_name = self.add_object_class(&field.name, nested_fields)?;
                 MemberVar {
-                    var_name: to_camel_case_or_unknown(&field.name, &mut self.iota),
+                    var_name: self.var_name(&field.name)?,
                     original_name: field.name,
                     type_name: nested_class_name,
+                    optional: false,
+                    constant: None,
+                    description: None,
+                    example: None,
                 }
             }
             FieldType::Union(types) => {
-                let nested_class_name = to_pascal_case_or_unknown(&field.name, &mut self.iota);
-                self.add_union_class(nested_class_name.clone(), types);
+                let nested_class_name = self.class_name(&field.name)?;
+                self.add_union_class(nested_class_name.clone(), types)?;
                 MemberVar {
-                    var_name: to_camel_case_or_unknown(&field.name, &mut self.iota),
+                    var_name: self.var_name(&field.name)?,
                     original_name: field.name,
                     type_name: nested_class_name,
+                    optional: false,
+                    constant: None,
+                    description: None,
+                    example: None,
                 }
             }
             FieldType::Array(ty) => {
+                self.path.push_str("[]");
+                let mut member_var = self.process_field(Field {
+                    name: singular(&field.name),
+                    ty: *ty,
+                    description: None,
+                    example: None,
+                })?;
+                self.path.truncate(self.path.len() - "[]".len());
+                member_var.var_name = self.var_name(&field.name)?;
+                member_var.original_name = field.name;
+                member_var.type_name = match self.collections {
+                    Collections::List => format!("java.util.List<{}>", member_var.type_name),
+                    Collections::Arrays => format!("{}[]", member_var.type_name),
+                };
+                member_var.optional = false;
+                member_var.constant = None;
+                member_var
+            }
+            FieldType::Map(ty) => {
+                self.path.push_str(".*");
+                let mut member_var = self.process_field(Field {
+                    name: singular(&field.name),
+                    ty: *ty,
+                    description: None,
+                    example: None,
+                })?;
+                self.path.truncate(self.path.len() - ".*".len());
+                member_var.var_name = self.var_name(&field.name)?;
+                member_var.original_name = field.name;
+                member_var.type_name = format!("java.util.Map<String, {}>", member_var.type_name);
+                member_var.optional = false;
+                member_var.constant = None;
+                member_var
+            }
+            FieldType::Optional(ty) => {
                 let mut member_var = self.process_field(Field {
                     name: field.name,
                     ty: *ty,
+                    description: None,
+                    example: None,
+                })?;
+                member_var.optional = true;
+                member_var
+            }
+            // no fields to generate a type from. any keys go into a map.
+            FieldType::EmptyObject => self.process_field(Field {
+                ty: FieldType::Map(Box::new(FieldType::Unknown)),
+                ..field
+            })?,
+            FieldType::Format(StringFormat::Base64) if !self.base64 => {
+                self.process_field(Field {
+                    ty: FieldType::String,
+                    ..field
+                })?
+            }
+            FieldType::Format(format) if self.string_formats => MemberVar {
+                var_name: self.var_name(&field.name)?,
+                original_name: field.name,
+                type_name: format_type(format).into(),
+                optional: false,
+                constant: None,
+                description: None,
+                example: None,
+            },
+            FieldType::Format(_) => self.process_field(Field {
+                ty: FieldType::String,
+                ..field
+            })?,
+            // java has no tuples. the positions are merged into the element type of a plain array.
+            FieldType::Tuple(tys) => self.process_field(Field {
+                ty: FieldType::Array(Box::new(tuple_element(tys))),
+                ..field
+            })?,
+            FieldType::Ref(name) => MemberVar {
+                type_name: self.ref_type_name(&name)?,
+                var_name: self.var_name(&field.name)?,
+                original_name: field.name,
+                optional: false,
+                constant: None,
+                description: None,
+                example: None,
+            },
+            FieldType::Constant(ty, value) => {
+                let mut member_var = self.process_field(Field {
+                    name: field.name,
+                    ty: *ty,
+                    description: None,
+                    example: None,
+                })?;
+                member_var.constant = Some(match value {
+                    Value::Number(n) if n.is_i64() => format!("{n}L"),
+                    Value::Number(n) if !n.is_f64() => format!("new java.math.BigInteger(\"{n}\")"),
+                    Value::Number(n) if self.floats == Floats::BigDecimal => {
+                        format!("new java.math.BigDecimal(\"{n}\")")
+                    }
+                    Value::Number(n) => format!("{:?}", n.as_f64().unwrap_or_default()),
+                    Value::String(text) => string_literal(&text),
+                    value => value.to_string(),
                 });
-                member_var.type_name = format!("List<{}>", member_var.type_name);
                 member_var
             }
-            FieldType::Optional(ty) => self.process_field(Field {
-                name: field.name,
-                ty: *ty,
-            }),
-        }
+        })
     }
 
-    fn process_union_field(&mut self, prefix: String, variant: FieldType) -> UnionMemberVar {
-        match variant {
+    fn process_union_field(
+        &mut self,
+        prefix: String,
+        variant: FieldType,
+    ) -> Result<UnionMemberVar, CodegenError> {
+        Ok(match variant {
             FieldType::String => UnionMemberVar {
                 var_name: "strVal".into(),
                 type_name: "String".into(),
@@ -274,9 +2025,19 @@ impl Context {
                 var_name: "longVal".into(),
                 type_name: "Long".into(),
             },
-            FieldType::Float => UnionMemberVar {
-                var_name: "doubleVal".into(),
-                type_name: "Double".into(),
+            FieldType::BigInteger => UnionMemberVar {
+                var_name: "bigIntVal".into(),
+                type_name: "java.math.BigInteger".into(),
+            },
+            FieldType::Float => match self.floats {
+                Floats::Double => UnionMemberVar {
+                    var_name: "doubleVal".into(),
+                    type_name: "Double".into(),
+                },
+                Floats::BigDecimal => UnionMemberVar {
+                    var_name: "decimalVal".into(),
+                    type_name: "java.math.BigDecimal".into(),
+                },
             },
             FieldType::Boolean => UnionMemberVar {
                 var_name: "boolVal".into(),
@@ -290,7 +2051,9 @@ impl Context {
                 let member_var = self.process_field(Field {
                     name: prefix + "Clazz",
                     ty: FieldType::Object(fields),
-                });
+                    description: None,
+                    example: None,
+                })?;
 
                 UnionMemberVar {
                     var_name: member_var.var_name,
@@ -301,7 +2064,9 @@ impl Context {
                 let member_var = self.process_field(Field {
                     name: prefix + "Ele",
                     ty: FieldType::Union(types),
-                });
+                    description: None,
+                    example: None,
+                })?;
 
                 UnionMemberVar {
                     var_name: member_var.var_name,
@@ -312,7 +2077,9 @@ impl Context {
                 let member_var = self.process_field(Field {
                     name: prefix + "Arr",
                     ty: FieldType::Array(ty),
-                });
+                    description: None,
+                    example: None,
+                })?;
 
                 UnionMemberVar {
                     var_name: member_var.var_name,
@@ -323,13 +2090,1498 @@ impl Context {
                 let member_var = self.process_field(Field {
                     name: prefix + "Opt",
                     ty: FieldType::Optional(ty),
-                });
+                    description: None,
+                    example: None,
+                })?;
 
                 UnionMemberVar {
                     var_name: member_var.var_name,
                     type_name: member_var.type_name,
                 }
             }
+            FieldType::Map(ty) => {
+                let member_var = self.process_field(Field {
+                    name: prefix + "Map",
+                    ty: FieldType::Map(ty),
+                    description: None,
+                    example: None,
+                })?;
+
+                UnionMemberVar {
+                    var_name: member_var.var_name,
+                    type_name: member_var.type_name,
+                }
+            }
+            FieldType::EmptyObject => {
+                self.process_union_field(prefix, FieldType::Map(Box::new(FieldType::Unknown)))?
+            }
+            FieldType::Format(StringFormat::Base64) if !self.base64 => {
+                self.process_union_field(prefix, FieldType::String)?
+            }
+            FieldType::Format(format) if self.string_formats => UnionMemberVar {
+                var_name: match format {
+                    StringFormat::DateTime => "instantVal",
+                    StringFormat::Date => "dateVal",
+                    StringFormat::Uuid => "uuidVal",
+                    StringFormat::Base64 => "bytesVal",
+                }
+                .into(),
+                type_name: format_type(format).into(),
+            },
+            FieldType::Format(_) => self.process_union_field(prefix, FieldType::String)?,
+            FieldType::Tuple(tys) => {
+                self.process_union_field(prefix, FieldType::Array(Box::new(tuple_element(tys))))?
+            }
+            FieldType::Constant(ty, _) => self.process_union_field(prefix, *ty)?,
+            FieldType::Ref(name) => UnionMemberVar {
+                type_name: self.ref_type_name(&name)?,
+                var_name: self.var_name(&name)?,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn codegen(json: &str, options: &JavaOptions) -> String {
+        let schema = crate::schema::extract(serde_json::from_str(json).unwrap());
+        let mut out = vec![];
+        java(schema, options, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn package() {
+        let options = JavaOptions {
+            package: Some("com.example".into()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            codegen(r#"{"id": 1}"#, &options),
+            r#"// Root.java
+package com.example;
+import com.fasterxml.jackson.annotation.*;
+@JsonIgnoreProperties(ignoreUnknown = true)
+public class Root {
+    private Long id;
+    public Long getId() { return id; }
+    public void setId(Long value) { this.id = value; }
+}
+"#
+        );
+
+        // every file, including unions and the root list subclass.
+        let code = codegen(r#"[{"a": {"b": 1}, "c": [1, "x"]}]"#, &options);
+        let files: Vec<&str> = code.split("// ").skip(1).collect();
+        assert_eq!(files.len(), 4);
+        for file in files {
+            assert_eq!(file.lines().nth(1), Some("package com.example;"), "{file}");
+        }
+    }
+
+    #[test]
+    fn directory_sink() {
+        let dir =
+            std::env::temp_dir().join(format!("jsoncodegen-java-files-{}", std::process::id()));
+        let options = JavaOptions {
+            package: Some("com.example".into()),
+            ..Default::default()
+        };
+        let schema = crate::schema::extract(
+            serde_json::from_str(r#"{"address": {"city": "x"}, "ids": [1, "a"]}"#).unwrap(),
+        );
+        java(
+            schema,
+            &options,
+            &mut crate::codegen::DirectorySink::new(&dir),
+        )
+        .unwrap();
+
+        let mut files: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        assert_eq!(files, ["Address.java", "Id.java", "Root.java"]);
+        for file in &files {
+            let code = std::fs::read_to_string(dir.join(file)).unwrap();
+            assert!(code.starts_with("package com.example;\nimport "), "{file}");
+            let class = file.trim_end_matches(".java");
+            assert!(code.contains(&format!("public class {class} ")), "{file}");
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn strict() {
+        let json = r#"{"id": 1}"#;
+
+        let code = codegen(
+            json,
+            &JavaOptions {
+                strict: true,
+                ..Default::default()
+            },
+        );
+        assert!(!code.contains("@JsonIgnoreProperties"));
+        assert!(code.contains(
+            "import com.fasterxml.jackson.annotation.*;\n// strict: unknown properties are rejected"
+        ));
+
+        // once, in the file of the root class.
+        let code = codegen(
+            r#"{"a": {"b": 1}, "c": [1, "x"]}"#,
+            &JavaOptions {
+                strict: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(code.matches("FAIL_ON_UNKNOWN_PROPERTIES").count(), 1);
+        let (_, root) = code.split_once("// Root.java\n").unwrap();
+        // up to the `// {filename}.java` of the next file
+        let root = root.split(".java\n").next().unwrap();
+        assert!(root.contains("FAIL_ON_UNKNOWN_PROPERTIES"));
+        let code = codegen(
+            "[1]",
+            &JavaOptions {
+                strict: true,
+                ..Default::default()
+            },
+        );
+        assert!(code.contains("// Root.java\n// strict: unknown properties are rejected"));
+
+        // gson ignores unknown fields without any annotation.
+        let code = codegen(
+            json,
+            &JavaOptions {
+                library: JsonLibrary::Gson,
+                ..Default::default()
+            },
+        );
+        assert!(!code.contains("@JsonIgnoreProperties"));
+        assert!(!code.contains("FAIL_ON_UNKNOWN_PROPERTIES"));
+    }
+
+    #[test]
+    fn colliding_member_names() {
+        assert_eq!(
+            codegen(
+                r#"{"user_name": "x", "userName": "y", "a": 1, "A": 2}"#,
+                &JavaOptions::default()
+            ),
+            r#"// Root.java
+import com.fasterxml.jackson.annotation.*;
+@JsonIgnoreProperties(ignoreUnknown = true)
+public class Root {
+    private Long a;
+    private Long a2;
+    private String userName;
+    private String userName2;
+    @JsonProperty("A")
+    public Long getA() { return a; }
+    @JsonProperty("A")
+    public void setA(Long value) { this.a = value; }
+    @JsonProperty("a")
+    public Long getA2() { return a2; }
+    @JsonProperty("a")
+    public void setA2(Long value) { this.a2 = value; }
+    public String getUserName() { return userName; }
+    public void setUserName(String value) { this.userName = value; }
+    @JsonProperty("user_name")
+    public String getUserName2() { return userName2; }
+    @JsonProperty("user_name")
+    public void setUserName2(String value) { this.userName2 = value; }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn union_of_objects() {
+        let schema = Schema::Object(vec![Field {
+            name: "shape".into(),
+            ty: FieldType::Union(vec![
+                FieldType::Object(vec![Field {
+                    name: "radius".into(),
+                    ty: FieldType::Float,
+                    description: None,
+                    example: None,
+                }]),
+                FieldType::Object(vec![Field {
+                    name: "side".into(),
+                    ty: FieldType::Float,
+                    description: None,
+                    example: None,
+                }]),
+            ]),
+            description: None,
+            example: None,
+        }]);
+        let mut out = vec![];
+        java(schema, &JavaOptions::default(), &mut out).unwrap();
+        let code = String::from_utf8(out).unwrap();
+
+        assert!(code.contains(
+            r#"            case START_OBJECT: {
+                JsonNode node = parser.readValueAsTree();
+                java.util.List<String> keys = new java.util.ArrayList<>();
+                node.fieldNames().forEachRemaining(keys::add);
+                if (java.util.Arrays.asList("radius").containsAll(keys)) try { value.shapeClazz = parser.getCodec().treeToValue(node, ShapeClazz.class); break; } catch (JsonProcessingException e) {}
+                if (java.util.Arrays.asList("side").containsAll(keys)) try { value.shapeClazz2 = parser.getCodec().treeToValue(node, ShapeClazz2.class); break; } catch (JsonProcessingException e) {}
+                if (!java.util.Collections.disjoint(java.util.Arrays.asList("radius"), keys)) try { value.shapeClazz = parser.getCodec().treeToValue(node, ShapeClazz.class); break; } catch (JsonProcessingException e) {}
+                if (!java.util.Collections.disjoint(java.util.Arrays.asList("side"), keys)) try { value.shapeClazz2 = parser.getCodec().treeToValue(node, ShapeClazz2.class); break; } catch (JsonProcessingException e) {}
+                throw new IOException("Cannot deserialize Shape");
+            }
+"#
+        ));
+    }
+
+    #[test]
+    fn union_of_a_map_and_an_object() {
+        let schema: Schema = "{v:{*:int}|{a:str}}".parse().unwrap();
+        let mut out = vec![];
+        java(schema, &JavaOptions::default(), &mut out).unwrap();
+        let code = String::from_utf8(out).unwrap();
+
+        // the map would accept `{"a": "x"}` as well, so it comes last and keeps its value type.
+        assert!(code.contains(
+            r#"            case START_OBJECT: {
+                JsonNode node = parser.readValueAsTree();
+                java.util.List<String> keys = new java.util.ArrayList<>();
+                node.fieldNames().forEachRemaining(keys::add);
+                if (java.util.Arrays.asList("a").containsAll(keys)) try { value.vClazz = parser.getCodec().treeToValue(node, VClazz.class); break; } catch (JsonProcessingException e) {}
+                try { value.vMap = parser.getCodec().readValue(parser.getCodec().treeAsTokens(node), new com.fasterxml.jackson.core.type.TypeReference<java.util.Map<String, Long>>() {}); break; } catch (JsonProcessingException e) {}
+                if (!java.util.Collections.disjoint(java.util.Arrays.asList("a"), keys)) try { value.vClazz = parser.getCodec().treeToValue(node, VClazz.class); break; } catch (JsonProcessingException e) {}
+                throw new IOException("Cannot deserialize V");
+            }
+"#
+        ));
+    }
+
+    #[test]
+    fn union_of_objects_ignore_unknown_keys() {
+        let schema = || {
+            crate::schema::extract_with(
+                serde_json::json!({"v": [{"a": 1}, "s", {"b": 2}]}),
+                &crate::schema::SchemaInferenceOptions {
+                    keep_distinct_objects: true,
+                    ..Default::default()
+                },
+            )
+        };
+        let mut out = vec![];
+        java(schema(), &JavaOptions::default(), &mut out).unwrap();
+        let code = String::from_utf8(out).unwrap();
+
+        // the members still ignore keys added to the json later,
+        // and `{"b": 2}` is bound to `VClazz2` by its keys rather than to `VClazz` by ignoring `b`.
+        assert!(code.contains(
+            "// VClazz.java\nimport com.fasterxml.jackson.annotation.*;\n@JsonIgnoreProperties(ignoreUnknown = true)\npublic class VClazz {"
+        ));
+        assert!(code.contains(
+            "// VClazz2.java\nimport com.fasterxml.jackson.annotation.*;\n@JsonIgnoreProperties(ignoreUnknown = true)\npublic class VClazz2 {"
+        ));
+        assert!(code.contains(
+            "if (java.util.Arrays.asList(\"b\").containsAll(keys)) try { value.vClazz2 = parser.getCodec().treeToValue(node, VClazz2.class); break; }"
+        ));
+
+        // strict classes fail on the keys they don't have, which is enough to tell them apart.
+        let mut out = vec![];
+        let options = JavaOptions {
+            strict: true,
+            ..Default::default()
+        };
+        java(schema(), &options, &mut out).unwrap();
+        let code = String::from_utf8(out).unwrap();
+        assert!(!code.contains("@JsonIgnoreProperties"));
+        assert!(!code.contains("containsAll"));
+        assert!(code.contains(
+            "                JsonNode node = parser.readValueAsTree();\n                try { value.vClazz = parser.getCodec().treeToValue(node, VClazz.class); break; }"
+        ));
+    }
+
+    #[test]
+    fn references() {
+        let schema: Schema = "{Node:{next:&Node?,shape:&Shape},Shape:&Node|str,Kind:str}"
+            .parse()
+            .unwrap();
+        let mut out = vec![];
+        java(schema, &JavaOptions::default(), &mut out).unwrap();
+        let code = String::from_utf8(out).unwrap();
+
+        assert_eq!(code.matches("public class Node {").count(), 1);
+        assert_eq!(code.matches("public class Shape {").count(), 1);
+        assert!(code.contains("    private Node next;\n    private Shape shape;\n"));
+        assert!(code.contains("    public Node node;\n    public String strVal;\n"));
+        assert!(code.contains(
+            "    private Node node;\n    private Shape shape;\n    private String kind;\n"
+        ));
+    }
+
+    #[test]
+    fn root_arrays() {
+        let options = JavaOptions::default();
+        let root = |json| {
+            codegen(json, &options)
+                .lines()
+                .last()
+                .unwrap_or_default()
+                .to_string()
+        };
+
+        assert_eq!(
+            codegen("[1, 2, 3]", &options),
+            "// Root.java\npublic class Root extends java.util.ArrayList<Long> {}\n"
+        );
+        assert_eq!(
+            root(r#"[1, "a"]"#),
+            "public class Root extends java.util.ArrayList<Item> {}"
+        );
+        assert_eq!(
+            root("[null]"),
+            "public class Root extends java.util.ArrayList<Object> {}"
+        );
+        assert_eq!(
+            root("[1, null]"),
+            "public class Root extends java.util.ArrayList<Long> {}"
+        );
+        assert_eq!(
+            root("[[1.5]]"),
+            "public class Root extends java.util.ArrayList<java.util.List<Double>> {}"
+        );
+    }
+
+    #[test]
+    fn empty_objects() {
+        let options = JavaOptions::default();
+
+        assert_eq!(
+            codegen("{}", &options),
+            "// Root.java\npublic class Root extends java.util.HashMap<String, Object> {}\n"
+        );
+        assert!(codegen(r#"{"x": {}}"#, &options)
+            .contains("    private java.util.Map<String, Object> x;\n"));
+    }
+
+    #[test]
+    fn escaped_keys() {
+        let json =
+            r#"{"he said \"hi\"": 1, "back\\slash": 2, "new\nline": 3, "ctl\u0001\u007f": 4}"#;
+
+        let code = codegen(json, &JavaOptions::default());
+        assert!(code.contains(r#"    @JsonProperty("he said \"hi\"")"#));
+        assert!(code.contains(r#"    @JsonProperty("back\\slash")"#));
+        assert!(code.contains(r#"    @JsonProperty("new\nline")"#));
+        assert!(code.contains(r#"    @JsonProperty("ctl\u0001\u007f")"#));
+
+        let code = codegen(
+            json,
+            &JavaOptions {
+                library: JsonLibrary::Gson,
+                ..Default::default()
+            },
+        );
+        assert!(code.contains(r#"    @SerializedName("he said \"hi\"")"#));
+    }
+
+    #[test]
+    fn root_name() {
+        let options = JavaOptions {
+            root_name: Some("user profile".into()),
+            ..Default::default()
+        };
+
+        assert!(codegen(r#"{"id": 1}"#, &options).starts_with(
+            "// UserProfile.java\nimport com.fasterxml.jackson.annotation.*;\n@JsonIgnoreProperties(ignoreUnknown = true)\npublic class UserProfile {\n"
+        ));
+        assert_eq!(
+            codegen("[1]", &options),
+            "// UserProfile.java\npublic class UserProfile extends java.util.ArrayList<Long> {}\n"
+        );
+
+        let options = JavaOptions {
+            root_name: Some("people".into()),
+            ..Default::default()
+        };
+        let code = codegen(r#"[{"id": 1}]"#, &options);
+        assert!(code.contains("public class Person {"));
+        assert!(code.contains("public class People extends java.util.ArrayList<Person> {}"));
+    }
+
+    #[test]
+    fn primitives() {
+        let json = r#"[
+            {"id": 1, "score": 1.5, "ok": true, "tags": [1], "opt": 1},
+            {"id": 2, "score": 2.5, "ok": false, "tags": [2]}
+        ]"#;
+        let options = JavaOptions {
+            primitives: true,
+            immutable: true,
+            ..Default::default()
+        };
+
+        let code = codegen(json, &options);
+        assert!(code.contains("    private final long id;\n"));
+        assert!(code.contains("    private final double score;\n"));
+        assert!(code.contains("    private final boolean ok;\n"));
+        assert!(code.contains("    private final java.util.List<Long> tags;\n"));
+        assert!(code.contains("    private final Long opt;\n"));
+        assert!(code.contains("    public Long getId() { return id; }\n"));
+        assert!(code.contains("@JsonProperty(\"id\") Long id,"));
+    }
+
+    #[test]
+    fn sealed_interface_unions() {
+        let options = JavaOptions {
+            unions: UnionStyle::SealedInterface,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            codegen(r#"{"items": ["one", 2, {"a": 1}, {"b": true}]}"#, &options),
+            r#"// ItemClazz.java
+import com.fasterxml.jackson.annotation.*;
+@JsonIgnoreProperties(ignoreUnknown = true)
+public class ItemClazz {
+    private Long a;
+    private Boolean b;
+    public Long getA() { return a; }
+    public void setA(Long value) { this.a = value; }
+    public Boolean getB() { return b; }
+    public void setB(Boolean value) { this.b = value; }
+}
+// Root.java
+import com.fasterxml.jackson.annotation.*;
+@JsonIgnoreProperties(ignoreUnknown = true)
+public class Root {
+    private java.util.List<Item> items;
+    public java.util.List<Item> getItems() { return items; }
+    public void setItems(java.util.List<Item> value) { this.items = value; }
+}
+// Item.java
+import java.io.IOException;
+import com.fasterxml.jackson.core.*;
+import com.fasterxml.jackson.databind.*;
+import com.fasterxml.jackson.databind.annotation.*;
+@JsonSerialize(using = Item.Serializer.class)
+@JsonDeserialize(using = Item.Deserializer.class)
+public sealed interface Item {
+    Object value();
+    <R> R accept(Visitor<R> visitor);
+    record StrVariant(String value) implements Item {
+        @Override public <R> R accept(Visitor<R> visitor) { return visitor.visitStr(value); }
+    }
+    record LongVariant(Long value) implements Item {
+        @Override public <R> R accept(Visitor<R> visitor) { return visitor.visitLong(value); }
+    }
+    record ItemClazzVariant(ItemClazz value) implements Item {
+        @Override public <R> R accept(Visitor<R> visitor) { return visitor.visitItemClazz(value); }
+    }
+    interface Visitor<R> {
+        R visitStr(String value);
+        R visitLong(Long value);
+        R visitItemClazz(ItemClazz value);
+    }
+    class Serializer extends JsonSerializer<Item> {
+        @Override public void serialize(Item value, JsonGenerator generator, SerializerProvider serializer) throws IOException {
+            generator.writeObject(value.value());
+        }
+    }
+    class Deserializer extends JsonDeserializer<Item> {
+        @Override public Item deserialize(JsonParser parser, DeserializationContext ctx) throws IOException {
+            switch (parser.currentToken()) {
+            case VALUE_NULL: return null;
+            case VALUE_STRING: return new StrVariant(parser.readValueAs(String.class));
+            case VALUE_NUMBER_INT: return new LongVariant(parser.readValueAs(Long.class));
+            case START_OBJECT: return new ItemClazzVariant(parser.readValueAs(ItemClazz.class));
+            default: throw new IOException("Cannot deserialize Item");
+            }
+        }
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn bean_validation() {
+        let options = JavaOptions {
+            bean_validation: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            codegen(
+                r#"{"point": {"x": 1, "y": null}, "points": [{"x": 1, "y": 2}, {"x": 3}], "tags": ["a"]}"#,
+                &options
+            ),
+            r#"// Point.java
+import com.fasterxml.jackson.annotation.*;
+import jakarta.validation.constraints.*;
+import jakarta.validation.Valid;
+@JsonIgnoreProperties(ignoreUnknown = true)
+public class Point {
+    @NotNull
+    private Long x;
+    private Object y;
+    public Long getX() { return x; }
+    public void setX(Long value) { this.x = value; }
+    public Object getY() { return y; }
+    public void setY(Object value) { this.y = value; }
+}
+// Point2.java
+import com.fasterxml.jackson.annotation.*;
+import jakarta.validation.constraints.*;
+import jakarta.validation.Valid;
+@JsonIgnoreProperties(ignoreUnknown = true)
+public class Point2 {
+    @NotNull
+    private Long x;
+    private Long y;
+    public Long getX() { return x; }
+    public void setX(Long value) { this.x = value; }
+    public Long getY() { return y; }
+    public void setY(Long value) { this.y = value; }
+}
+// Root.java
+import com.fasterxml.jackson.annotation.*;
+import jakarta.validation.constraints.*;
+import jakarta.validation.Valid;
+@JsonIgnoreProperties(ignoreUnknown = true)
+public class Root {
+    @NotNull
+    @Valid
+    private Point point;
+    @NotNull
+    @Valid
+    @Size(min = 0)
+    private java.util.List<Point2> points;
+    @NotNull
+    @Size(min = 0)
+    private java.util.List<String> tags;
+    public Point getPoint() { return point; }
+    public void setPoint(Point value) { this.point = value; }
+    public java.util.List<Point2> getPoints() { return points; }
+    public void setPoints(java.util.List<Point2> value) { this.points = value; }
+    public java.util.List<String> getTags() { return tags; }
+    public void setTags(java.util.List<String> value) { this.tags = value; }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn string_formats() {
+        let json = r#"{"at": ["2024-01-31T09:30:00Z", 1], "day": "2024-01-31", "id": "67e55044-10b1-426f-9247-bb680e5fe0c8"}"#;
+        let schema = crate::schema::extract_with(
+            serde_json::from_str(json).unwrap(),
+            &crate::schema::SchemaInferenceOptions {
+                detect_formats: true,
+                ..Default::default()
+            },
+        );
+        let options = JavaOptions {
+            string_formats: true,
+            ..Default::default()
+        };
+        let mut out = vec![];
+        java(schema.clone(), &options, &mut out).unwrap();
+        let code = String::from_utf8(out).unwrap();
+
+        assert!(code.contains(
+            r#"    @JsonFormat(shape = JsonFormat.Shape.STRING)
+    private java.time.LocalDate day;
+    private java.util.UUID id;
+"#
+        ));
+        assert!(code.contains(
+            "case VALUE_STRING: value.instantVal = parser.readValueAs(java.time.Instant.class); break;"
+        ));
+
+        let mut out = vec![];
+        java(schema, &JavaOptions::default(), &mut out).unwrap();
+        let code = String::from_utf8(out).unwrap();
+        assert!(code.contains("    private String day;\n    private String id;\n"));
+        assert!(code.contains(
+            "case VALUE_STRING: value.strVal = parser.readValueAs(String.class); break;"
+        ));
+    }
+
+    #[test]
+    fn base64() {
+        let json =
+            r#"{"png": "iVBORw0KGgoAAAANSUhEUg==", "blobs": [1, "R0lGODlhAQABAIAAAAUEBA=="]}"#;
+        let schema = crate::schema::extract_with(
+            serde_json::from_str(json).unwrap(),
+            &crate::schema::SchemaInferenceOptions {
+                detect_base64: true,
+                ..Default::default()
+            },
+        );
+        let options = JavaOptions {
+            string_formats: true,
+            ..Default::default()
+        };
+        let mut out = vec![];
+        java(schema.clone(), &options, &mut out).unwrap();
+        let code = String::from_utf8(out).unwrap();
+        assert!(code.contains("    private byte[] png;\n"));
+        assert!(code.contains(
+            "case VALUE_STRING: value.bytesVal = parser.readValueAs(byte[].class); break;"
+        ));
+
+        // gson writes byte[] as an array of numbers.
+        let options = JavaOptions {
+            library: JsonLibrary::Gson,
+            ..options
+        };
+        let mut out = vec![];
+        java(schema, &options, &mut out).unwrap();
+        let code = String::from_utf8(out).unwrap();
+        assert!(code.contains("    private String png;\n"));
+        assert!(!code.contains("byte[]"));
+    }
+
+    #[test]
+    fn numeric_unions() {
+        let code = codegen(
+            r#"{"a": [1.5, "x"], "b": [1, 2.5], "c": [18446744073709551615, "x"]}"#,
+            &JavaOptions::default(),
+        );
+
+        assert!(code.contains(
+            "case VALUE_NUMBER_INT: case VALUE_NUMBER_FLOAT: value.doubleVal = parser.getDoubleValue(); break;"
+        ));
+        assert!(code.contains(
+            "case VALUE_NUMBER_INT: value.longVal = parser.readValueAs(Long.class); break;"
+        ));
+        assert!(code.contains(
+            "case VALUE_NUMBER_FLOAT: value.doubleVal = parser.getDoubleValue(); break;"
+        ));
+        assert!(code.contains(
+            "case VALUE_NUMBER_INT: value.bigIntVal = parser.readValueAs(java.math.BigInteger.class); break;"
+        ));
+    }
+
+    #[test]
+    fn optional_fields() {
+        let json = r#"[{"id": 1, "name": "a"}, {"id": 2}]"#;
+
+        assert_eq!(
+            codegen(
+                json,
+                &JavaOptions {
+                    optional_fields: OptionalFields::JavaOptional,
+                    ..Default::default()
+                }
+            ),
+            r#"// Item.java
+import com.fasterxml.jackson.annotation.*;
+@JsonIgnoreProperties(ignoreUnknown = true)
+public class Item {
+    private Long id;
+    private java.util.Optional<String> name = java.util.Optional.empty();
+    public Long getId() { return id; }
+    public void setId(Long value) { this.id = value; }
+    public java.util.Optional<String> getName() { return name; }
+    public void setName(java.util.Optional<String> value) { this.name = value; }
+}
+// Root.java
+public class Root extends java.util.ArrayList<Item> {}
+"#
+        );
+
+        assert_eq!(
+            codegen(
+                json,
+                &JavaOptions {
+                    optional_fields: OptionalFields::Nullable,
+                    ..Default::default()
+                }
+            ),
+            r#"// Item.java
+import com.fasterxml.jackson.annotation.*;
+@JsonIgnoreProperties(ignoreUnknown = true)
+public class Item {
+    private Long id;
+    private String name;
+    public Long getId() { return id; }
+    public void setId(Long value) { this.id = value; }
+    @org.jetbrains.annotations.Nullable public String getName() { return name; }
+    public void setName(@org.jetbrains.annotations.Nullable String value) { this.name = value; }
+}
+// Root.java
+public class Root extends java.util.ArrayList<Item> {}
+"#
+        );
+    }
+
+    #[test]
+    fn equals_hashcode_to_string() {
+        let options = JavaOptions {
+            generate_equals_hashcode: true,
+            generate_to_string: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            codegen(r#"{"id": 1, "name": "a"}"#, &options),
+            r#"// Root.java
+import java.util.Objects;
+import com.fasterxml.jackson.annotation.*;
+@JsonIgnoreProperties(ignoreUnknown = true)
+public class Root {
+    private Long id;
+    private String name;
+    public Long getId() { return id; }
+    public void setId(Long value) { this.id = value; }
+    public String getName() { return name; }
+    public void setName(String value) { this.name = value; }
+    @Override public boolean equals(Object o) {
+        if (this == o) return true;
+        if (o == null || getClass() != o.getClass()) return false;
+        Root that = (Root) o;
+        return Objects.equals(this.id, that.id) && Objects.equals(this.name, that.name);
+    }
+    @Override public int hashCode() { return Objects.hash(this.id, this.name); }
+    @Override public String toString() {
+        StringBuilder sb = new StringBuilder("Root{");
+        sb.append("id=").append(this.id);
+        sb.append(", name=").append(this.name);
+        return sb.append("}").toString();
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn collections() {
+        let json = r#"{"ids": [1, 2], "matrix": [[1.5]], "value": [1, [2]]}"#;
+
+        let code = codegen(json, &JavaOptions::default());
+        assert!(code.contains("private java.util.List<Long> ids;"));
+        assert!(code.contains("private java.util.List<java.util.List<Double>> matrix;"));
+        assert!(code.contains(
+            "case START_ARRAY: value.valueArr = parser.readValueAs(new com.fasterxml.jackson.core.type.TypeReference<java.util.List<Long>>() {}); break;"
+        ));
+
+        let code = codegen(
+            json,
+            &JavaOptions {
+                collections: Collections::Arrays,
+                ..Default::default()
+            },
+        );
+        assert!(code.contains("private Long[] ids;"));
+        assert!(code.contains("private Double[][] matrix;"));
+        assert!(code.contains(
+            "case START_ARRAY: value.valueArr = parser.readValueAs(Long[].class); break;"
+        ));
+    }
+
+    #[test]
+    fn options_from_json() {
+        let options: JavaOptions = serde_json::from_str(
+            r#"{"package": "com.example", "indent": {"spaces": 2}, "collections": "arrays", "derives": []}"#,
+        )
+        .unwrap();
+
+        assert_eq!(options.package.as_deref(), Some("com.example"));
+        assert_eq!(options.indent, IndentStyle::Spaces(2));
+        assert_eq!(options.collections, Collections::Arrays);
+        assert_eq!(options.optional_fields, OptionalFields::Plain);
+    }
+
+    #[test]
+    fn equals_hashcode_to_string_arrays() {
+        let options = JavaOptions {
+            generate_equals_hashcode: true,
+            generate_to_string: true,
+            collections: Collections::Arrays,
+            ..Default::default()
+        };
+
+        let code = codegen(r#"{"id": 1, "tags": ["a"]}"#, &options);
+        assert!(code.contains(
+            "return Objects.equals(this.id, that.id) && java.util.Arrays.deepEquals(this.tags, that.tags);"
+        ));
+        assert!(code.contains(
+            "@Override public int hashCode() { return Objects.hash(this.id, java.util.Arrays.deepHashCode(this.tags)); }"
+        ));
+        assert!(code
+            .contains("sb.append(\", tags=\").append(java.util.Arrays.deepToString(this.tags));"));
+    }
+
+    #[test]
+    fn gson() {
+        let options = JavaOptions {
+            library: JsonLibrary::Gson,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            codegen(r#"{"user_id": 1, "value": [1, "x", 1.5]}"#, &options),
+            r#"// Root.java
+import com.google.gson.annotations.*;
+public class Root {
+    @SerializedName("user_id")
+    private Long userId;
+    private java.util.List<Value> value;
+    public Long getUserId() { return userId; }
+    public void setUserId(Long value) { this.userId = value; }
+    public java.util.List<Value> getValue() { return value; }
+    public void setValue(java.util.List<Value> value) { this.value = value; }
+}
+// Value.java
+import java.io.IOException;
+import com.google.gson.*;
+import com.google.gson.annotations.*;
+import com.google.gson.reflect.*;
+import com.google.gson.stream.*;
+@JsonAdapter(Value.Adapter.class)
+public class Value {
+    public String strVal;
+    public Long longVal;
+    public Double doubleVal;
+    static class Adapter extends TypeAdapter<Value> {
+        private static final Gson GSON = new Gson();
+        @Override public void write(JsonWriter writer, Value value) throws IOException {
+            if (value.strVal != null) { GSON.toJson(value.strVal, value.strVal.getClass(), writer); return; }
+            if (value.longVal != null) { GSON.toJson(value.longVal, value.longVal.getClass(), writer); return; }
+            if (value.doubleVal != null) { GSON.toJson(value.doubleVal, value.doubleVal.getClass(), writer); return; }
+            writer.nullValue();
+        }
+        @Override public Value read(JsonReader reader) throws IOException {
+            JsonElement json = JsonParser.parseReader(reader);
+            Value value = new Value();
+            if (json.isJsonPrimitive() && json.getAsJsonPrimitive().isString()) { value.strVal = GSON.fromJson(json, String.class); return value; }
+            if (json.isJsonPrimitive() && json.getAsJsonPrimitive().isNumber() && isIntegral(json)) { value.longVal = GSON.fromJson(json, Long.class); return value; }
+            if (json.isJsonPrimitive() && json.getAsJsonPrimitive().isNumber() && !isIntegral(json)) { value.doubleVal = json.getAsDouble(); return value; }
+            throw new JsonParseException("Cannot deserialize Value");
+        }
+        private static boolean isIntegral(JsonElement json) {
+            String text = json.getAsString();
+            return !(text.contains(".") || text.contains("e") || text.contains("E"));
+        }
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn keywords() {
+        assert_eq!(
+            codegen(
+                r#"{"class": 1, "default": true, "string": {"x": 1}}"#,
+                &JavaOptions::default()
+            ),
+            r#"// Root.java
+import com.fasterxml.jackson.annotation.*;
+@JsonIgnoreProperties(ignoreUnknown = true)
+public class Root {
+    private Long class_2;
+    private Boolean default_;
+    private String2 string;
+    @JsonProperty("class")
+    public Long getClass2() { return class_2; }
+    @JsonProperty("class")
+    public void setClass2(Long value) { this.class_2 = value; }
+    @JsonProperty("default")
+    public Boolean getDefault() { return default_; }
+    @JsonProperty("default")
+    public void setDefault(Boolean value) { this.default_ = value; }
+    public String2 getString() { return string; }
+    public void setString(String2 value) { this.string = value; }
+}
+// String2.java
+import com.fasterxml.jackson.annotation.*;
+@JsonIgnoreProperties(ignoreUnknown = true)
+public class String2 {
+    private Long x;
+    public Long getX() { return x; }
+    public void setX(Long value) { this.x = value; }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn reserved_class_names() {
+        let code = codegen(
+            r#"{"root": {"x": 1}, "items": [1, {"serializer": {"visitor": {"a": 1}}}], "adapter": {"b": 1}}"#,
+            &JavaOptions::default(),
+        );
+
+        assert!(code.contains("public class Root {"));
+        assert!(code.contains("public class Root2 {"));
+        assert!(code.contains("public class Serializer2 {"));
+        assert!(code.contains("public class Visitor2 {"));
+        assert!(code.contains("public class Adapter2 {"));
+        assert!(code.contains("private Serializer2 serializer;"));
+    }
+
+    #[test]
+    fn stable_class_order() {
+        let files = |json: &str| -> Vec<String> {
+            codegen(json, &JavaOptions::default())
+                .lines()
+                .filter_map(|line| line.strip_prefix("// "))
+                .map(String::from)
+                .collect()
+        };
+
+        assert_eq!(
+            files(r#"{"b": {"y": 1}, "c": {"a": {"x": 1}}, "d": [1, "x"]}"#),
+            ["A.java", "B.java", "C.java", "Root.java", "D.java"]
+        );
+        assert_eq!(
+            files(r#"{"a": {"x": 1}, "b": {"y": 1}, "d": [1, "x"]}"#),
+            ["A.java", "B.java", "Root.java", "D.java"]
+        );
+    }
+
+    #[test]
+    fn manifest() {
+        let options = JavaOptions {
+            manifest: true,
+            ..Default::default()
+        };
+        let code = codegen(
+            r#"{"cart": [{"sku": "a", "tags": [1, "x"]}], "meta": {"id": 1}}"#,
+            &options,
+        );
+
+        assert!(code.ends_with(
+            r#"// manifest.json
+{
+  "Cart": "$.cart[]",
+  "Meta": "$.meta",
+  "Root": "$",
+  "Tag": "$.cart[].tags[]"
+}
+"#
+        ));
+        assert!(codegen("[1]", &options).ends_with(
+            r#"// manifest.json
+{
+  "Root": "$"
+}
+"#
+        ));
+    }
+
+    #[test]
+    fn immutable() {
+        let json = r#"[{"id": 1, "first name": "a"}, {"id": 2}]"#;
+
+        assert_eq!(
+            codegen(
+                json,
+                &JavaOptions {
+                    immutable: true,
+                    optional_fields: OptionalFields::JavaOptional,
+                    ..Default::default()
+                }
+            ),
+            r#"// Item.java
+import com.fasterxml.jackson.annotation.*;
+@JsonIgnoreProperties(ignoreUnknown = true)
+public class Item {
+    private final java.util.Optional<String> firstName;
+    private final Long id;
+    @JsonCreator
+    public Item(@JsonProperty("first name") String firstName, @JsonProperty("id") Long id) {
+        this.firstName = java.util.Optional.ofNullable(firstName);
+        this.id = id;
+    }
+    @JsonProperty("first name")
+    public java.util.Optional<String> getFirstName() { return firstName; }
+    public Long getId() { return id; }
+}
+// Root.java
+public class Root extends java.util.ArrayList<Item> {}
+"#
+        );
+
+        assert_eq!(
+            codegen(
+                json,
+                &JavaOptions {
+                    immutable: true,
+                    library: JsonLibrary::Gson,
+                    ..Default::default()
+                }
+            ),
+            r#"// Item.java
+import com.google.gson.annotations.*;
+public class Item {
+    @SerializedName("first name")
+    private final String firstName;
+    private final Long id;
+    public Item(String firstName, Long id) {
+        this.firstName = firstName;
+        this.id = id;
+    }
+    public String getFirstName() { return firstName; }
+    public Long getId() { return id; }
+}
+// Root.java
+public class Root extends java.util.ArrayList<Item> {}
+"#
+        );
+    }
+
+    #[test]
+    fn fluent_setters_and_withers() {
+        let json = r#"[{"id": 1, "first name": "a"}, {"id": 2}]"#;
+
+        let code = codegen(
+            json,
+            &JavaOptions {
+                fluent_setters: true,
+                ..Default::default()
+            },
+        );
+        assert!(code.contains(
+            r#"    @JsonProperty("first name")
+    public Item setFirstName(String value) { this.firstName = value; return this; }
+"#
+        ));
+        assert!(
+            code.contains("    public Item setId(Long value) { this.id = value; return this; }\n")
+        );
+
+        let code = codegen(
+            json,
+            &JavaOptions {
+                immutable: true,
+                withers: true,
+                optional_fields: OptionalFields::JavaOptional,
+                ..Default::default()
+            },
+        );
+        assert!(code.contains(
+            "    public Item withFirstName(String value) { return new Item(value, this.id); }\n"
+        ));
+        assert!(code.contains(
+            "    public Item withId(Long value) { return new Item(this.firstName.orElse(null), value); }\n"
+        ));
+    }
+
+    #[test]
+    fn json_facade() {
+        assert_eq!(
+            codegen(
+                r#"{"price": 1.5}"#,
+                &JavaOptions {
+                    json_facade: true,
+                    floats: Floats::BigDecimal,
+                    ..Default::default()
+                }
+            ),
+            r#"// Root.java
+import com.fasterxml.jackson.annotation.*;
+@JsonIgnoreProperties(ignoreUnknown = true)
+public class Root {
+    private java.math.BigDecimal price;
+    public java.math.BigDecimal getPrice() { return price; }
+    public void setPrice(java.math.BigDecimal value) { this.price = value; }
+    private static final com.fasterxml.jackson.databind.ObjectMapper MAPPER = new com.fasterxml.jackson.databind.ObjectMapper()
+        .enable(com.fasterxml.jackson.databind.DeserializationFeature.USE_BIG_DECIMAL_FOR_FLOATS);
+    public static Root fromJson(String json) throws com.fasterxml.jackson.core.JsonProcessingException { return MAPPER.readValue(json, Root.class); }
+    public static String toJson(Root value) throws com.fasterxml.jackson.core.JsonProcessingException { return MAPPER.writeValueAsString(value); }
+}
+"#
+        );
+
+        assert_eq!(
+            codegen(
+                "[1]",
+                &JavaOptions {
+                    json_facade: true,
+                    library: JsonLibrary::Gson,
+                    ..Default::default()
+                }
+            ),
+            r#"// Root.java
+public class Root extends java.util.ArrayList<Long> {
+    private static final com.google.gson.Gson GSON = new com.google.gson.Gson();
+    public static Root fromJson(String json) { return GSON.fromJson(json, Root.class); }
+    public static String toJson(Root value) { return GSON.toJson(value); }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn constants() {
+        let json =
+            r#"[{"version": "1.0", "type": "a", "n": 1}, {"version": "1.0", "type": "b", "n": 1}]"#;
+        let schema = crate::schema::extract_with(
+            serde_json::from_str(json).unwrap(),
+            &crate::schema::SchemaInferenceOptions {
+                detect_constants: true,
+                ..Default::default()
+            },
+        );
+        let mut out = vec![];
+        java(schema, &JavaOptions::default(), &mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            r#"// Item.java
+import com.fasterxml.jackson.annotation.*;
+@JsonIgnoreProperties(ignoreUnknown = true)
+public class Item {
+    public static final Long N = 1L;
+    public static final String VERSION = "1.0";
+    private Long n;
+    private String type;
+    private String version;
+    public Long getN() { return n; }
+    public void setN(Long value) { this.n = value; }
+    public String getType() { return type; }
+    public void setType(String value) { this.type = value; }
+    public String getVersion() { return version; }
+    public void setVersion(String value) { this.version = value; }
+}
+// Root.java
+public class Root extends java.util.ArrayList<Item> {}
+"#
+        );
+    }
+
+    #[test]
+    fn big_decimal_floats() {
+        let json = r#"{"price": 1299.50, "rate": [0.1, "x"]}"#;
+        let options = JavaOptions {
+            floats: Floats::BigDecimal,
+            ..Default::default()
+        };
+
+        let code = codegen(json, &options);
+        assert!(code.contains("private java.math.BigDecimal price;"));
+        assert!(code.contains("public java.math.BigDecimal decimalVal;"));
+        assert!(code.contains(
+            "case VALUE_NUMBER_FLOAT: value.decimalVal = parser.getDecimalValue(); break;"
+        ));
+        assert!(!code.contains("Double"));
+
+        let code = codegen(
+            json,
+            &JavaOptions {
+                library: JsonLibrary::Gson,
+                ..options
+            },
+        );
+        assert!(code.contains("value.decimalVal = json.getAsBigDecimal(); return value;"));
+    }
+
+    #[test]
+    fn maps() {
+        let days = (1..=50)
+            .map(|day| format!(r#""2024-03-{day:02}": {{"open": 1.5}}"#))
+            .collect::<Vec<_>>()
+            .join(",");
+        let json = format!(r#"{{"prices": {{{days}}}, "value": [{{"7": 1}}, 1]}}"#);
+        let schema = crate::schema::extract_with(
+            serde_json::from_str(&json).unwrap(),
+            &crate::schema::SchemaInferenceOptions {
+                detect_maps: true,
+                ..Default::default()
+            },
+        );
+        let mut out = vec![];
+        java(schema, &JavaOptions::default(), &mut out).unwrap();
+        let code = String::from_utf8(out).unwrap();
+
+        assert!(code.contains("private java.util.Map<String, Price> prices;"));
+        assert!(code.contains("// Price.java"));
+        assert!(code.contains("private Double open;"));
+        assert!(code.contains("public java.util.Map<String, Long> valueMap;"));
+        assert!(code.contains("case START_OBJECT: value.valueMap = parser.readValueAs(new com.fasterxml.jackson.core.type.TypeReference<java.util.Map<String, Long>>() {}); break;"));
+    }
+
+    #[test]
+    fn tuples() {
+        let json =
+            r#"{"pair": ["id", 42], "point": [1.5, 2.5], "points": [[1.5, 2.5], [3.5, 4.5]]}"#;
+        let schema = crate::schema::extract_with(
+            serde_json::from_str(json).unwrap(),
+            &crate::schema::SchemaInferenceOptions {
+                detect_tuples: true,
+                ..Default::default()
+            },
+        );
+        let mut out = vec![];
+        java(schema, &JavaOptions::default(), &mut out).unwrap();
+        let code = String::from_utf8(out).unwrap();
+
+        assert!(code.contains("    private java.util.List<Pair> pair;\n"));
+        assert!(code.contains("    private java.util.List<Double> point;\n"));
+        assert!(code.contains("    private java.util.List<java.util.List<Double>> points;\n"));
+    }
+
+    #[test]
+    fn javadoc_paths_and_examples() {
+        let json = r#"{"cart": [{"sku": "A<1>", "qty": 2}, {"sku": "B", "qty": null}], "from": {"x": 1}, "to": {"x": 2}}"#;
+        let schema = crate::schema::extract_with(
+            serde_json::from_str(json).unwrap(),
+            &crate::schema::SchemaInferenceOptions {
+                examples: true,
+                ..Default::default()
+            },
+        );
+        let options = JavaOptions {
+            javadoc: true,
+            reuse_identical_objects: true,
+            ..Default::default()
+        };
+        let mut out = vec![];
+        java(schema, &options, &mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            r#"// Cart.java
+import com.fasterxml.jackson.annotation.*;
+/** Derived from JSON path $.cart[] */
+@JsonIgnoreProperties(ignoreUnknown = true)
+public class Cart {
+    private Long qty;
+    private String sku;
+    /** Example: 2 */
+    public Long getQty() { return qty; }
+    public void setQty(Long value) { this.qty = value; }
+    /** Example: "A&lt;1&gt;" */
+    public String getSku() { return sku; }
+    public void setSku(String value) { this.sku = value; }
+}
+// FromOrTo.java
+import com.fasterxml.jackson.annotation.*;
+/** Derived from JSON path $.from */
+@JsonIgnoreProperties(ignoreUnknown = true)
+public class FromOrTo {
+    private Long x;
+    /** Example: 1 */
+    public Long getX() { return x; }
+    public void setX(Long value) { this.x = value; }
+}
+// Root.java
+import com.fasterxml.jackson.annotation.*;
+/** Derived from JSON path $ */
+@JsonIgnoreProperties(ignoreUnknown = true)
+public class Root {
+    private java.util.List<Cart> cart;
+    private FromOrTo from;
+    private FromOrTo to;
+    public java.util.List<Cart> getCart() { return cart; }
+    public void setCart(java.util.List<Cart> value) { this.cart = value; }
+    public FromOrTo getFrom() { return from; }
+    public void setFrom(FromOrTo value) { this.from = value; }
+    public FromOrTo getTo() { return to; }
+    public void setTo(FromOrTo value) { this.to = value; }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn descriptions() {
+        let json = r#"{"id": 1, "id_description": "primary key", "name": "a", "name.$description": "display name\nshown in the ui"}"#;
+        let schema = crate::schema::extract_with(
+            serde_json::from_str(json).unwrap(),
+            &crate::schema::SchemaInferenceOptions {
+                extract_descriptions: true,
+                ..Default::default()
+            },
+        );
+        let mut out = vec![];
+        java(schema, &JavaOptions::default(), &mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            r#"// Root.java
+import com.fasterxml.jackson.annotation.*;
+@JsonIgnoreProperties(ignoreUnknown = true)
+public class Root {
+    /** primary key */
+    private Long id;
+    /**
+     * display name
+     * shown in the ui
+     */
+    private String name;
+    public Long getId() { return id; }
+    public void setId(Long value) { this.id = value; }
+    public String getName() { return name; }
+    public void setName(String value) { this.name = value; }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn nested_layout() {
+        let options = JavaOptions {
+            package: Some("com.example".into()),
+            layout: Layout::Nested,
+            manifest: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            codegen(r#"{"point": {"x": 1}, "items": ["one", 2]}"#, &options),
+            r#"// Root.java
+package com.example;
+import com.fasterxml.jackson.annotation.*;
+import java.io.IOException;
+import com.fasterxml.jackson.core.*;
+import com.fasterxml.jackson.databind.*;
+import com.fasterxml.jackson.databind.annotation.*;
+@JsonIgnoreProperties(ignoreUnknown = true)
+public class Root {
+    private java.util.List<Item> items;
+    private Point point;
+    public java.util.List<Item> getItems() { return items; }
+    public void setItems(java.util.List<Item> value) { this.items = value; }
+    public Point getPoint() { return point; }
+    public void setPoint(Point value) { this.point = value; }
+    @JsonIgnoreProperties(ignoreUnknown = true)
+    public static class Point {
+        private Long x;
+        public Long getX() { return x; }
+        public void setX(Long value) { this.x = value; }
+    }
+    @JsonSerialize(using = Item.Serializer.class)
+    @JsonDeserialize(using = Item.Deserializer.class)
+    public static class Item {
+        public String strVal;
+        public Long longVal;
+        static class Serializer extends JsonSerializer<Item> {
+            @Override public void serialize(Item value, JsonGenerator generator, SerializerProvider serializer) throws IOException {
+                if (value.strVal != null) { generator.writeObject(value.strVal); return; }
+                if (value.longVal != null) { generator.writeObject(value.longVal); return; }
+                generator.writeNull();
+            }
         }
+        static class Deserializer extends JsonDeserializer<Item> {
+            @Override public Item deserialize(JsonParser parser, DeserializationContext ctx) throws IOException {
+                Item value = new Item();
+                switch (parser.currentToken()) {
+                case VALUE_NULL: break;
+                case VALUE_STRING: value.strVal = parser.readValueAs(String.class); break;
+                case VALUE_NUMBER_INT: value.longVal = parser.readValueAs(Long.class); break;
+                default: throw new IOException("Cannot deserialize Item");
+                }
+                return value;
+            }
+        }
+    }
+}
+// manifest.json
+{
+  "Item": "$.items[]",
+  "Point": "$.point",
+  "Root": "$"
+}
+"#
+        );
+        assert_eq!(
+            codegen(r#"[{"x": 1}]"#, &options),
+            r#"// Root.java
+package com.example;
+import com.fasterxml.jackson.annotation.*;
+public class Root extends java.util.ArrayList<Item> {
+    @JsonIgnoreProperties(ignoreUnknown = true)
+    public static class Item {
+        private Long x;
+        public Long getX() { return x; }
+        public void setX(Long value) { this.x = value; }
+    }
+}
+// manifest.json
+{
+  "Item": "$[]",
+  "Root": "$"
+}
+"#
+        );
+    }
+
+    #[test]
+    fn exclude_null_on_serialize() {
+        let options = JavaOptions {
+            exclude_null_on_serialize: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            codegen(r#"[{"id": 1, "nickname": "x"}, {"id": 2}]"#, &options),
+            r#"// Item.java
+import com.fasterxml.jackson.annotation.*;
+@JsonIgnoreProperties(ignoreUnknown = true)
+@JsonInclude(JsonInclude.Include.NON_NULL)
+public class Item {
+    private Long id;
+    private String nickname;
+    public Long getId() { return id; }
+    public void setId(Long value) { this.id = value; }
+    public String getNickname() { return nickname; }
+    public void setNickname(String value) { this.nickname = value; }
+}
+// Root.java
+public class Root extends java.util.ArrayList<Item> {}
+"#
+        );
+
+        let options = JavaOptions {
+            optional_fields: OptionalFields::JavaOptional,
+            ..options
+        };
+        assert!(codegen(r#"{"a": 1}"#, &options)
+            .contains("@JsonInclude(JsonInclude.Include.NON_ABSENT)\npublic class Root {"));
+
+        let options = JavaOptions {
+            library: JsonLibrary::Gson,
+            ..options
+        };
+        assert!(!codegen(r#"{"a": 1}"#, &options).contains("@JsonInclude"));
+    }
+
+    #[test]
+    fn null_field_policy() {
+        let options = JavaOptions {
+            null_field_policy: NullFieldPolicy::Skip,
+            ..Default::default()
+        };
+        let json = r#"{"id": 1, "deleted_at": null, "tags": [null]}"#;
+        assert_eq!(
+            codegen(json, &options),
+            r#"// Root.java
+import com.fasterxml.jackson.annotation.*;
+@JsonIgnoreProperties(ignoreUnknown = true)
+public class Root {
+    private Long id;
+    private java.util.List<Object> tags;
+    public Long getId() { return id; }
+    public void setId(Long value) { this.id = value; }
+    public java.util.List<Object> getTags() { return tags; }
+    public void setTags(java.util.List<Object> value) { this.tags = value; }
+}
+"#
+        );
+
+        let options = JavaOptions {
+            strict: true,
+            ..options
+        };
+        assert!(codegen(json, &options)
+            .contains("@JsonIgnoreProperties({\"deleted_at\"})\npublic class Root {"));
     }
 }
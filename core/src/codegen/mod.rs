@@ -1,11 +1,163 @@
 mod java;
 mod rust;
 
-pub use java::java;
-pub use rust::rust;
+pub use java::{
+    java, Collections, Floats, JavaOptions, JsonLibrary, Layout, NullFieldPolicy, OptionalFields,
+    UnionStyle,
+};
+pub use rust::{
+    rust, rust_file, rust_files, RecursiveWrapper, RustOptions, UnknownType, Visibility,
+};
 
+use crate::schema::{same_fields, Field, FieldType, Schema};
 use convert_case::{Case, Casing};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{
+    fmt::{self, Display, Formatter},
+    fs::{self, File},
+    io::{self, Error, Write},
+    path::PathBuf,
+};
 
+/// why code could not be generated.
+#[derive(Debug)]
+pub enum CodegenError {
+    /// the generated code could not be written.
+    Io(io::Error),
+    /// the input file of `rust_file` isn't json.
+    InvalidJson(serde_json::Error),
+    /// the top level json value is neither an object nor an array.
+    InvalidTopLevel,
+    /// `dispatch` has no generator for the language. eg: `"go"`
+    UnsupportedLanguage(String),
+    /// the options passed to `dispatch` don't fit the generator of the language.
+    InvalidOptions(serde_json::Error),
+    /// every number of the `Iota` was used to name fields that have no usable identifier.
+    NamesExhausted,
+}
+
+impl Display for CodegenError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CodegenError::Io(err) => write!(f, "{err}"),
+            CodegenError::InvalidJson(err) => write!(f, "invalid json: {err}"),
+            CodegenError::InvalidTopLevel => {
+                write!(f, "the top level json value must be an object or an array")
+            }
+            CodegenError::UnsupportedLanguage(lang) => write!(f, "no generator for {lang:?}"),
+            CodegenError::InvalidOptions(err) => write!(f, "invalid options: {err}"),
+            CodegenError::NamesExhausted => {
+                write!(f, "ran out of names for fields without an identifier")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CodegenError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CodegenError::Io(err) => Some(err),
+            CodegenError::InvalidJson(err) | CodegenError::InvalidOptions(err) => Some(err),
+            CodegenError::InvalidTopLevel
+            | CodegenError::UnsupportedLanguage(_)
+            | CodegenError::NamesExhausted => None,
+        }
+    }
+}
+
+impl From<io::Error> for CodegenError {
+    fn from(err: io::Error) -> Self {
+        CodegenError::Io(err)
+    }
+}
+
+/// generate code with the generator of the language named `lang` (`"java"` or `"rust"`, in any case).
+/// `options` is a json object with the options of that generator (eg: `{"package": "com.example"}`).
+/// the missing ones get their defaults.
+/// with `{"manifest": true}`, the `manifest.json` of either language follows the code in `out`,
+/// every file preceded by a `// {filename}` comment. see `Sink`
+pub fn dispatch<W: Write>(
+    lang: &str,
+    schema: Schema,
+    options: Value,
+    out: &mut W,
+) -> Result<(), CodegenError> {
+    match lang.to_ascii_lowercase().as_str() {
+        "java" => java(
+            schema,
+            &serde_json::from_value(options).map_err(CodegenError::InvalidOptions)?,
+            out,
+        ),
+        "rust" => {
+            let options: RustOptions =
+                serde_json::from_value(options).map_err(CodegenError::InvalidOptions)?;
+            match options.manifest {
+                true => rust_files(schema, &options, out),
+                false => rust(schema, &options, out),
+            }
+        }
+        _ => Err(CodegenError::UnsupportedLanguage(lang.into())),
+    }
+}
+
+/// `"tabs"` or `{"spaces": n}` in json.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndentStyle {
+    Tabs,
+    Spaces(usize),
+}
+
+impl Default for IndentStyle {
+    fn default() -> Self {
+        Self::Spaces(4)
+    }
+}
+
+impl IndentStyle {
+    /// indentation for the given nesting level.
+    pub fn level(&self, level: usize) -> String {
+        match self {
+            IndentStyle::Tabs => "\t".repeat(level),
+            IndentStyle::Spaces(n) => " ".repeat(n * level),
+        }
+    }
+}
+
+/// destination for generated source files.
+pub trait Sink {
+    fn sink(&mut self, filename: &str) -> Result<Box<dyn Write + '_>, Error>;
+}
+
+/// every file goes into the same writer, preceded by a `// {filename}` comment.
+impl<W: Write> Sink for W {
+    fn sink(&mut self, filename: &str) -> Result<Box<dyn Write + '_>, Error> {
+        writeln!(self, "// {}", filename)?;
+        Ok(Box::new(self))
+    }
+}
+
+/// every file is created (or truncated) inside `dir`.
+/// `dir` is created if it doesn't exist.
+pub struct DirectorySink {
+    dir: PathBuf,
+}
+
+impl DirectorySink {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+impl Sink for DirectorySink {
+    fn sink(&mut self, filename: &str) -> Result<Box<dyn Write + '_>, Error> {
+        fs::create_dir_all(&self.dir)?;
+        Ok(Box::new(File::create(self.dir.join(filename))?))
+    }
+}
+
+/// counter behind the `Unknown0`, `unknown1`, ... names of fields that have no usable identifier.
 pub struct Iota {
     n: usize,
 }
@@ -15,34 +167,275 @@ impl Iota {
         Self { n: 0 }
     }
 
+    /// the next number. wraps around after `usize::MAX`, see `checked_next`.
     pub fn get(&mut self) -> usize {
         let n = self.n;
-        self.n += 1;
+        self.n = n.wrapping_add(1);
         n
     }
+
+    /// the next number, or `None` once every `usize` has been handed out.
+    pub fn checked_next(&mut self) -> Option<usize> {
+        let n = self.n;
+        self.n = n.checked_add(1)?;
+        Some(n)
+    }
+
+    /// the number the next call to `get` returns.
+    pub fn peek(&self) -> usize {
+        self.n
+    }
+
+    /// start counting from 0 again.
+    pub fn reset(&mut self) {
+        self.n = 0;
+    }
+}
+
+impl Default for Iota {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// structurally identical objects, generated once and reused by every field they appear in.
+///
+/// a shape that is reached through a single field name keeps that name.
+/// a shape that is reached through different field names gets a neutral name
+/// joining all of them in order of appearance.
+/// eg: `{"from": {"x": 1}, "to": {"x": 2}}` generates a single `FromOrTo` type.
+pub(crate) struct SharedObjects {
+    names: Vec<(Vec<Field>, Vec<String>, usize)>,
+    generated: Vec<(Vec<Field>, String)>,
+}
+
+impl SharedObjects {
+    pub(crate) fn new(schema: &Schema, root_name: Option<&str>) -> Self {
+        let mut shared = Self {
+            names: vec![],
+            generated: vec![],
+        };
+
+        match schema {
+            Schema::Object(fields) => {
+                for field in fields {
+                    shared.collect(&field.ty, &field.name);
+                }
+            }
+            Schema::Array(ty) => shared.collect(ty, &root_element_name(root_name)),
+        }
+
+        shared
+    }
+
+    fn collect(&mut self, ty: &FieldType, name: &str) {
+        match ty {
+            FieldType::Object(fields) => {
+                match self
+                    .names
+                    .iter_mut()
+                    .find(|(shape, _, _)| same_fields(shape, fields))
+                {
+                    Some((_, names, occurrences)) => {
+                        if !names.iter().any(|n| n == name) {
+                            names.push(name.into());
+                        }
+                        // the nested shapes were already collected the first time.
+                        *occurrences += 1;
+                    }
+                    None => {
+                        self.names.push((fields.clone(), vec![name.into()], 1));
+                        for field in fields {
+                            self.collect(&field.ty, &field.name);
+                        }
+                    }
+                }
+            }
+            FieldType::Union(tys) => {
+                for ty in tys {
+                    self.collect(ty, name);
+                }
+            }
+            FieldType::Array(ty) | FieldType::Map(ty) => self.collect(ty, &singular(name)),
+            FieldType::Tuple(tys) => {
+                for ty in tys {
+                    self.collect(ty, &singular(name));
+                }
+            }
+            FieldType::Optional(ty) => self.collect(ty, name),
+            _ => {}
+        }
+    }
+
+    /// number of distinct places (field names or parents) the shape is reached through.
+    pub(crate) fn occurrences(&self, fields: &[Field]) -> usize {
+        self.names
+            .iter()
+            .find(|(shape, _, _)| same_fields(shape, fields))
+            .map_or(0, |(_, _, n)| *n)
+    }
+
+    /// text to derive the type name from. `name` unless the shape is shared between different names.
+    pub(crate) fn name(&self, fields: &[Field], name: &str) -> String {
+        match self
+            .names
+            .iter()
+            .find(|(shape, _, _)| same_fields(shape, fields))
+        {
+            Some((_, names, _)) if names.len() > 1 => names.join(" or "),
+            _ => name.into(),
+        }
+    }
+
+    /// type name of the already generated shape.
+    pub(crate) fn generated(&self, fields: &[Field]) -> Option<String> {
+        self.generated
+            .iter()
+            .find(|(shape, _)| same_fields(shape, fields))
+            .map(|(_, type_name)| type_name.clone())
+    }
+
+    pub(crate) fn insert(&mut self, fields: Vec<Field>, type_name: String) {
+        self.generated.push((fields, type_name));
+    }
+}
+
+/// the top level fields of the schema that `FieldType::Ref`s refer to,
+/// and the names of the types already generated for them.
+pub(crate) struct Definitions {
+    types: Vec<(String, FieldType)>,
+    generated: Vec<(String, String)>,
+    /// definitions whose type is being generated, innermost last.
+    resolving: Vec<String>,
+}
+
+impl Definitions {
+    pub(crate) fn new(schema: &Schema) -> Self {
+        let mut referred = vec![];
+        for ty in schema.iter() {
+            if let FieldType::Ref(name) = ty {
+                referred.push(name.as_str());
+            }
+        }
+        let types = match schema {
+            Schema::Object(fields) => fields
+                .iter()
+                .filter(|field| referred.contains(&field.name.as_str()))
+                .map(|field| {
+                    let ty = match &field.ty {
+                        // a nullable definition. the fields that refer to it are optional.
+                        FieldType::Optional(ty) => ty.as_ref(),
+                        ty => ty,
+                    };
+                    (field.name.clone(), ty.clone())
+                })
+                .collect(),
+            Schema::Array(_) => vec![],
+        };
+        Self {
+            types,
+            generated: vec![],
+            resolving: vec![],
+        }
+    }
+
+    /// the top level `fields` with the definitions that aren't objects replaced by references,
+    /// so that they are generated once under their own name like the objects.
+    pub(crate) fn refer_to(&self, fields: Vec<Field>) -> Vec<Field> {
+        fields
+            .into_iter()
+            .map(|mut field| {
+                if let Some(ty) = self.get(&field.name) {
+                    if !matches!(ty, FieldType::Object(_)) {
+                        let reference = FieldType::Ref(field.name.clone());
+                        field.ty = match field.ty {
+                            FieldType::Optional(_) => FieldType::Optional(Box::new(reference)),
+                            _ => reference,
+                        };
+                    }
+                }
+                field
+            })
+            .collect()
+    }
+
+    /// the type of the definition `name`. `None` if there is no such top level field.
+    pub(crate) fn get(&self, name: &str) -> Option<&FieldType> {
+        self.types
+            .iter()
+            .find(|(definition, _)| definition == name)
+            .map(|(_, ty)| ty)
+    }
+
+    /// whether the object `fields` is the definition `name` itself, rather than another object in its place.
+    pub(crate) fn defines(&self, name: &str, fields: &[Field]) -> bool {
+        matches!(self.get(name), Some(FieldType::Object(definition)) if definition == fields)
+    }
+
+    /// name of the type generated for the definition.
+    pub(crate) fn generated(&self, name: &str) -> Option<String> {
+        self.generated
+            .iter()
+            .find(|(definition, _)| definition == name)
+            .map(|(_, type_name)| type_name.clone())
+    }
+
+    /// to be called before generating the members of the type, so that they can refer to it.
+    pub(crate) fn insert(&mut self, name: &str, type_name: String) {
+        self.generated.push((name.into(), type_name));
+    }
+
+    /// whether the type of the definition is being generated, ie: it refers to itself.
+    pub(crate) fn resolving(&self, name: &str) -> bool {
+        self.resolving.iter().any(|definition| definition == name)
+    }
+
+    pub(crate) fn enter(&mut self, name: &str) {
+        self.resolving.push(name.into());
+    }
+
+    pub(crate) fn exit(&mut self) {
+        self.resolving.pop();
+    }
+}
+
+/// number of the next `Unknown0`, `unknown1`, ... name.
+fn unknown_number(iota: &mut Iota) -> Result<usize, CodegenError> {
+    iota.checked_next().ok_or(CodegenError::NamesExhausted)
+}
+
+pub fn to_pascal_case_or_unknown(text: &str, iota: &mut Iota) -> Result<String, CodegenError> {
+    let text = clean(text);
+    match text.is_empty() {
+        true => Ok(format!("Unknown{}", unknown_number(iota)?)),
+        false => Ok(text.to_case(Case::Pascal)),
+    }
 }
 
-pub fn to_pascal_case_or_unknown(text: &str, iota: &mut Iota) -> String {
+pub fn to_camel_case_or_unknown(text: &str, iota: &mut Iota) -> Result<String, CodegenError> {
     let text = clean(text);
     match text.is_empty() {
-        true => format!("Unknown{}", iota.get()),
-        false => text.to_case(Case::Pascal),
+        true => Ok(format!("unknown{}", unknown_number(iota)?)),
+        false => Ok(text.to_case(Case::Camel)),
     }
 }
 
-pub fn to_camel_case_or_unknown(text: &str, iota: &mut Iota) -> String {
+pub fn to_snake_case_or_unknown(text: &str, iota: &mut Iota) -> Result<String, CodegenError> {
     let text = clean(text);
     match text.is_empty() {
-        true => format!("unknown{}", iota.get()),
-        false => text.to_case(Case::Camel),
+        true => Ok(format!("unknown_{}", unknown_number(iota)?)),
+        false => Ok(text.to_case(Case::Snake)),
     }
 }
 
-pub fn to_snake_case_or_unknown(text: &str, iota: &mut Iota) -> String {
+pub fn to_screaming_snake_case_or_unknown(
+    text: &str,
+    iota: &mut Iota,
+) -> Result<String, CodegenError> {
     let text = clean(text);
     match text.is_empty() {
-        true => format!("unknown_{}", iota.get()),
-        false => text.to_case(Case::Snake),
+        true => Ok(format!("UNKNOWN_{}", unknown_number(iota)?)),
+        false => Ok(text.to_case(Case::UpperSnake)),
     }
 }
 
@@ -77,35 +470,184 @@ fn trim_leading_digits<'s>(segments: &[&'s str]) -> Vec<&'s str> {
     }
 }
 
+/// name of the elements of a top level array.
+/// the singular of the root name (eg: "Users" -> "User"), or "Item" if there is none.
+fn root_element_name(root_name: Option<&str>) -> String {
+    match root_name.map(singular) {
+        Some(element) if Some(element.as_str()) != root_name => element,
+        _ => "Item".into(),
+    }
+}
+
+/// name of a single element of an array field (eg: "children" -> "child", "users" -> "user").
+/// only simple english rules are applied. words that don't look plural are left as is.
+fn singular(text: &str) -> String {
+    const IRREGULAR: [(&str, &str); 8] = [
+        ("children", "child"),
+        ("people", "person"),
+        ("women", "woman"),
+        ("men", "man"),
+        ("mice", "mouse"),
+        ("geese", "goose"),
+        ("teeth", "tooth"),
+        ("feet", "foot"),
+    ];
+
+    let lower = text.to_ascii_lowercase();
+
+    for (plural, singular) in IRREGULAR {
+        let Some(prefix) = lower.strip_suffix(plural) else {
+            continue;
+        };
+        let (prefix, suffix) = text.split_at(prefix.len());
+        // only whole words. "specimen" is not the plural of "speciman".
+        let word_boundary = prefix.is_empty()
+            || prefix.ends_with(|c: char| !c.is_ascii_alphanumeric())
+            || (prefix.ends_with(|c: char| c.is_ascii_lowercase())
+                && suffix.starts_with(|c: char| c.is_ascii_uppercase()));
+        if word_boundary {
+            let singular = match (
+                suffix.chars().all(|c| c.is_ascii_uppercase()),
+                suffix.starts_with(|c: char| c.is_ascii_uppercase()),
+            ) {
+                (true, _) => singular.to_ascii_uppercase(),
+                (false, true) => singular[..1].to_ascii_uppercase() + &singular[1..],
+                (false, false) => singular.into(),
+            };
+            return format!("{prefix}{singular}");
+        }
+    }
+
+    let strip = |n: usize, replacement: &str| {
+        let replacement = match text.ends_with(|c: char| c.is_ascii_uppercase()) {
+            true => replacement.to_ascii_uppercase(),
+            false => replacement.into(),
+        };
+        format!("{}{}", &text[..text.len() - n], replacement)
+    };
+
+    if lower.len() > 4 && lower.ends_with("ies") {
+        return strip(3, "y");
+    }
+    if ["sses", "shes", "ches", "xes", "zes"]
+        .iter()
+        .any(|suffix| lower.ends_with(suffix))
+    {
+        return strip(2, "");
+    }
+    if lower.len() > 1
+        && lower.ends_with('s')
+        && !["ss", "us", "is"]
+            .iter()
+            .any(|suffix| lower.ends_with(suffix))
+    {
+        return strip(1, "");
+    }
+
+    text.into()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use pretty_assertions::assert_eq;
 
+    #[test]
+    fn dispatch() {
+        let schema = || crate::schema::extract(serde_json::json!({"a": 1}));
+        let mut out = vec![];
+        super::dispatch(
+            "Rust",
+            schema(),
+            serde_json::json!({"root_name": "Point"}),
+            &mut out,
+        )
+        .unwrap();
+        assert!(String::from_utf8(out)
+            .unwrap()
+            .contains("pub struct Point {"));
+
+        for lang in ["rust", "java"] {
+            let mut out = vec![];
+            super::dispatch(
+                lang,
+                schema(),
+                serde_json::json!({"manifest": true}),
+                &mut out,
+            )
+            .unwrap();
+            assert!(
+                String::from_utf8(out)
+                    .unwrap()
+                    .ends_with("// manifest.json\n{\n  \"Root\": \"$\"\n}\n"),
+                "{lang}"
+            );
+        }
+
+        let err = super::dispatch("go", schema(), Value::Null, &mut vec![]).unwrap_err();
+        assert_eq!(err.to_string(), r#"no generator for "go""#);
+        assert!(matches!(
+            super::dispatch(
+                "java",
+                schema(),
+                serde_json::json!({"floats": 1}),
+                &mut vec![]
+            ),
+            Err(CodegenError::InvalidOptions(_))
+        ));
+    }
+
+    #[test]
+    fn iota() {
+        let mut iota = Iota::new();
+        assert_eq!(iota.get(), 0);
+        assert_eq!(iota.checked_next(), Some(1));
+        assert_eq!(iota.peek(), 2);
+        iota.reset();
+        assert_eq!(iota.get(), 0);
+
+        let mut iota = Iota { n: usize::MAX };
+        assert_eq!(iota.checked_next(), None);
+        assert_eq!(iota.peek(), usize::MAX);
+        assert!(matches!(
+            to_snake_case_or_unknown("", &mut iota),
+            Err(CodegenError::NamesExhausted)
+        ));
+        assert_eq!(to_snake_case_or_unknown("a", &mut iota).unwrap(), "a");
+        assert_eq!(iota.get(), usize::MAX);
+        assert_eq!(iota.get(), 0);
+    }
+
     struct TestCase<'a> {
         input: &'a str,
         pascal: &'a str,
         camel: &'a str,
         snake: &'a str,
+        screaming_snake: &'a str,
     }
 
     impl<'a> TestCase<'a> {
         fn assert(self) {
             assert_eq!(
                 self.pascal,
-                to_pascal_case_or_unknown(self.input, &mut Iota::new()),
+                to_pascal_case_or_unknown(self.input, &mut Iota::new()).unwrap(),
                 "mismatch pascal"
             );
             assert_eq!(
                 self.camel,
-                to_camel_case_or_unknown(self.input, &mut Iota::new()),
+                to_camel_case_or_unknown(self.input, &mut Iota::new()).unwrap(),
                 "mismatch camel"
             );
             assert_eq!(
                 self.snake,
-                to_snake_case_or_unknown(self.input, &mut Iota::new()),
+                to_snake_case_or_unknown(self.input, &mut Iota::new()).unwrap(),
                 "mismatch snake"
             );
+            assert_eq!(
+                self.screaming_snake,
+                to_screaming_snake_case_or_unknown(self.input, &mut Iota::new()).unwrap(),
+                "mismatch screaming snake"
+            );
         }
     }
 
@@ -116,6 +658,7 @@ mod tests {
             pascal: "Basic",
             camel: "basic",
             snake: "basic",
+            screaming_snake: "BASIC",
         }
         .assert();
 
@@ -124,6 +667,7 @@ mod tests {
             pascal: "RubberDuck",
             camel: "rubberDuck",
             snake: "rubber_duck",
+            screaming_snake: "RUBBER_DUCK",
         }
         .assert();
 
@@ -132,6 +676,7 @@ mod tests {
             pascal: "RubberDuck",
             camel: "rubberDuck",
             snake: "rubber_duck",
+            screaming_snake: "RUBBER_DUCK",
         }
         .assert();
 
@@ -140,6 +685,7 @@ mod tests {
             pascal: "RubberDuck",
             camel: "rubberDuck",
             snake: "rubber_duck",
+            screaming_snake: "RUBBER_DUCK",
         }
         .assert();
 
@@ -148,6 +694,7 @@ mod tests {
             pascal: "Unknown0",
             camel: "unknown0",
             snake: "unknown_0",
+            screaming_snake: "UNKNOWN_0",
         }
         .assert();
 
@@ -156,6 +703,7 @@ mod tests {
             pascal: "Unknown0",
             camel: "unknown0",
             snake: "unknown_0",
+            screaming_snake: "UNKNOWN_0",
         }
         .assert();
 
@@ -164,6 +712,7 @@ mod tests {
             pascal: "Unknown0",
             camel: "unknown0",
             snake: "unknown_0",
+            screaming_snake: "UNKNOWN_0",
         }
         .assert();
 
@@ -172,6 +721,7 @@ mod tests {
             pascal: "SpacesBetween",
             camel: "spacesBetween",
             snake: "spaces_between",
+            screaming_snake: "SPACES_BETWEEN",
         }
         .assert();
 
@@ -180,6 +730,7 @@ mod tests {
             pascal: "Digits",
             camel: "digits",
             snake: "digits",
+            screaming_snake: "DIGITS",
         }
         .assert();
 
@@ -188,6 +739,7 @@ mod tests {
             pascal: "Digits",
             camel: "digits",
             snake: "digits",
+            screaming_snake: "DIGITS",
         }
         .assert();
 
@@ -196,7 +748,33 @@ mod tests {
             pascal: "Foo8833",
             camel: "foo8833",
             snake: "foo_88_33",
+            screaming_snake: "FOO_88_33",
         }
         .assert();
     }
+
+    #[test]
+    fn singular() {
+        for (plural, singular) in [
+            ("users", "user"),
+            ("children", "child"),
+            ("grandChildren", "grandChild"),
+            ("CHILDREN", "CHILD"),
+            ("specimen", "specimen"),
+            ("categories", "category"),
+            ("ties", "tie"),
+            ("addresses", "address"),
+            ("boxes", "box"),
+            ("matches", "match"),
+            ("user_ids", "user_id"),
+            ("ITEMS", "ITEM"),
+            ("address", "address"),
+            ("status", "status"),
+            ("analysis", "analysis"),
+            ("data", "data"),
+            ("s", "s"),
+        ] {
+            assert_eq!(singular, super::singular(plural), "{plural}");
+        }
+    }
 }
@@ -1,73 +1,822 @@
-use super::{to_pascal_case_or_unknown, to_snake_case_or_unknown, Iota};
-use crate::schema::{Field, FieldType, Schema};
-use std::io::{Error, Write};
+use super::{
+    root_element_name, singular, to_pascal_case_or_unknown, to_screaming_snake_case_or_unknown,
+    to_snake_case_or_unknown, CodegenError, Definitions, IndentStyle, Iota, SharedObjects, Sink,
+};
+use crate::schema::{path_segment, Field, FieldType, Schema, StringFormat};
+use convert_case::{Case, Casing};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{Error, Write},
+    path::Path,
+};
 
-pub fn rust<W: Write>(schema: Schema, out: &mut W) -> Result<(), Error> {
-    let mut ctx = Context::new();
+/// can be deserialized from json (eg: `{"indent": {"spaces": 2}}`).
+/// missing keys keep their defaults and unknown keys are ignored.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RustOptions {
+    pub indent: IndentStyle,
+    /// generate a single struct for structurally identical objects.
+    /// a shape reached through different field names gets a neutral name (eg: `FromOrTo`)
+    pub reuse_identical_objects: bool,
+    /// `impl From<Inner> for Enum` and `impl TryFrom<Enum> for Inner` for every enum variant.
+    /// skipped for variants whose inner type is shared with another variant of the same enum.
+    pub variant_conversions: bool,
+    /// nest the types generated for an object in a `mod` named after its parent type
+    /// (eg: `user::Address` instead of a flat `Address`).
+    /// types shared by several parents stay at the top level.
+    pub nested_modules: bool,
+    /// name of the type generated for the top level value. `Root` when `None`.
+    pub root_name: Option<String>,
+    /// `#[non_exhaustive]` on every enum, so that matches in other crates
+    /// keep compiling when a new variant shows up.
+    pub non_exhaustive_enums: bool,
+    /// `#[non_exhaustive]` on every struct, so that other crates can't construct them
+    /// with struct literals and keep compiling when a new field shows up.
+    pub non_exhaustive_structs: bool,
+    /// `#[serde(deny_unknown_fields)]` on every struct, so that keys missing from the samples
+    /// are an error instead of being ignored.
+    pub deny_unknown_fields: bool,
+    /// a `#[cfg(test)]` module that round trips `input.json` through the root type.
+    /// the json file must be next to the generated file, as it is read with `include_str!`.
+    pub generate_tests: bool,
+    /// visibility of the generated types, aliases and modules.
+    /// child modules of `nested_modules` need at least `super` for their parent to see them.
+    pub type_visibility: Visibility,
+    /// visibility of the fields and constants of the generated structs.
+    pub field_visibility: Visibility,
+    /// fields that are an integer in some samples and a string in others (eg: ids sent as `42` or `"42"`)
+    /// become `isize` with `#[serde_as(as = "serde_with::PickFirst<(_, serde_with::DisplayFromStr)>")]`,
+    /// which accepts both, instead of an enum. the generated code depends on the `serde_with` crate.
+    /// base64 strings (see `SchemaInferenceOptions::detect_base64`) become `Vec<u8>`
+    /// with `serde_with::base64::Base64`, which needs its `base64` feature.
+    pub use_serde_with: bool,
+    /// an associated const with the json key of every string and integer field
+    /// (eg: `pub const USER_ID: &'static str = "userId";`), to refer to the keys without string literals.
+    pub generate_constants: bool,
+    pub unknown_type: UnknownType,
+    /// `impl From<Root> for serde_json::Map<String, serde_json::Value>` and the reverse `TryFrom`
+    /// when the root is a struct. eg: to assemble the body of a PATCH request from typed fields.
+    /// `None` fields become `null` entries.
+    pub generate_map_conversion: bool,
+    /// pointer to a type from inside itself (eg: `next` of a linked list node from openapi `$ref`s).
+    pub recursive_wrapper: RecursiveWrapper,
+    /// a `manifest.json` file next to the code, mapping the path of every generated type
+    /// to the json path it was derived from. eg: `{"Root": "$", "user::Address": "$.user.address"}`
+    /// only written by `rust_files`, as `rust` writes nothing but the code.
+    pub manifest: bool,
+}
+
+/// eg: `"arc"` in json.
+/// `Arc` and `Rc` fields need the `rc` feature of serde to be (de)serialized.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecursiveWrapper {
+    /// `Box<T>`
+    #[default]
+    Box,
+    /// `Arc<T>`, to share the parsed data across threads.
+    Arc,
+    /// `Rc<T>`, for reference counted graphs.
+    Rc,
+}
+
+impl RecursiveWrapper {
+    fn wrap(self, type_name: &str) -> String {
+        match self {
+            RecursiveWrapper::Box => format!("Box<{type_name}>"),
+            RecursiveWrapper::Arc => format!("Arc<{type_name}>"),
+            RecursiveWrapper::Rc => format!("Rc<{type_name}>"),
+        }
+    }
+
+    /// `None` for `Box`, which is in the prelude.
+    fn import(self) -> Option<&'static str> {
+        match self {
+            RecursiveWrapper::Box => None,
+            RecursiveWrapper::Arc => Some("use std::sync::Arc;"),
+            RecursiveWrapper::Rc => Some("use std::rc::Rc;"),
+        }
+    }
+}
+
+/// type of the values that were `null` in every sample, so nothing is known about them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnknownType {
+    /// `serde_json::Value`, which can hold anything the field turns out to have.
+    #[default]
+    JsonValue,
+    /// `()`, for fields that are always `null` and never carry data.
+    Unit,
+    /// fields are left out of (de)serialization with `#[serde(skip)]` and typed `std::marker::PhantomData<()>`.
+    /// their keys are rejected by `deny_unknown_fields`.
+    /// other values (eg: array elements) stay `serde_json::Value`.
+    Skip,
+}
+
+/// eg: `"crate"` or `{"module": "crate::api"}` in json.
+/// parsed from rust syntax (eg: `pub(crate)`) by `str::parse`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Visibility {
+    /// `pub`
+    #[default]
+    Public,
+    /// `pub(crate)`
+    Crate,
+    /// `pub(super)`
+    Super,
+    /// `pub(in path)`
+    Module(String),
+    /// no visibility keyword
+    Private,
+}
+
+impl Visibility {
+    /// the keyword followed by a space. empty for `Private`.
+    fn keyword(&self) -> String {
+        match self {
+            Visibility::Public => "pub ".into(),
+            Visibility::Crate => "pub(crate) ".into(),
+            Visibility::Super => "pub(super) ".into(),
+            Visibility::Module(path) => format!("pub(in {path}) "),
+            Visibility::Private => "".into(),
+        }
+    }
+}
+
+impl std::str::FromStr for Visibility {
+    type Err = String;
+
+    /// `pub`, `pub(crate)`, `pub(super)`, `pub(in path)` or `private`.
+    /// whitespace is allowed around the tokens, like in rust. eg: `pub( crate )`
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let error = || {
+            format!("expected pub, pub(crate), pub(super), pub(in path) or private. got {text:?}")
+        };
+        let trimmed = text.trim();
+        if trimmed == "private" {
+            return Ok(Visibility::Private);
+        }
+        let restriction = match trimmed.strip_prefix("pub").map(str::trim_start) {
+            Some("") => return Ok(Visibility::Public),
+            Some(rest) => rest
+                .strip_prefix('(')
+                .and_then(|rest| rest.strip_suffix(')'))
+                .map(str::trim)
+                .ok_or_else(error)?,
+            None => return Err(error()),
+        };
+        match restriction {
+            "crate" => Ok(Visibility::Crate),
+            "super" => Ok(Visibility::Super),
+            _ => match restriction
+                .strip_prefix("in")
+                .filter(|path| path.starts_with(char::is_whitespace))
+                .map(str::trim_start)
+            {
+                Some(path)
+                    if !path.is_empty()
+                        && path
+                            .chars()
+                            .all(|c| c.is_alphanumeric() || c == '_' || c == ':') =>
+                {
+                    Ok(Visibility::Module(path.into()))
+                }
+                _ => Err(error()),
+            },
+        }
+    }
+}
+
+impl std::str::FromStr for RecursiveWrapper {
+    type Err = String;
+
+    /// `box`, `arc` or `rc`, in any case.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text.to_ascii_lowercase().as_str() {
+            "box" => Ok(RecursiveWrapper::Box),
+            "arc" => Ok(RecursiveWrapper::Arc),
+            "rc" => Ok(RecursiveWrapper::Rc),
+            _ => Err(format!("expected box, arc or rc. got {text:?}")),
+        }
+    }
+}
+
+/// read the json at `input`, infer its schema with the default options and write the rust code to `output`.
+/// meant for `build.rs` scripts, to `include!` the types of a json fixture:
+///
+/// ```no_run
+/// let out = std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("fixture.rs");
+/// jsoncodegen::codegen::rust_file(
+///     "fixture.json".as_ref(),
+///     &out,
+///     &jsoncodegen::codegen::RustOptions::default(),
+/// )
+/// .unwrap();
+/// println!("cargo:rerun-if-changed=fixture.json");
+/// ```
+///
+/// the parent directories of `output` are created.
+/// `output` is left untouched if it already has the generated code, so its modification time doesn't trigger rebuilds.
+pub fn rust_file(input: &Path, output: &Path, options: &RustOptions) -> Result<(), CodegenError> {
+    let json: Value =
+        serde_json::from_slice(&fs::read(input)?).map_err(CodegenError::InvalidJson)?;
+    if !json.is_object() && !json.is_array() {
+        return Err(CodegenError::InvalidTopLevel);
+    }
+    let mut code = vec![];
+    rust(crate::schema::extract(json), options, &mut code)?;
+
+    if fs::read(output).is_ok_and(|existing| existing == code) {
+        return Ok(());
+    }
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    Ok(fs::write(output, code)?)
+}
+
+pub fn rust<W: Write>(
+    schema: Schema,
+    options: &RustOptions,
+    out: &mut W,
+) -> Result<(), CodegenError> {
+    rust_code(schema, options, out)?;
+    Ok(())
+}
+
+/// the code as `root.rs` and, with `RustOptions::manifest`, the `manifest.json` of its types.
+pub fn rust_files<S: Sink>(
+    schema: Schema,
+    options: &RustOptions,
+    sink: &mut S,
+) -> Result<(), CodegenError> {
+    let manifest = rust_code(schema, options, &mut sink.sink("root.rs")?)?;
+
+    if options.manifest {
+        let mut out = sink.sink("manifest.json")?;
+        serde_json::to_writer_pretty(&mut out, &manifest).map_err(Error::from)?;
+        writeln!(out)?;
+    }
+
+    Ok(())
+}
+
+/// writes the code and returns the json path of every generated type. see `RustOptions::manifest`
+fn rust_code(
+    schema: Schema,
+    options: &RustOptions,
+    out: &mut dyn Write,
+) -> Result<BTreeMap<String, String>, CodegenError> {
+    let mut ctx = Context::new(
+        options
+            .reuse_identical_objects
+            .then(|| SharedObjects::new(&schema, options.root_name.as_deref())),
+        Definitions::new(&schema),
+        options,
+    )?;
+    let (i1, i2, i3) = (
+        options.indent.level(1),
+        options.indent.level(2),
+        options.indent.level(3),
+    );
+    let (type_vis, field_vis) = (
+        options.type_visibility.keyword(),
+        options.field_visibility.keyword(),
+    );
     writeln!(out, "use serde::{{Serialize, Deserialize}};")?;
 
     match schema {
-        Schema::Object(fields) => ctx.add_struct("Root".into(), fields),
+        Schema::Object(fields) if fields.is_empty() => ctx.add_alias(
+            ctx.root.clone(),
+            "std::collections::HashMap<String, serde_json::Value>".into(),
+        ),
+        Schema::Object(fields) => {
+            let fields = ctx.definitions.refer_to(fields);
+            ctx.add_struct(ctx.root.clone(), fields)?
+        }
         Schema::Array(ty) => {
+            ctx.path.push_str("[]");
             let struct_field = ctx.process_field(Field {
-                name: "Item".into(),
+                name: root_element_name(options.root_name.as_deref()),
                 ty,
-            });
-            ctx.add_alias("Root".into(), format!("Vec<{}>", struct_field.type_name));
+                description: None,
+                example: None,
+            })?;
+            ctx.path.truncate(ctx.path.len() - "[]".len());
+            ctx.add_alias(ctx.root.clone(), format!("Vec<{}>", struct_field.type_name));
         }
     };
 
+    // every definition is rendered on its own along with the module it belongs to.
+    let mut items: Vec<(Vec<String>, Vec<u8>)> = vec![];
+
+    // by name rather than in the order they were found, so that a small change in the json
+    // doesn't move the definitions around.
+    ctx.aliases.sort_by(|a, b| a.name.cmp(&b.name));
+    ctx.structs.sort_by(|a, b| a.name.cmp(&b.name));
+    ctx.enums.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let full_path = |module: &[String], name: &str| {
+        let mut path = module.to_vec();
+        path.push(name.into());
+        path.join("::")
+    };
+    let manifest: BTreeMap<String, String> = ctx
+        .aliases
+        .iter()
+        .map(|def| (full_path(&def.module, &def.name), def.path.clone()))
+        .chain(
+            ctx.structs
+                .iter()
+                .map(|def| (full_path(&def.module, &def.name), def.path.clone())),
+        )
+        .chain(
+            ctx.enums
+                .iter()
+                .map(|def| (full_path(&def.module, &def.name), def.path.clone())),
+        )
+        .collect();
+
     for def in ctx.aliases {
-        writeln!(out, "pub type {} = {};", def.name, def.ty)?;
+        let mut out = vec![];
+        writeln!(out, "{type_vis}type {} = {};", def.name, def.ty)?;
+        items.push((def.module, out));
     }
 
     for def in ctx.structs {
+        let mut out = vec![];
+        if def.fields.iter().any(|field| field.serde_as.is_some()) {
+            writeln!(out, "#[serde_with::serde_as]")?;
+        }
         writeln!(out, "#[derive(Serialize, Deserialize, Debug)]")?;
-        writeln!(out, "pub struct {} {{", def.name)?;
-        for field in def.fields {
-            if field.original_name != field.variable_name {
-                writeln!(out, "    #[serde(rename = \"{}\")]", field.original_name)?;
+        if options.deny_unknown_fields {
+            writeln!(out, "#[serde(deny_unknown_fields)]")?;
+        }
+        if options.non_exhaustive_structs {
+            writeln!(out, "#[non_exhaustive]")?;
+        }
+        writeln!(out, "{type_vis}struct {} {{", def.name)?;
+        for field in &def.fields {
+            let name = field.variable_name.trim_start_matches("r#");
+            for line in field.description.iter().flat_map(|text| text.lines()) {
+                writeln!(out, "{}", format!("{i1}/// {line}").trim_end())?;
+            }
+            if field.skip {
+                writeln!(out, "{i1}#[serde(skip)]")?;
+            } else if field.original_name != name {
+                writeln!(out, "{i1}#[serde(rename = {:?})]", field.original_name)?;
+            }
+            if field.constant.is_some() {
+                writeln!(
+                    out,
+                    "{i1}#[serde(default = \"{}::default_{name}\")]",
+                    def.name
+                )?;
             }
-            writeln!(out, "    pub {}: {},", field.variable_name, field.type_name)?;
+            if let Some(serde_as) = &field.serde_as {
+                writeln!(out, "{i1}#[serde_as(as = {serde_as:?})]")?;
+            }
+            writeln!(
+                out,
+                "{i1}{field_vis}{}: {},",
+                field.variable_name, field.type_name
+            )?;
         }
         writeln!(out, "}}")?;
+
+        // fields with the same value in every sample get an associated const,
+        // which is also their value when missing.
+        let constants: Vec<_> = def
+            .fields
+            .iter()
+            .filter_map(|field| Some((field, field.constant.as_ref()?)))
+            .collect();
+        let keys: Vec<&StructField> = def
+            .fields
+            .iter()
+            .filter(|field| field.key_constant)
+            .collect();
+        if !constants.is_empty() || !keys.is_empty() {
+            writeln!(out, "impl {} {{", def.name)?;
+            for (field, constant) in &constants {
+                let const_type = match field.type_name.as_str() {
+                    "String" => "&'static str",
+                    type_name => type_name,
+                };
+                writeln!(
+                    out,
+                    "{i1}{field_vis}const {}: {const_type} = {constant};",
+                    const_name(field)?
+                )?;
+            }
+            for field in &keys {
+                // the value of a constant field already has its name.
+                let suffix = match field.constant {
+                    Some(_) => "_KEY",
+                    None => "",
+                };
+                writeln!(
+                    out,
+                    "{i1}{field_vis}const {}{suffix}: &'static str = {:?};",
+                    const_name(field)?,
+                    field.original_name
+                )?;
+            }
+            for (field, _) in &constants {
+                let name = field.variable_name.trim_start_matches("r#");
+                let into = match field.type_name.as_str() {
+                    "String" => ".into()",
+                    _ => "",
+                };
+                writeln!(out, "{i1}fn default_{name}() -> {} {{", field.type_name)?;
+                writeln!(out, "{i2}Self::{}{into}", const_name(field)?)?;
+                writeln!(out, "{i1}}}")?;
+            }
+            writeln!(out, "}}")?;
+        }
+
+        if options.generate_map_conversion && def.name == ctx.root {
+            let map = "serde_json::Map<String, serde_json::Value>";
+            // qualified like the variant conversions.
+            writeln!(out, "impl ::core::convert::From<{}> for {map} {{", def.name)?;
+            writeln!(out, "{i1}fn from(value: {}) -> Self {{", def.name)?;
+            writeln!(out, "{i2}match serde_json::to_value(value) {{")?;
+            writeln!(
+                out,
+                "{i3}::core::result::Result::Ok(serde_json::Value::Object(map)) => map,"
+            )?;
+            writeln!(
+                out,
+                "{i3}_ => unreachable!(\"a struct serializes to a json object\"),"
+            )?;
+            writeln!(out, "{i2}}}")?;
+            writeln!(out, "{i1}}}")?;
+            writeln!(out, "}}")?;
+
+            writeln!(
+                out,
+                "impl ::core::convert::TryFrom<{map}> for {} {{",
+                def.name
+            )?;
+            writeln!(out, "{i1}type Error = serde_json::Error;")?;
+            writeln!(
+                out,
+                "{i1}fn try_from(map: {map}) -> ::core::result::Result<Self, Self::Error> {{"
+            )?;
+            writeln!(
+                out,
+                "{i2}serde_json::from_value(serde_json::Value::Object(map))"
+            )?;
+            writeln!(out, "{i1}}}")?;
+            writeln!(out, "}}")?;
+        }
+        items.push((def.module, out));
     }
 
     for def in ctx.enums {
+        let mut out = vec![];
+        if def
+            .variants
+            .iter()
+            .any(|variant| variant.serde_as.is_some())
+        {
+            writeln!(out, "#[serde_with::serde_as]")?;
+        }
         writeln!(out, "#[derive(Serialize, Deserialize, Debug)]")?;
-        writeln!(out, "pub enum {} {{", def.name)?;
-        for variant in def.variants {
+        // the json has no tag, the variant is told apart by the shape of the value.
+        writeln!(out, "#[serde(untagged)]")?;
+        if options.non_exhaustive_enums {
+            writeln!(out, "#[non_exhaustive]")?;
+        }
+        writeln!(out, "{type_vis}enum {} {{", def.name)?;
+        for variant in &def.variants {
+            let serde_as = match &variant.serde_as {
+                Some(serde_as) => format!("#[serde_as(as = {serde_as:?})] "),
+                None => String::new(),
+            };
             writeln!(
                 out,
-                "    {}({}),",
+                "{i1}{}({serde_as}{}),",
                 variant.variant_name, variant.associated_type
             )?;
         }
         writeln!(out, "}}")?;
+
+        if options.variant_conversions {
+            for variant in &def.variants {
+                let ambiguous = def
+                    .variants
+                    .iter()
+                    .filter(|v| v.associated_type == variant.associated_type)
+                    .count()
+                    > 1;
+                if ambiguous {
+                    continue;
+                }
+
+                // qualified, as the structs generated for keys like `from` or `result` would
+                // shadow the prelude.
+                writeln!(
+                    out,
+                    "impl ::core::convert::From<{}> for {} {{",
+                    variant.associated_type, def.name
+                )?;
+                writeln!(
+                    out,
+                    "{i1}fn from(value: {}) -> Self {{",
+                    variant.associated_type
+                )?;
+                writeln!(out, "{i2}Self::{}(value)", variant.variant_name)?;
+                writeln!(out, "{i1}}}")?;
+                writeln!(out, "}}")?;
+
+                writeln!(
+                    out,
+                    "impl ::core::convert::TryFrom<{}> for {} {{",
+                    def.name, variant.associated_type
+                )?;
+                writeln!(out, "{i1}type Error = {};", def.name)?;
+                writeln!(
+                    out,
+                    "{i1}fn try_from(value: {}) -> ::core::result::Result<Self, Self::Error> {{",
+                    def.name
+                )?;
+                writeln!(out, "{i2}match value {{")?;
+                writeln!(
+                    out,
+                    "{i3}{}::{}(inner) => ::core::result::Result::Ok(inner),",
+                    def.name, variant.variant_name
+                )?;
+                writeln!(out, "{i3}other => ::core::result::Result::Err(other),")?;
+                writeln!(out, "{i2}}}")?;
+                writeln!(out, "{i1}}}")?;
+                writeln!(out, "}}")?;
+            }
+        }
+        items.push((def.module, out));
+    }
+
+    // only where a recursive type is wrapped, so that there are no unused imports.
+    let import = |module: &[String]| {
+        options
+            .recursive_wrapper
+            .import()
+            .filter(|_| ctx.wrapped_in.iter().any(|m| m == module))
+    };
+    match options.nested_modules {
+        true => write_module(out, &[], &items, options.indent, &type_vis, &import)?,
+        false => {
+            if let Some(import) = import(&[]) {
+                writeln!(out, "{import}")?;
+            }
+            items.iter().try_for_each(|(_, item)| out.write_all(item))?
+        }
+    }
+
+    if options.generate_tests {
+        // not `tests`, which a nested module of a `Tests` struct could be named.
+        writeln!(out, "#[cfg(test)]")?;
+        writeln!(out, "mod roundtrip_tests {{")?;
+        writeln!(out, "{i1}use super::*;")?;
+        writeln!(out, "{i1}#[test]")?;
+        writeln!(
+            out,
+            "{i1}fn roundtrip_{}() {{",
+            ctx.root.to_case(Case::Snake)
+        )?;
+        writeln!(out, "{i2}let json = include_str!(\"input.json\");")?;
+        writeln!(
+            out,
+            "{i2}let parsed: {} = serde_json::from_str(json).unwrap();",
+            ctx.root
+        )?;
+        writeln!(
+            out,
+            "{i2}let reserialized = serde_json::to_string(&parsed).unwrap();"
+        )?;
+        writeln!(
+            out,
+            "{i2}let reparsed: {} = serde_json::from_str(&reserialized).unwrap();",
+            ctx.root
+        )?;
+        writeln!(
+            out,
+            "{i2}assert_eq!(serde_json::to_string(&reparsed).unwrap(), reserialized);"
+        )?;
+        writeln!(out, "{i1}}}")?;
+        writeln!(out, "}}")?;
+    }
+
+    Ok(manifest)
+}
+
+/// definitions of `module` followed by its child modules, indented by nesting depth.
+fn write_module(
+    out: &mut dyn Write,
+    module: &[String],
+    items: &[(Vec<String>, Vec<u8>)],
+    indent: IndentStyle,
+    visibility: &str,
+    import: &dyn Fn(&[String]) -> Option<&'static str>,
+) -> Result<(), Error> {
+    let depth = module.len();
+    let pad = indent.level(depth);
+
+    if let Some(import) = import(module) {
+        writeln!(out, "{pad}{import}")?;
+    }
+
+    for (_, item) in items.iter().filter(|(m, _)| m == module) {
+        for line in String::from_utf8_lossy(item).lines() {
+            writeln!(out, "{pad}{line}")?;
+        }
+    }
+
+    let mut children: Vec<&String> = vec![];
+    for (m, _) in items {
+        if m.len() > depth && m.starts_with(module) && !children.contains(&&m[depth]) {
+            children.push(&m[depth]);
+        }
+    }
+
+    for child in children {
+        writeln!(out, "{pad}{visibility}mod {child} {{")?;
+        writeln!(
+            out,
+            "{}use serde::{{Serialize, Deserialize}};",
+            indent.level(depth + 1)
+        )?;
+        let mut path = module.to_vec();
+        path.push(child.clone());
+        write_module(out, &path, items, indent, visibility, import)?;
+        writeln!(out, "{pad}}}")?;
     }
 
     Ok(())
 }
 
+/// strict and reserved keywords. these can't be used as bare identifiers.
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// keywords that can't be used as raw identifiers either.
+const NON_RAW_KEYWORDS: &[&str] = &["self", "Self", "super", "crate"];
+
+/// enum variant names of the primitive types. other variants are suffixed instead of reusing them.
+const PRIMITIVE_VARIANTS: [&str; 7] = [
+    "String",
+    "Integer",
+    "BigInteger",
+    "Float",
+    "Boolean",
+    "Unknown",
+    "Bytes",
+];
+
+/// string or integer, possibly optional. see `RustOptions::generate_constants`
+fn is_key_type(ty: &FieldType) -> bool {
+    match ty {
+        FieldType::String | FieldType::Integer | FieldType::Format(_) => true,
+        FieldType::Optional(ty) | FieldType::Constant(ty, _) => is_key_type(ty),
+        _ => false,
+    }
+}
+
+/// union of exactly an integer and a string. eg: ids sent as `42` or `"42"`
+fn is_integer_or_string(types: &[FieldType]) -> bool {
+    types.len() == 2 && types.contains(&FieldType::Integer) && types.contains(&FieldType::String)
+}
+
+fn is_primitive(ty: &FieldType) -> bool {
+    match ty {
+        FieldType::String
+        | FieldType::Integer
+        | FieldType::BigInteger
+        | FieldType::Float
+        | FieldType::Boolean
+        | FieldType::Unknown
+        | FieldType::Format(_) => true,
+        FieldType::Constant(ty, _) => is_primitive(ty),
+        _ => false,
+    }
+}
+
+/// snake_case field name that is a valid rust identifier.
+/// keywords are emitted as raw identifiers (eg: `r#type`)
+/// except the ones that can't be raw, which get a trailing underscore (eg: `self_`)
+fn field_name(text: &str, iota: &mut Iota) -> Result<String, CodegenError> {
+    Ok(escape_keyword(to_snake_case_or_unknown(text, iota)?))
+}
+
+/// `variable_name`, or with a numeric suffix (eg: `user_name2`) if another field of the struct has it.
+/// eg: "userName" and "user_name", "self" and "self_", or "123foo" and "fooあ" which are both `foo`.
+/// `#[serde(rename)]` is always emitted for them because the name no longer matches the key.
+fn unique_field_name(fields: &[StructField], variable_name: String) -> String {
+    let taken = |name: &str| {
+        fields
+            .iter()
+            .any(|field| field.variable_name.trim_start_matches("r#") == name)
+    };
+
+    let base = variable_name.trim_start_matches("r#");
+    if !taken(base) {
+        return variable_name;
+    }
+
+    let mut n = 2;
+    while taken(&format!("{}{}", base, n)) {
+        n += 1;
+    }
+    format!("{}{}", base, n)
+}
+
+/// PascalCase type name that is a valid rust identifier.
+fn type_name(text: &str, iota: &mut Iota) -> Result<String, CodegenError> {
+    Ok(escape_keyword(to_pascal_case_or_unknown(text, iota)?))
+}
+
+/// SCREAMING_SNAKE_CASE name of an associated const of the field.
+fn const_name(field: &StructField) -> Result<String, CodegenError> {
+    to_screaming_snake_case_or_unknown(
+        field.variable_name.trim_start_matches("r#"),
+        &mut Iota::new(),
+    )
+}
+
+/// `Address` of `super::user::Address`.
+fn last_segment(path: &str) -> String {
+    path.rsplit("::").next().unwrap_or(path).into()
+}
+
+fn escape_keyword(ident: String) -> String {
+    match (
+        NON_RAW_KEYWORDS.contains(&ident.as_str()),
+        KEYWORDS.contains(&ident.as_str()),
+    ) {
+        (true, _) => ident + "_",
+        (false, true) => format!("r#{}", ident),
+        (false, false) => ident,
+    }
+}
+
 struct Context {
     aliases: Vec<AliasDef>,
     structs: Vec<StructDef>,
     enums: Vec<EnumDef>,
     iota: Iota,
+    shared_objects: Option<SharedObjects>,
+    definitions: Definitions,
+    /// the field being processed is the element of a `Vec` or a map, which are pointers already.
+    in_collection: bool,
+    /// full paths (eg: `user::Address`) of the generated types.
+    type_names: Vec<String>,
+    nested_modules: bool,
+    /// module of the definition whose fields or variants are being processed.
+    scope: Vec<String>,
+    /// module that new definitions are added to.
+    module: Vec<String>,
+    /// name of the top level type.
+    root: String,
+    use_serde_with: bool,
+    generate_constants: bool,
+    unknown_type: UnknownType,
+    recursive_wrapper: RecursiveWrapper,
+    /// modules with a type that points to itself through the `recursive_wrapper`.
+    wrapped_in: Vec<Vec<String>>,
+    /// json path of the value being processed. eg: `$.cart[].metadata`
+    path: String,
 }
 
 struct StructDef {
     name: String,
+    module: Vec<String>,
+    /// json path of the (first) object the struct is generated for.
+    path: String,
     fields: Vec<StructField>,
 }
 
 struct EnumDef {
     name: String,
+    module: Vec<String>,
+    path: String,
     variants: Vec<EnumVariant>,
 }
 
 struct AliasDef {
     name: String,
+    module: Vec<String>,
+    path: String,
     ty: String,
 }
 
@@ -75,191 +824,1767 @@ struct StructField {
     original_name: String,
     variable_name: String,
     type_name: String,
+    /// rust literal of the value the field has in every sample.
+    constant: Option<String>,
+    description: Option<String>,
+    /// `serde_with` adapter of the field. see `RustOptions::use_serde_with`
+    serde_as: Option<String>,
+    /// an associated const with the json key. see `RustOptions::generate_constants`
+    key_constant: bool,
+    /// `#[serde(skip)]`. see `UnknownType::Skip`
+    skip: bool,
 }
 
 struct EnumVariant {
     variant_name: String,
     associated_type: String,
+    /// `serde_with` adapter of the associated value.
+    serde_as: Option<String>,
 }
 
 impl Context {
-    fn new() -> Self {
-        Self {
+    fn new(
+        shared_objects: Option<SharedObjects>,
+        definitions: Definitions,
+        options: &RustOptions,
+    ) -> Result<Self, CodegenError> {
+        let mut ctx = Self {
             aliases: vec![],
             structs: vec![],
             enums: vec![],
             iota: Iota::new(),
+            shared_objects,
+            definitions,
+            in_collection: false,
+            type_names: vec![],
+            nested_modules: options.nested_modules,
+            scope: vec![],
+            module: vec![],
+            root: String::new(),
+            use_serde_with: options.use_serde_with,
+            generate_constants: options.generate_constants,
+            unknown_type: options.unknown_type,
+            recursive_wrapper: options.recursive_wrapper,
+            wrapped_in: vec![],
+            path: "$".into(),
+        };
+        ctx.root = ctx.unique_type_name(options.root_name.as_deref().unwrap_or("Root"))?;
+        Ok(ctx)
+    }
+
+    /// start processing the members of the definition `name`.
+    /// returns the previous `(scope, module)` to restore when done.
+    fn enter(&mut self, name: &str) -> Result<(Vec<String>, Vec<String>), CodegenError> {
+        let outer = (self.scope.clone(), self.module.clone());
+        self.scope = self.module.clone();
+        // the types of the root fields live at the top level.
+        if self.nested_modules && name != self.root {
+            self.module.push(field_name(name, &mut self.iota)?);
         }
+        Ok(outer)
+    }
+
+    /// full path of the type `name` in the current module.
+    fn full_path(&self, name: &str) -> String {
+        let mut path = self.module.clone();
+        path.push(name.into());
+        path.join("::")
+    }
+
+    /// path to the generated type from the current scope (eg: `super::user::Address`).
+    fn type_path(&self, full_path: &str) -> String {
+        let mut module: Vec<&str> = full_path.split("::").collect();
+        let name = module.pop().unwrap_or(full_path);
+
+        let common = self
+            .scope
+            .iter()
+            .zip(&module)
+            .take_while(|(a, b)| a == *b)
+            .count();
+        let mut path = vec!["super"; self.scope.len() - common];
+        path.extend(&module[common..]);
+        path.push(name);
+        path.join("::")
+    }
+
+    /// PascalCase type name that isn't used by any other generated struct or enum of the current module.
+    /// the same name in different places (eg: "address" in two objects) gets a numeric suffix.
+    fn unique_type_name(&mut self, text: &str) -> Result<String, CodegenError> {
+        let name = type_name(text, &mut self.iota)?;
+
+        let mut unique_name = name.clone();
+        let mut n = 2;
+        while self.type_names.contains(&self.full_path(&unique_name)) {
+            unique_name = format!("{}{}", name, n);
+            n += 1;
+        }
+
+        self.type_names.push(self.full_path(&unique_name));
+        Ok(unique_name)
     }
 
     fn add_alias(&mut self, name: String, ty: String) {
-        self.aliases.push(AliasDef { name, ty });
+        self.aliases.push(AliasDef {
+            name,
+            module: self.module.clone(),
+            path: self.path.clone(),
+            ty,
+        });
     }
 
-    fn add_struct(&mut self, name: String, fields: Vec<Field>) {
+    fn add_struct(&mut self, name: String, fields: Vec<Field>) -> Result<(), CodegenError> {
+        let module = self.module.clone();
+        let (scope, outer_module) = self.enter(&name)?;
+        let in_collection = std::mem::take(&mut self.in_collection);
         let mut def = StructDef {
             name,
+            module,
+            path: self.path.clone(),
             fields: vec![],
         };
 
         for field in fields {
-            def.fields.push(self.process_field(field));
+            let description = field.description.clone();
+            let serde_as = self.serde_as(&field.ty);
+            let key_constant = self.generate_constants && is_key_type(&field.ty);
+            let skip = self.unknown_type == UnknownType::Skip && field.ty == FieldType::Unknown;
+            let len = self.path.len();
+            self.path.push_str(&path_segment(&field.name));
+            let mut struct_field = self.process_field(field)?;
+            self.path.truncate(len);
+            struct_field.description = description;
+            struct_field.serde_as = serde_as;
+            struct_field.key_constant = key_constant;
+            if skip {
+                struct_field.type_name = "std::marker::PhantomData<()>".into();
+                struct_field.skip = true;
+            }
+            struct_field.variable_name = unique_field_name(&def.fields, struct_field.variable_name);
+            def.fields.push(struct_field);
         }
-
-        // TODO
-        // struct field_name might have duplicates.
-        // eg: "123foo" and "fooあ" will both resolve to "foo"
+        (self.scope, self.module) = (scope, outer_module);
+        self.in_collection = in_collection;
 
         self.structs.push(def);
+        Ok(())
+    }
+
+    /// full path of the struct generated for the nested object.
+    fn add_object_struct(
+        &mut self,
+        name: &str,
+        fields: Vec<Field>,
+    ) -> Result<String, CodegenError> {
+        if self.definitions.defines(name, &fields) {
+            return self.add_definition_struct(name, fields);
+        }
+
+        let Some(shared_objects) = &self.shared_objects else {
+            let struct_name = self.unique_type_name(name)?;
+            let path = self.full_path(&struct_name);
+            self.add_struct(struct_name, fields)?;
+            return Ok(path);
+        };
+
+        if let Some(path) = shared_objects.generated(&fields) {
+            return Ok(path);
+        }
+
+        // a shape used by several parents doesn't belong to any of their modules.
+        let module = match shared_objects.occurrences(&fields) > 1 {
+            true => std::mem::take(&mut self.module),
+            false => self.module.clone(),
+        };
+        let name = shared_objects.name(&fields, name);
+        let struct_name = self.unique_type_name(&name)?;
+        let path = self.full_path(&struct_name);
+        if let Some(shared_objects) = &mut self.shared_objects {
+            shared_objects.insert(fields.clone(), path.clone());
+        }
+        self.add_struct(struct_name, fields)?;
+        self.module = module;
+        Ok(path)
+    }
+
+    /// full path of the struct of a top level object that `FieldType::Ref`s refer to, named after it.
+    /// the name is known before the fields are processed, so that they can refer to the struct itself.
+    fn add_definition_struct(
+        &mut self,
+        name: &str,
+        fields: Vec<Field>,
+    ) -> Result<String, CodegenError> {
+        if let Some(path) = self.definitions.generated(name) {
+            return Ok(path);
+        }
+        if let Some(path) = self
+            .shared_objects
+            .as_ref()
+            .and_then(|shared_objects| shared_objects.generated(&fields))
+        {
+            self.definitions.insert(name, path.clone());
+            return Ok(path);
+        }
+
+        // like the root fields, wherever they are first referred to.
+        let module = std::mem::take(&mut self.module);
+        let struct_name = self.unique_type_name(name)?;
+        let path = self.full_path(&struct_name);
+        self.definitions.insert(name, path.clone());
+        if let Some(shared_objects) = &mut self.shared_objects {
+            shared_objects.insert(fields.clone(), path.clone());
+        }
+        let json_path = std::mem::replace(&mut self.path, format!("${}", path_segment(name)));
+        self.definitions.enter(name);
+        self.add_struct(struct_name, fields)?;
+        self.definitions.exit();
+        self.path = json_path;
+        self.module = module;
+        Ok(path)
     }
 
-    fn add_enum(&mut self, name: String, variants: Vec<FieldType>) {
+    /// full path of the type generated for the top level field `name`. see `FieldType::Ref`
+    /// `None` if there is no such field, or it is only made of itself (eg: `{Tree:[&Tree]}`).
+    fn ref_type_path(&mut self, name: &str) -> Result<Option<String>, CodegenError> {
+        if let Some(path) = self.definitions.generated(name) {
+            return Ok(Some(path));
+        }
+        let Some(ty) = self.definitions.get(name).cloned() else {
+            return Ok(None);
+        };
+        match ty {
+            FieldType::Object(fields) => Ok(Some(self.add_definition_struct(name, fields)?)),
+            _ if self.definitions.resolving(name) => Ok(None),
+            ty => {
+                let outer = (
+                    std::mem::take(&mut self.scope),
+                    std::mem::take(&mut self.module),
+                    std::mem::replace(&mut self.path, format!("${}", path_segment(name))),
+                );
+                self.definitions.enter(name);
+                let struct_field = self.process_field(Field {
+                    name: name.into(),
+                    ty,
+                    description: None,
+                    example: None,
+                })?;
+                self.definitions.exit();
+                // a union is already an enum named after the definition. anything else gets an alias.
+                // eg: `pub type Kind = String;`
+                let path = match self
+                    .enums
+                    .iter()
+                    .any(|def| def.module.is_empty() && def.name == struct_field.type_name)
+                {
+                    true => struct_field.type_name,
+                    false => {
+                        let alias = self.unique_type_name(name)?;
+                        self.add_alias(alias.clone(), struct_field.type_name);
+                        alias
+                    }
+                };
+                (self.scope, self.module, self.path) = outer;
+                self.definitions.insert(name, path.clone());
+                Ok(Some(path))
+            }
+        }
+    }
+
+    fn add_enum(&mut self, name: String, variants: Vec<FieldType>) -> Result<(), CodegenError> {
+        let module = self.module.clone();
+        let (scope, outer_module) = self.enter(&name)?;
+        let in_collection = std::mem::take(&mut self.in_collection);
         let mut def = EnumDef {
             name: name.clone(),
+            module,
+            path: self.path.clone(),
             variants: vec![],
         };
 
+        // an object named eg: "string" must not take the name of the `String(String)` variant.
+        let mut taken: Vec<String> = PRIMITIVE_VARIANTS.map(String::from).to_vec();
         for variant in variants {
-            def.variants
-                .push(self.process_enum_variant(name.clone(), variant));
+            let primitive = is_primitive(&variant);
+            let serde_as = self.serde_as(&variant);
+            let mut variant = self.process_enum_variant(name.clone(), variant)?;
+            variant.serde_as = serde_as;
+            if !primitive {
+                let base = variant.variant_name.clone();
+                let mut n = 2;
+                while taken.contains(&variant.variant_name) {
+                    variant.variant_name = format!("{}{}", base, n);
+                    n += 1;
+                }
+                taken.push(variant.variant_name.clone());
+            }
+            def.variants.push(variant);
         }
+        (self.scope, self.module) = (scope, outer_module);
+        self.in_collection = in_collection;
 
         self.enums.push(def);
+        Ok(())
     }
 
-    fn process_field(&mut self, field: Field) -> StructField {
-        match field.ty {
+    fn process_field(&mut self, field: Field) -> Result<StructField, CodegenError> {
+        Ok(match field.ty {
             FieldType::String => StructField {
-                variable_name: to_snake_case_or_unknown(&field.name, &mut self.iota),
+                variable_name: field_name(&field.name, &mut self.iota)?,
                 original_name: field.name,
                 type_name: "String".into(),
+                constant: None,
+                description: None,
+                serde_as: None,
+                key_constant: false,
+                skip: false,
             },
             FieldType::Integer => StructField {
-                variable_name: to_snake_case_or_unknown(&field.name, &mut self.iota),
+                variable_name: field_name(&field.name, &mut self.iota)?,
                 original_name: field.name,
                 type_name: "isize".into(),
+                constant: None,
+                description: None,
+                serde_as: None,
+                key_constant: false,
+                skip: false,
+            },
+            FieldType::BigInteger => StructField {
+                variable_name: field_name(&field.name, &mut self.iota)?,
+                original_name: field.name,
+                type_name: "i128".into(),
+                constant: None,
+                description: None,
+                serde_as: None,
+                key_constant: false,
+                skip: false,
             },
             FieldType::Float => StructField {
-                variable_name: to_snake_case_or_unknown(&field.name, &mut self.iota),
+                variable_name: field_name(&field.name, &mut self.iota)?,
                 original_name: field.name,
                 type_name: "f64".into(),
+                constant: None,
+                description: None,
+                serde_as: None,
+                key_constant: false,
+                skip: false,
             },
             FieldType::Boolean => StructField {
-                variable_name: to_snake_case_or_unknown(&field.name, &mut self.iota),
+                variable_name: field_name(&field.name, &mut self.iota)?,
                 original_name: field.name,
                 type_name: "bool".into(),
+                constant: None,
+                description: None,
+                serde_as: None,
+                key_constant: false,
+                skip: false,
             },
             FieldType::Unknown => StructField {
-                variable_name: to_snake_case_or_unknown(&field.name, &mut self.iota),
+                variable_name: field_name(&field.name, &mut self.iota)?,
                 original_name: field.name,
-                type_name: "serde_json::Value".into(),
+                type_name: self.unknown_type_name().into(),
+                constant: None,
+                description: None,
+                serde_as: None,
+                key_constant: false,
+                skip: false,
             },
             FieldType::Object(nested_fields) => {
-                let nested_struct_name = to_pascal_case_or_unknown(&field.name, &mut self.iota);
-                self.add_struct(nested_struct_name.clone(), nested_fields);
+                let nested_struct_path = self.add_object_struct(&field.name, nested_fields)?;
+                StructField {
+                    variable_name: field_name(&field.name, &mut self.iota)?,
+                    original_name: field.name,
+                    type_name: self.type_path(&nested_struct_path),
+                    constant: None,
+                    description: None,
+                    serde_as: None,
+                    key_constant: false,
+                    skip: false,
+                }
+            }
+            FieldType::Union(types) if self.use_serde_with && is_integer_or_string(&types) => {
                 StructField {
-                    variable_name: to_snake_case_or_unknown(&field.name, &mut self.iota),
+                    variable_name: field_name(&field.name, &mut self.iota)?,
                     original_name: field.name,
-                    type_name: nested_struct_name,
+                    type_name: "isize".into(),
+                    constant: None,
+                    description: None,
+                    serde_as: None,
+                    key_constant: false,
+                    skip: false,
                 }
             }
             FieldType::Union(types) => {
-                let nested_enum_name = to_pascal_case_or_unknown(&field.name, &mut self.iota);
-                self.add_enum(nested_enum_name.clone(), types);
+                let nested_enum_name = self.unique_type_name(&field.name)?;
+                let nested_enum_path = self.full_path(&nested_enum_name);
+                self.add_enum(nested_enum_name, types)?;
                 StructField {
-                    variable_name: to_snake_case_or_unknown(&field.name, &mut self.iota),
+                    variable_name: field_name(&field.name, &mut self.iota)?,
                     original_name: field.name,
-                    type_name: nested_enum_name,
+                    type_name: self.type_path(&nested_enum_path),
+                    constant: None,
+                    description: None,
+                    serde_as: None,
+                    key_constant: false,
+                    skip: false,
                 }
             }
             FieldType::Array(ty) => {
+                let in_collection = std::mem::replace(&mut self.in_collection, true);
+                self.path.push_str("[]");
                 let mut struct_field = self.process_field(Field {
-                    name: field.name,
+                    name: singular(&field.name),
                     ty: *ty,
-                });
+                    description: None,
+                    example: None,
+                })?;
+                self.path.truncate(self.path.len() - "[]".len());
+                self.in_collection = in_collection;
+                struct_field.variable_name = field_name(&field.name, &mut self.iota)?;
+                struct_field.original_name = field.name;
                 struct_field.type_name = format!("Vec<{}>", struct_field.type_name);
+                struct_field.constant = None;
+                struct_field
+            }
+            FieldType::Map(ty) => {
+                let in_collection = std::mem::replace(&mut self.in_collection, true);
+                self.path.push_str(".*");
+                let mut struct_field = self.process_field(Field {
+                    name: singular(&field.name),
+                    ty: *ty,
+                    description: None,
+                    example: None,
+                })?;
+                self.path.truncate(self.path.len() - ".*".len());
+                self.in_collection = in_collection;
+                struct_field.variable_name = field_name(&field.name, &mut self.iota)?;
+                struct_field.original_name = field.name;
+                struct_field.type_name = format!(
+                    "std::collections::HashMap<String, {}>",
+                    struct_field.type_name
+                );
+                struct_field.constant = None;
                 struct_field
             }
             FieldType::Optional(ty) => {
                 let mut struct_field = self.process_field(Field {
                     name: field.name,
                     ty: *ty,
-                });
+                    description: None,
+                    example: None,
+                })?;
                 struct_field.type_name = format!("Option<{}>", struct_field.type_name);
                 struct_field
             }
+            // no fields to generate a type from. any keys go into a map.
+            FieldType::EmptyObject => self.process_field(Field {
+                ty: FieldType::Map(Box::new(FieldType::Unknown)),
+                ..field
+            })?,
+            FieldType::Format(StringFormat::Base64) if self.use_serde_with => StructField {
+                variable_name: field_name(&field.name, &mut self.iota)?,
+                original_name: field.name,
+                type_name: "Vec<u8>".into(),
+                constant: None,
+                description: None,
+                serde_as: None,
+                key_constant: false,
+                skip: false,
+            },
+            FieldType::Format(_) => self.process_field(Field {
+                ty: FieldType::String,
+                ..field
+            })?,
+            FieldType::Tuple(tys) => {
+                let type_names = tys
+                    .into_iter()
+                    .map(|ty| {
+                        Ok(self
+                            .process_field(Field {
+                                name: singular(&field.name),
+                                ty,
+                                description: None,
+                                example: None,
+                            })?
+                            .type_name)
+                    })
+                    .collect::<Result<Vec<String>, CodegenError>>()?;
+                StructField {
+                    variable_name: field_name(&field.name, &mut self.iota)?,
+                    original_name: field.name,
+                    type_name: format!("({})", type_names.join(", ")),
+                    constant: None,
+                    description: None,
+                    serde_as: None,
+                    key_constant: false,
+                    skip: false,
+                }
+            }
+            FieldType::Ref(name) => {
+                let type_name = match self.ref_type_path(&name)? {
+                    Some(path) => self.type_path(&path),
+                    None => self.unknown_type_name().into(),
+                };
+                // a struct can't contain itself, only a pointer to itself.
+                let type_name = match self.definitions.resolving(&name) && !self.in_collection {
+                    true => {
+                        if !self.wrapped_in.contains(&self.scope) {
+                            self.wrapped_in.push(self.scope.clone());
+                        }
+                        self.recursive_wrapper.wrap(&type_name)
+                    }
+                    false => type_name,
+                };
+                StructField {
+                    variable_name: field_name(&field.name, &mut self.iota)?,
+                    original_name: field.name,
+                    type_name,
+                    constant: None,
+                    description: None,
+                    serde_as: None,
+                    key_constant: false,
+                    skip: false,
+                }
+            }
+            FieldType::Constant(ty, value) => {
+                let mut struct_field = self.process_field(Field {
+                    name: field.name,
+                    ty: *ty,
+                    description: None,
+                    example: None,
+                })?;
+                struct_field.constant = Some(match value {
+                    Value::Number(n) if n.is_f64() => {
+                        format!("{:?}", n.as_f64().unwrap_or_default())
+                    }
+                    Value::String(text) => format!("{text:?}"),
+                    value => value.to_string(),
+                });
+                struct_field
+            }
+        })
+    }
+
+    /// `serde_as` type of a field of type `ty` that is (or contains) an integer or string union, or base64.
+    /// `_` stands for the parts that serde handles as usual.
+    fn serde_as(&self, ty: &FieldType) -> Option<String> {
+        if !self.use_serde_with {
+            return None;
+        }
+        match ty {
+            FieldType::Union(types) if is_integer_or_string(types) => {
+                Some("serde_with::PickFirst<(_, serde_with::DisplayFromStr)>".into())
+            }
+            FieldType::Format(StringFormat::Base64) => Some("serde_with::base64::Base64".into()),
+            // the alias of a definition is the type without the adapter.
+            FieldType::Ref(name) => match self.definitions.get(name)? {
+                ty @ (FieldType::Union(_) | FieldType::Format(_)) => self.serde_as(ty),
+                _ => None,
+            },
+            FieldType::Array(ty) => Some(format!("Vec<{}>", self.serde_as(ty)?)),
+            FieldType::Optional(ty) => Some(format!("Option<{}>", self.serde_as(ty)?)),
+            FieldType::Map(ty) => Some(format!(
+                "std::collections::HashMap<_, {}>",
+                self.serde_as(ty)?
+            )),
+            FieldType::Tuple(tys) => {
+                let positions: Vec<Option<String>> =
+                    tys.iter().map(|ty| self.serde_as(ty)).collect();
+                positions.iter().any(Option::is_some).then(|| {
+                    let positions: Vec<&str> = positions
+                        .iter()
+                        .map(|position| position.as_deref().unwrap_or("_"))
+                        .collect();
+                    format!("({})", positions.join(", "))
+                })
+            }
+            _ => None,
         }
     }
 
-    fn process_enum_variant(&mut self, prefix: String, variant: FieldType) -> EnumVariant {
-        match variant {
+    /// see `RustOptions::unknown_type`
+    fn unknown_type_name(&self) -> &'static str {
+        match self.unknown_type {
+            UnknownType::Unit => "()",
+            UnknownType::JsonValue | UnknownType::Skip => "serde_json::Value",
+        }
+    }
+
+    fn process_enum_variant(
+        &mut self,
+        prefix: String,
+        variant: FieldType,
+    ) -> Result<EnumVariant, CodegenError> {
+        Ok(match variant {
             FieldType::String => EnumVariant {
                 variant_name: "String".into(),
                 associated_type: "String".into(),
+                serde_as: None,
             },
             FieldType::Integer => EnumVariant {
                 variant_name: "Integer".into(),
                 associated_type: "isize".into(),
+                serde_as: None,
+            },
+            FieldType::BigInteger => EnumVariant {
+                variant_name: "BigInteger".into(),
+                associated_type: "i128".into(),
+                serde_as: None,
             },
             FieldType::Float => EnumVariant {
                 variant_name: "Float".into(),
                 associated_type: "f64".into(),
+                serde_as: None,
             },
             FieldType::Boolean => EnumVariant {
                 variant_name: "Boolean".into(),
                 associated_type: "bool".into(),
+                serde_as: None,
             },
             FieldType::Unknown => EnumVariant {
                 variant_name: "Unknown".into(),
-                associated_type: "serde_json::Value".into(),
+                associated_type: self.unknown_type_name().into(),
+                serde_as: None,
             },
             FieldType::Object(fields) => {
                 let struct_field = self.process_field(Field {
                     name: prefix + "Class",
                     ty: FieldType::Object(fields),
-                });
+                    description: None,
+                    example: None,
+                })?;
 
                 EnumVariant {
-                    variant_name: struct_field.type_name.clone(),
+                    variant_name: last_segment(&struct_field.type_name),
                     associated_type: struct_field.type_name,
+                    serde_as: None,
                 }
             }
             FieldType::Union(types) => {
                 let struct_field = self.process_field(Field {
                     name: prefix + "Element",
                     ty: FieldType::Union(types),
-                });
+                    description: None,
+                    example: None,
+                })?;
 
                 EnumVariant {
-                    variant_name: struct_field.type_name.clone(),
+                    variant_name: last_segment(&struct_field.type_name),
                     associated_type: struct_field.type_name,
+                    serde_as: None,
                 }
             }
             FieldType::Array(ty) => {
                 let struct_field = self.process_field(Field {
                     name: prefix + "Array",
                     ty: FieldType::Array(ty),
-                });
+                    description: None,
+                    example: None,
+                })?;
 
                 EnumVariant {
                     variant_name: to_pascal_case_or_unknown(
                         &struct_field.variable_name,
                         &mut self.iota,
-                    ),
+                    )?,
                     associated_type: struct_field.type_name,
+                    serde_as: None,
                 }
             }
             FieldType::Optional(ty) => {
                 let struct_field = self.process_field(Field {
                     name: prefix + "Optional",
                     ty: FieldType::Optional(ty),
-                });
+                    description: None,
+                    example: None,
+                })?;
 
                 EnumVariant {
                     variant_name: struct_field.type_name.clone(),
                     associated_type: struct_field.type_name,
+                    serde_as: None,
+                }
+            }
+            FieldType::Map(ty) => {
+                let struct_field = self.process_field(Field {
+                    name: prefix + "Map",
+                    ty: FieldType::Map(ty),
+                    description: None,
+                    example: None,
+                })?;
+
+                EnumVariant {
+                    variant_name: to_pascal_case_or_unknown(
+                        &struct_field.variable_name,
+                        &mut self.iota,
+                    )?,
+                    associated_type: struct_field.type_name,
+                    serde_as: None,
+                }
+            }
+            FieldType::EmptyObject => {
+                self.process_enum_variant(prefix, FieldType::Map(Box::new(FieldType::Unknown)))?
+            }
+            FieldType::Constant(ty, _) => self.process_enum_variant(prefix, *ty)?,
+            FieldType::Format(StringFormat::Base64) if self.use_serde_with => EnumVariant {
+                variant_name: "Bytes".into(),
+                associated_type: "Vec<u8>".into(),
+                serde_as: None,
+            },
+            FieldType::Format(_) => self.process_enum_variant(prefix, FieldType::String)?,
+            FieldType::Ref(name) => {
+                let struct_field = self.process_field(Field {
+                    name: name.clone(),
+                    ty: FieldType::Ref(name.clone()),
+                    description: None,
+                    example: None,
+                })?;
+
+                EnumVariant {
+                    variant_name: type_name(&name, &mut self.iota)?,
+                    associated_type: struct_field.type_name,
+                    serde_as: None,
+                }
+            }
+            FieldType::Tuple(tys) => {
+                let struct_field = self.process_field(Field {
+                    name: prefix + "Tuple",
+                    ty: FieldType::Tuple(tys),
+                    description: None,
+                    example: None,
+                })?;
+
+                EnumVariant {
+                    variant_name: to_pascal_case_or_unknown(
+                        &struct_field.variable_name,
+                        &mut self.iota,
+                    )?,
+                    associated_type: struct_field.type_name,
+                    serde_as: None,
                 }
             }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn codegen(json: &str) -> String {
+        codegen_with(json, &RustOptions::default())
+    }
+
+    fn codegen_with(json: &str, options: &RustOptions) -> String {
+        let schema = crate::schema::extract(serde_json::from_str(json).unwrap());
+        let mut out = vec![];
+        rust(schema, options, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn rust_file() {
+        let dir =
+            std::env::temp_dir().join(format!("jsoncodegen-rust-file-{}", std::process::id()));
+        let input = dir.join("fixture.json");
+        let output = dir.join("generated").join("fixture.rs");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&input, r#"{"a": 1}"#).unwrap();
+
+        super::rust_file(&input, &output, &RustOptions::default()).unwrap();
+        assert_eq!(fs::read_to_string(&output).unwrap(), codegen(r#"{"a": 1}"#));
+
+        let modified = fs::metadata(&output).unwrap().modified().unwrap();
+        super::rust_file(&input, &output, &RustOptions::default()).unwrap();
+        assert_eq!(fs::metadata(&output).unwrap().modified().unwrap(), modified);
+
+        fs::write(&input, "{").unwrap();
+        assert!(matches!(
+            super::rust_file(&input, &output, &RustOptions::default()),
+            Err(CodegenError::InvalidJson(_))
+        ));
+        fs::write(&input, "1").unwrap();
+        assert!(matches!(
+            super::rust_file(&input, &output, &RustOptions::default()),
+            Err(CodegenError::InvalidTopLevel)
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn generate_tests() {
+        let options = RustOptions {
+            generate_tests: true,
+            root_name: Some("Config".into()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            codegen_with(r#"{"a": 1}"#, &options),
+            r#"use serde::{Serialize, Deserialize};
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Config {
+    pub a: isize,
+}
+#[cfg(test)]
+mod roundtrip_tests {
+    use super::*;
+    #[test]
+    fn roundtrip_config() {
+        let json = include_str!("input.json");
+        let parsed: Config = serde_json::from_str(json).unwrap();
+        let reserialized = serde_json::to_string(&parsed).unwrap();
+        let reparsed: Config = serde_json::from_str(&reserialized).unwrap();
+        assert_eq!(serde_json::to_string(&reparsed).unwrap(), reserialized);
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn visibility() {
+        let options = RustOptions {
+            type_visibility: Visibility::Crate,
+            field_visibility: Visibility::Private,
+            nested_modules: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            codegen_with(r#"{"user": {"address": {"city": "x"}}}"#, &options),
+            r#"use serde::{Serialize, Deserialize};
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct Root {
+    user: User,
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct User {
+    address: user::Address,
+}
+pub(crate) mod user {
+    use serde::{Serialize, Deserialize};
+    #[derive(Serialize, Deserialize, Debug)]
+    pub(crate) struct Address {
+        city: String,
+    }
+}
+"#
+        );
+
+        assert_eq!("pub".parse(), Ok(Visibility::Public));
+        assert_eq!("pub( crate )".parse(), Ok(Visibility::Crate));
+        assert_eq!("pub(super)".parse(), Ok(Visibility::Super));
+        assert_eq!(
+            "pub(in crate::api)".parse(),
+            Ok(Visibility::Module("crate::api".into()))
+        );
+        assert_eq!(
+            " pub (in super::super) ".parse(),
+            Ok(Visibility::Module("super::super".into()))
+        );
+        assert_eq!("private".parse(), Ok(Visibility::Private));
+        assert_eq!(" pub\n".parse(), Ok(Visibility::Public));
+        for text in [
+            "pub(self)",
+            "",
+            " ",
+            "pub(inner)",
+            "pub(in)",
+            "pub(in )",
+            "pub(in a b)",
+            "p u b",
+            "pri vate",
+            "pubx",
+            "pub(crate",
+            "pub crate",
+        ] {
+            assert!(text.parse::<Visibility>().is_err(), "{text:?}");
+        }
+    }
+
+    #[test]
+    fn manifest() {
+        let options = RustOptions {
+            manifest: true,
+            nested_modules: true,
+            ..Default::default()
+        };
+        let manifest = |json: &str| {
+            let schema = crate::schema::extract(serde_json::from_str(json).unwrap());
+            let mut out = vec![];
+            rust_files(schema, &options, &mut out).unwrap();
+            let out = String::from_utf8(out).unwrap();
+            assert!(out.starts_with("// root.rs\n"));
+            out.split_once("// manifest.json\n").unwrap().1.to_string()
+        };
+
+        assert_eq!(
+            manifest(r#"{"cart": [{"sku": "a", "tags": [1, "x"]}], "meta": {"id": {"n": 1}}}"#),
+            r#"{
+  "Cart": "$.cart[]",
+  "Meta": "$.meta",
+  "Root": "$",
+  "cart::Tag": "$.cart[].tags[]",
+  "meta::Id": "$.meta.id"
+}
+"#
+        );
+        assert_eq!(
+            manifest("[{\"a\": 1}]"),
+            r#"{
+  "Item": "$[]",
+  "Root": "$"
+}
+"#
+        );
+
+        // nothing but the code without the option.
+        let mut out = vec![];
+        rust_files(
+            crate::schema::extract(serde_json::json!({"a": 1})),
+            &RustOptions::default(),
+            &mut out,
+        )
+        .unwrap();
+        assert!(!String::from_utf8(out).unwrap().contains("manifest.json"));
+    }
+
+    #[test]
+    fn keywords() {
+        assert_eq!(
+            codegen(r#"{"type": "foo", "ref": 1, "self": {"crate": null}}"#),
+            r#"use serde::{Serialize, Deserialize};
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Root {
+    pub r#ref: isize,
+    #[serde(rename = "self")]
+    pub self_: Self_,
+    pub r#type: String,
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Self_ {
+    #[serde(rename = "crate")]
+    pub crate_: serde_json::Value,
+}
+"#
+        );
+    }
+
+    #[test]
+    fn indent() {
+        let options = RustOptions {
+            indent: IndentStyle::Tabs,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            codegen_with(r#"{"a": 1}"#, &options),
+            "use serde::{Serialize, Deserialize};\n#[derive(Serialize, Deserialize, Debug)]\npub struct Root {\n\tpub a: isize,\n}\n"
+        );
+    }
+
+    #[test]
+    fn reuse_identical_objects() {
+        let options = RustOptions {
+            reuse_identical_objects: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            codegen_with(
+                r#"{"from": {"x": 1, "y": 2}, "to": {"x": 3, "y": 4}, "nest": {"from": {"x": 5, "y": 6}}}"#,
+                &options
+            ),
+            r#"use serde::{Serialize, Deserialize};
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FromOrTo {
+    pub x: isize,
+    pub y: isize,
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Nest {
+    pub from: FromOrTo,
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Root {
+    pub from: FromOrTo,
+    pub nest: Nest,
+    pub to: FromOrTo,
+}
+"#
+        );
+    }
+
+    #[test]
+    fn singular_array_element_names() {
+        assert_eq!(
+            codegen(r#"{"children": [{"name": "a"}], "categories": [{"id": 1}]}"#),
+            r#"use serde::{Serialize, Deserialize};
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Category {
+    pub id: isize,
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Child {
+    pub name: String,
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Root {
+    pub categories: Vec<Category>,
+    pub children: Vec<Child>,
+}
+"#
+        );
+    }
+
+    #[test]
+    fn unique_type_names() {
+        assert_eq!(
+            codegen(r#"{"a": {"address": {"x": 1}}, "b": {"address": {"y": 2}}}"#),
+            r#"use serde::{Serialize, Deserialize};
+#[derive(Serialize, Deserialize, Debug)]
+pub struct A {
+    pub address: Address,
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Address {
+    pub x: isize,
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Address2 {
+    pub y: isize,
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct B {
+    pub address: Address2,
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Root {
+    pub a: A,
+    pub b: B,
+}
+"#
+        );
+    }
+
+    #[test]
+    fn reserved_variant_names() {
+        let options = RustOptions {
+            reuse_identical_objects: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            codegen_with(
+                r#"{"integer": {"a": 1}, "y": {"integer": [{"a": 1}, 1]}}"#,
+                &options
+            ),
+            r#"use serde::{Serialize, Deserialize};
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Integer {
+    pub a: isize,
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Root {
+    pub integer: Integer,
+    pub y: Y,
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Y {
+    pub integer: Vec<Integer2>,
+}
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum Integer2 {
+    Integer(isize),
+    Integer2(Integer),
+}
+"#
+        );
+    }
+
+    #[test]
+    fn variant_conversions() {
+        let options = RustOptions {
+            variant_conversions: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            codegen_with(r#"{"id": [1, "x"]}"#, &options),
+            r#"use serde::{Serialize, Deserialize};
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Root {
+    pub id: Vec<Id>,
+}
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum Id {
+    String(String),
+    Integer(isize),
+}
+impl ::core::convert::From<String> for Id {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+impl ::core::convert::TryFrom<Id> for String {
+    type Error = Id;
+    fn try_from(value: Id) -> ::core::result::Result<Self, Self::Error> {
+        match value {
+            Id::String(inner) => ::core::result::Result::Ok(inner),
+            other => ::core::result::Result::Err(other),
+        }
+    }
+}
+impl ::core::convert::From<isize> for Id {
+    fn from(value: isize) -> Self {
+        Self::Integer(value)
+    }
+}
+impl ::core::convert::TryFrom<Id> for isize {
+    type Error = Id;
+    fn try_from(value: Id) -> ::core::result::Result<Self, Self::Error> {
+        match value {
+            Id::Integer(inner) => ::core::result::Result::Ok(inner),
+            other => ::core::result::Result::Err(other),
+        }
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn root_name() {
+        let options = RustOptions {
+            root_name: Some("user profile".into()),
+            nested_modules: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            codegen_with(r#"{"address": {"x": 1}}"#, &options),
+            r#"use serde::{Serialize, Deserialize};
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Address {
+    pub x: isize,
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UserProfile {
+    pub address: Address,
+}
+"#
+        );
+        assert_eq!(
+            codegen_with("[1]", &options),
+            "use serde::{Serialize, Deserialize};\npub type UserProfile = Vec<isize>;\n"
+        );
+    }
+
+    #[test]
+    fn root_array_element_name() {
+        let options = |root_name: &str| RustOptions {
+            root_name: Some(root_name.into()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            codegen_with(r#"[{"a": 1}]"#, &options("Users")),
+            r#"use serde::{Serialize, Deserialize};
+pub type Users = Vec<User>;
+#[derive(Serialize, Deserialize, Debug)]
+pub struct User {
+    pub a: isize,
+}
+"#
+        );
+        // a singular root name can't be shared with the element.
+        assert!(codegen_with(r#"[{"a": 1}]"#, &options("User"))
+            .contains("pub type User = Vec<Item>;\n"));
+    }
+
+    #[test]
+    fn empty_objects() {
+        assert_eq!(
+            codegen(r#"{}"#),
+            "use serde::{Serialize, Deserialize};\npub type Root = std::collections::HashMap<String, serde_json::Value>;\n"
+        );
+        assert_eq!(
+            codegen(r#"{"x": {}, "y": [{}, 1]}"#),
+            r#"use serde::{Serialize, Deserialize};
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Root {
+    pub x: std::collections::HashMap<String, serde_json::Value>,
+    pub y: Vec<Y>,
+}
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum Y {
+    Integer(isize),
+    YMap(std::collections::HashMap<String, serde_json::Value>),
+}
+"#
+        );
+    }
+
+    #[test]
+    fn escaped_keys() {
+        assert_eq!(
+            codegen(r#"{"he said \"hi\"": 1, "back\\slash\u007f": 2}"#),
+            r#"use serde::{Serialize, Deserialize};
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Root {
+    #[serde(rename = "back\\slash\u{7f}")]
+    pub back_slash: isize,
+    #[serde(rename = "he said \"hi\"")]
+    pub he_said_hi: isize,
+}
+"#
+        );
+    }
+
+    #[test]
+    fn deny_unknown_fields() {
+        let options = RustOptions {
+            deny_unknown_fields: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            codegen_with(r#"{"a": {"b": 1}, "c": [{"d": 1}, 2]}"#, &options),
+            r#"use serde::{Serialize, Deserialize};
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct A {
+    pub b: isize,
+}
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct CClass {
+    pub d: isize,
+}
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Root {
+    pub a: A,
+    pub c: Vec<C>,
+}
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum C {
+    Integer(isize),
+    CClass(CClass),
+}
+"#
+        );
+    }
+
+    #[test]
+    fn serde_with() {
+        let options = RustOptions {
+            use_serde_with: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            codegen_with(
+                r#"[{"id": 42, "ids": [1, "2"], "parent": null}, {"id": "42", "ids": [3], "parent": "7", "x": [1.5, [1, "2"]]}, {"id": 1, "ids": [4], "parent": 7}]"#,
+                &options
+            ),
+            r#"use serde::{Serialize, Deserialize};
+pub type Root = Vec<Item>;
+#[serde_with::serde_as]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Item {
+    #[serde_as(as = "serde_with::PickFirst<(_, serde_with::DisplayFromStr)>")]
+    pub id: isize,
+    #[serde_as(as = "Vec<serde_with::PickFirst<(_, serde_with::DisplayFromStr)>>")]
+    pub ids: Vec<isize>,
+    #[serde_as(as = "Option<serde_with::PickFirst<(_, serde_with::DisplayFromStr)>>")]
+    pub parent: Option<isize>,
+    pub x: Option<Vec<X>>,
+}
+#[serde_with::serde_as]
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum X {
+    Float(f64),
+    XArray(#[serde_as(as = "Vec<serde_with::PickFirst<(_, serde_with::DisplayFromStr)>>")] Vec<isize>),
+}
+"#
+        );
+        // an enum without the option.
+        assert!(codegen(r#"{"id": [42, "42"]}"#).contains("pub enum Id {"));
+    }
+
+    #[test]
+    fn base64() {
+        let json = r#"[{"png": "iVBORw0KGgoAAAANSUhEUg==", "thumbs": ["R0lGODlhAQABAIAAAAUEBA=="], "data": [1, "c2VjcmV0LWtleS0xMjM0NTY3OA=="]}, {"png": "R0lGODlhAQABAIAAAAUEBA==", "thumbs": ["iVBORw0KGgoAAAANSUhEUg=="], "data": 2}]"#;
+        let schema = crate::schema::extract_with(
+            serde_json::from_str(json).unwrap(),
+            &crate::schema::SchemaInferenceOptions {
+                detect_base64: true,
+                ..Default::default()
+            },
+        );
+        let mut out = vec![];
+        rust(
+            schema.clone(),
+            &RustOptions {
+                use_serde_with: true,
+                ..Default::default()
+            },
+            &mut out,
+        )
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            r#"use serde::{Serialize, Deserialize};
+pub type Root = Vec<Item>;
+#[serde_with::serde_as]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Item {
+    pub data: Data,
+    #[serde_as(as = "serde_with::base64::Base64")]
+    pub png: Vec<u8>,
+    #[serde_as(as = "Vec<serde_with::base64::Base64>")]
+    pub thumbs: Vec<Vec<u8>>,
+}
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum Data {
+    Integer(isize),
+    DataArray(Vec<DataArray>),
+}
+#[serde_with::serde_as]
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum DataArray {
+    Bytes(#[serde_as(as = "serde_with::base64::Base64")] Vec<u8>),
+    Integer(isize),
+}
+"#
+        );
+
+        // plain strings without the option.
+        let mut out = vec![];
+        rust(schema, &RustOptions::default(), &mut out).unwrap();
+        assert!(String::from_utf8(out)
+            .unwrap()
+            .contains("    pub png: String,\n"));
+    }
+
+    #[test]
+    fn non_exhaustive() {
+        let json = r#"{"id": [1, "a"]}"#;
+
+        assert_eq!(
+            codegen_with(
+                json,
+                &RustOptions {
+                    non_exhaustive_enums: true,
+                    non_exhaustive_structs: true,
+                    ..Default::default()
+                }
+            ),
+            r#"use serde::{Serialize, Deserialize};
+#[derive(Serialize, Deserialize, Debug)]
+#[non_exhaustive]
+pub struct Root {
+    pub id: Vec<Id>,
+}
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+#[non_exhaustive]
+pub enum Id {
+    String(String),
+    Integer(isize),
+}
+"#
+        );
+        assert!(!codegen(json).contains("non_exhaustive"));
+    }
+
+    #[test]
+    fn nested_modules() {
+        let options = RustOptions {
+            nested_modules: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            codegen_with(
+                r#"{"user": {"address": {"geo": {"lat": 1.0}}, "tags": [{"k": "v"}, 1]}, "company": {"address": {"city": "x"}}}"#,
+                &options
+            ),
+            r#"use serde::{Serialize, Deserialize};
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Company {
+    pub address: company::Address,
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Root {
+    pub company: Company,
+    pub user: User,
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct User {
+    pub address: user::Address,
+    pub tags: Vec<user::Tag>,
+}
+pub mod company {
+    use serde::{Serialize, Deserialize};
+    #[derive(Serialize, Deserialize, Debug)]
+    pub struct Address {
+        pub city: String,
+    }
+}
+pub mod user {
+    use serde::{Serialize, Deserialize};
+    #[derive(Serialize, Deserialize, Debug)]
+    pub struct Address {
+        pub geo: address::Geo,
+    }
+    #[derive(Serialize, Deserialize, Debug)]
+    #[serde(untagged)]
+    pub enum Tag {
+        Integer(isize),
+        TagClass(tag::TagClass),
+    }
+    pub mod address {
+        use serde::{Serialize, Deserialize};
+        #[derive(Serialize, Deserialize, Debug)]
+        pub struct Geo {
+            pub lat: f64,
+        }
+    }
+    pub mod tag {
+        use serde::{Serialize, Deserialize};
+        #[derive(Serialize, Deserialize, Debug)]
+        pub struct TagClass {
+            pub k: String,
         }
     }
 }
+"#
+        );
+    }
+
+    #[test]
+    fn nested_modules_shared_objects() {
+        let options = RustOptions {
+            nested_modules: true,
+            reuse_identical_objects: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            codegen_with(
+                r#"{"user": {"home": {"at": {"x": 1}}, "work": {"at": {"x": 2}, "id": 3}}}"#,
+                &options
+            ),
+            r#"use serde::{Serialize, Deserialize};
+#[derive(Serialize, Deserialize, Debug)]
+pub struct At {
+    pub x: isize,
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Root {
+    pub user: User,
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct User {
+    pub home: user::Home,
+    pub work: user::Work,
+}
+pub mod user {
+    use serde::{Serialize, Deserialize};
+    #[derive(Serialize, Deserialize, Debug)]
+    pub struct Home {
+        pub at: super::At,
+    }
+    #[derive(Serialize, Deserialize, Debug)]
+    pub struct Work {
+        pub at: super::At,
+        pub id: isize,
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn constants() {
+        let json =
+            r#"[{"version": "1.0", "type": "a", "n": 1}, {"version": "1.0", "type": "b", "n": 1}]"#;
+        let schema = crate::schema::extract_with(
+            serde_json::from_str(json).unwrap(),
+            &crate::schema::SchemaInferenceOptions {
+                detect_constants: true,
+                ..Default::default()
+            },
+        );
+        let mut out = vec![];
+        rust(schema, &RustOptions::default(), &mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            r#"use serde::{Serialize, Deserialize};
+pub type Root = Vec<Item>;
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Item {
+    #[serde(default = "Item::default_n")]
+    pub n: isize,
+    pub r#type: String,
+    #[serde(default = "Item::default_version")]
+    pub version: String,
+}
+impl Item {
+    pub const N: isize = 1;
+    pub const VERSION: &'static str = "1.0";
+    fn default_n() -> isize {
+        Self::N
+    }
+    fn default_version() -> String {
+        Self::VERSION.into()
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn generate_constants() {
+        let options = RustOptions {
+            generate_constants: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            codegen_with(
+                r#"{"userId": 1, "type": "a", "email": null, "tags": ["x"], "address": {"zipCode": "10115"}}"#,
+                &options
+            ),
+            r#"use serde::{Serialize, Deserialize};
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Address {
+    #[serde(rename = "zipCode")]
+    pub zip_code: String,
+}
+impl Address {
+    pub const ZIP_CODE: &'static str = "zipCode";
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Root {
+    pub address: Address,
+    pub email: serde_json::Value,
+    pub tags: Vec<String>,
+    pub r#type: String,
+    #[serde(rename = "userId")]
+    pub user_id: isize,
+}
+impl Root {
+    pub const TYPE: &'static str = "type";
+    pub const USER_ID: &'static str = "userId";
+}
+"#
+        );
+
+        // next to the value of a constant field.
+        let schema = crate::schema::extract_with(
+            serde_json::from_str(r#"[{"version": "1.0"}, {"version": "1.0"}]"#).unwrap(),
+            &crate::schema::SchemaInferenceOptions {
+                detect_constants: true,
+                ..Default::default()
+            },
+        );
+        let mut out = vec![];
+        rust(schema, &options, &mut out).unwrap();
+        assert!(String::from_utf8(out).unwrap().contains(
+            r#"    pub const VERSION: &'static str = "1.0";
+    pub const VERSION_KEY: &'static str = "version";
+"#
+        ));
+    }
+
+    #[test]
+    fn tuples() {
+        let json =
+            r#"{"pairs": [["id", {"x": 1}], ["n", {"x": 2}]], "points": [[1.5, 2.5], [3.5, 4.5]]}"#;
+        let schema = crate::schema::extract_with(
+            serde_json::from_str(json).unwrap(),
+            &crate::schema::SchemaInferenceOptions {
+                detect_tuples: true,
+                ..Default::default()
+            },
+        );
+        let mut out = vec![];
+        rust(schema, &RustOptions::default(), &mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            r#"use serde::{Serialize, Deserialize};
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Pair {
+    pub x: isize,
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Root {
+    pub pairs: Vec<(String, Pair)>,
+    pub points: Vec<(f64, f64)>,
+}
+"#
+        );
+    }
+
+    #[test]
+    fn descriptions() {
+        let json = r#"{"id": 1, "id_description": "primary key", "name": "a", "name.$description": "display name\nshown in the ui"}"#;
+        let schema = crate::schema::extract_with(
+            serde_json::from_str(json).unwrap(),
+            &crate::schema::SchemaInferenceOptions {
+                extract_descriptions: true,
+                ..Default::default()
+            },
+        );
+        let mut out = vec![];
+        rust(schema, &RustOptions::default(), &mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            r#"use serde::{Serialize, Deserialize};
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Root {
+    /// primary key
+    pub id: isize,
+    /// display name
+    /// shown in the ui
+    pub name: String,
+}
+"#
+        );
+    }
+
+    #[test]
+    fn unknown_type() {
+        let json = r#"{"id": 1, "deleted_at": null, "tags": [null]}"#;
+        assert_eq!(
+            codegen_with(
+                json,
+                &RustOptions {
+                    unknown_type: UnknownType::Unit,
+                    ..Default::default()
+                }
+            ),
+            r#"use serde::{Serialize, Deserialize};
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Root {
+    pub deleted_at: (),
+    pub id: isize,
+    pub tags: Vec<()>,
+}
+"#
+        );
+        assert_eq!(
+            codegen_with(
+                json,
+                &RustOptions {
+                    unknown_type: UnknownType::Skip,
+                    ..Default::default()
+                }
+            ),
+            r#"use serde::{Serialize, Deserialize};
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Root {
+    #[serde(skip)]
+    pub deleted_at: std::marker::PhantomData<()>,
+    pub id: isize,
+    pub tags: Vec<serde_json::Value>,
+}
+"#
+        );
+    }
+
+    #[test]
+    fn generate_map_conversion() {
+        let options = RustOptions {
+            generate_map_conversion: true,
+            root_name: Some("Patch".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            codegen_with(r#"{"name": "a", "address": {"city": "x"}}"#, &options),
+            r#"use serde::{Serialize, Deserialize};
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Address {
+    pub city: String,
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Patch {
+    pub address: Address,
+    pub name: String,
+}
+impl ::core::convert::From<Patch> for serde_json::Map<String, serde_json::Value> {
+    fn from(value: Patch) -> Self {
+        match serde_json::to_value(value) {
+            ::core::result::Result::Ok(serde_json::Value::Object(map)) => map,
+            _ => unreachable!("a struct serializes to a json object"),
+        }
+    }
+}
+impl ::core::convert::TryFrom<serde_json::Map<String, serde_json::Value>> for Patch {
+    type Error = serde_json::Error;
+    fn try_from(map: serde_json::Map<String, serde_json::Value>) -> ::core::result::Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::Object(map))
+    }
+}
+"#
+        );
+        assert!(
+            !codegen_with(r#"[{"name": "a"}]"#, &options).contains("impl ::core::convert::From")
+        );
+    }
+
+    #[test]
+    fn recursive_wrapper() {
+        let schema: Schema = "{Node:{next:&Node?,items:[&Node]}}".parse().unwrap();
+        let code = |recursive_wrapper| {
+            let mut out = vec![];
+            let options = RustOptions {
+                recursive_wrapper,
+                ..Default::default()
+            };
+            rust(schema.clone(), &options, &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        // the elements of a `Vec` are behind a pointer already.
+        assert!(code(RecursiveWrapper::Box).starts_with(
+            "use serde::{Serialize, Deserialize};\n#[derive(Serialize, Deserialize, Debug)]\npub struct Node {\n    pub next: Option<Box<Node>>,\n    pub items: Vec<Node>,\n}\n"
+        ));
+        assert!(code(RecursiveWrapper::Arc).starts_with(
+            "use serde::{Serialize, Deserialize};\nuse std::sync::Arc;\n#[derive(Serialize, Deserialize, Debug)]\npub struct Node {\n    pub next: Option<Arc<Node>>,\n"
+        ));
+        assert!(code(RecursiveWrapper::Rc).starts_with(
+            "use serde::{Serialize, Deserialize};\nuse std::rc::Rc;\n#[derive(Serialize, Deserialize, Debug)]\npub struct Node {\n    pub next: Option<Rc<Node>>,\n"
+        ));
+
+        assert_eq!("Arc".parse(), Ok(RecursiveWrapper::Arc));
+        assert!("cell".parse::<RecursiveWrapper>().is_err());
+
+        // no import without a recursive type.
+        let mut out = vec![];
+        let options = RustOptions {
+            recursive_wrapper: RecursiveWrapper::Arc,
+            ..Default::default()
+        };
+        rust("{a:int}".parse().unwrap(), &options, &mut out).unwrap();
+        assert!(!String::from_utf8(out)
+            .unwrap()
+            .contains("use std::sync::Arc;"));
+    }
+}
@@ -0,0 +1,112 @@
+use serde_json::Value;
+
+/// parse jsonc: json with `//` and `/* */` comments and trailing commas.
+pub fn from_str(text: &str) -> serde_json::Result<Value> {
+    serde_json::from_str(&strip(text))
+}
+
+/// plain json with the comments and trailing commas of the jsonc `text` removed.
+/// they are replaced by spaces (comments keep their newlines)
+/// so that the line and column of a parse error still point into the original text.
+pub fn strip(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    // byte offset in `out` of a comma that is trailing if the next token closes an array or object.
+    let mut pending_comma: Option<usize> = None;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                pending_comma = None;
+                out.push(c);
+                while let Some(c) = chars.next() {
+                    out.push(c);
+                    match c {
+                        '\\' => out.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                out.push(' ');
+                while let Some(&c) = chars.peek() {
+                    if c == '\n' {
+                        break;
+                    }
+                    out.push(blank(c));
+                    chars.next();
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                out.push(' ');
+                out.push(blank(chars.next().unwrap_or(' ')));
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    out.push(blank(c));
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+            }
+            ',' => {
+                pending_comma = Some(out.len());
+                out.push(c);
+            }
+            ']' | '}' => {
+                if let Some(idx) = pending_comma.take() {
+                    out.replace_range(idx..idx + 1, " ");
+                }
+                out.push(c);
+            }
+            c if c.is_whitespace() => out.push(c),
+            c => {
+                pending_comma = None;
+                out.push(c);
+            }
+        }
+    }
+
+    out
+}
+
+fn blank(c: char) -> char {
+    match c {
+        '\n' | '\r' => c,
+        _ => ' ',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn comments_and_trailing_commas() {
+        let text = r#"{
+    // line comment
+    "a": [1, 2, /* block */ 3,],
+    "b": "not // a comment, /* nor this */",
+    "c": {"d": "escaped \" quote",}, /* multi
+    line */
+}"#;
+
+        assert_eq!(
+            from_str(text).unwrap(),
+            serde_json::json!({
+                "a": [1, 2, 3],
+                "b": "not // a comment, /* nor this */",
+                "c": {"d": "escaped \" quote"},
+            })
+        );
+        assert_eq!(strip(text).lines().count(), text.lines().count());
+    }
+
+    #[test]
+    fn plain_json_is_unchanged() {
+        let text = r#"{"a": [1, {"b": ",]"}], "c": null}"#;
+        assert_eq!(strip(text), text);
+    }
+}
@@ -1,2 +1,9 @@
+// the code generated by `#[derive(JsonSchemaSample)]` refers to `::jsoncodegen`, also in the tests of this crate.
+extern crate self as jsoncodegen;
+
 pub mod codegen;
+pub mod jsonc;
+pub mod openapi;
 pub mod schema;
+#[cfg(feature = "yaml")]
+pub mod yaml;
@@ -0,0 +1,640 @@
+use crate::schema::{Field, FieldType, Schema, StringFormat};
+use serde_json::{Map, Value};
+use std::fmt::{self, Display, Formatter};
+
+/// a schema that can't be converted. `path` is the json pointer of the offending value
+/// in the spec. eg: `#/components/schemas/Pet/properties/tags`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpenApiError {
+    pub path: String,
+    pub message: String,
+}
+
+impl Display for OpenApiError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.message, self.path)
+    }
+}
+
+impl std::error::Error for OpenApiError {}
+
+/// schema of the json described by an openapi 3.x (or json schema) schema object.
+/// properties that aren't `required` and `nullable` values are optional, `oneOf` and `anyOf` become unions
+/// and the properties of the `allOf` subschemas are merged.
+/// `$ref`s point into `schema` itself (eg: `#/$defs/Address`) and are replaced with what they point to.
+/// a reference to a definition that is still being converted (a recursive type) becomes `any`,
+/// which is optional unless the property is required.
+/// properties are in the order of `schema` with the `preserve_order` feature, otherwise sorted by name.
+pub fn from_openapi_schema(schema: &Value) -> Result<Schema, OpenApiError> {
+    let mut converter = Converter {
+        document: schema,
+        components: false,
+        // `{"$ref": "#"}` is the schema itself
+        resolving: vec![String::new()],
+    };
+    let ty = match converter.field_type(schema, "#")? {
+        // a nullable root
+        FieldType::Optional(ty) => *ty,
+        ty => ty,
+    };
+    match ty {
+        FieldType::Object(fields) => Ok(Schema::Object(fields)),
+        FieldType::EmptyObject => Ok(Schema::Object(vec![])),
+        FieldType::Array(ty) => Ok(Schema::Array(*ty)),
+        ty => Err(error(
+            "#",
+            &format!("expected an object or an array schema, not {ty}"),
+        )),
+    }
+}
+
+/// schema of a whole openapi spec: an object with a field for every schema in `components/schemas`,
+/// so that a class or struct is generated for each of them. `$ref`s point into the spec.
+/// those to a schema of the components become a `FieldType::Ref` to its field,
+/// so that there is one type per schema, and a schema can refer to itself.
+pub fn from_openapi(document: &Value) -> Result<Schema, OpenApiError> {
+    let schemas = document
+        .pointer("/components/schemas")
+        .and_then(Value::as_object)
+        .ok_or_else(|| error("#", "no components/schemas"))?;
+
+    let mut converter = Converter {
+        document,
+        components: true,
+        resolving: vec![],
+    };
+    let mut fields = vec![];
+    for (name, schema) in schemas {
+        let pointer = format!("/components/schemas/{}", escape(name));
+        converter.resolving.push(pointer.clone());
+        let ty = converter.field_type(schema, &format!("#{pointer}"))?;
+        converter.resolving.pop();
+        fields.push(Field {
+            name: name.clone(),
+            ty,
+            description: description(schema),
+            example: None,
+        });
+    }
+
+    // the references to a nullable schema may be null too.
+    let nullable: Vec<String> = fields
+        .iter()
+        .filter(|field| matches!(field.ty, FieldType::Optional(_)))
+        .map(|field| field.name.clone())
+        .collect();
+    for field in &mut fields {
+        nullable_refs(&mut field.ty, &nullable);
+    }
+    Ok(Schema::Object(fields))
+}
+
+fn nullable_refs(ty: &mut FieldType, nullable: &[String]) {
+    match ty {
+        FieldType::Ref(name) if nullable.contains(name) => {
+            *ty = optional(FieldType::Ref(name.clone()))
+        }
+        FieldType::Object(fields) => fields
+            .iter_mut()
+            .for_each(|field| nullable_refs(&mut field.ty, nullable)),
+        FieldType::Union(tys) | FieldType::Tuple(tys) => {
+            tys.iter_mut().for_each(|ty| nullable_refs(ty, nullable))
+        }
+        // already optional
+        FieldType::Optional(inner) if matches!(inner.as_ref(), FieldType::Ref(_)) => {}
+        FieldType::Array(ty) | FieldType::Optional(ty) | FieldType::Map(ty) => {
+            nullable_refs(ty, nullable)
+        }
+        _ => {}
+    }
+}
+
+struct Converter<'d> {
+    /// what the `$ref`s point into
+    document: &'d Value,
+    /// the `$ref`s to `#/components/schemas/{name}` become `FieldType::Ref(name)`. see `from_openapi`
+    components: bool,
+    /// json pointers of the definitions being converted, innermost last.
+    resolving: Vec<String>,
+}
+
+impl<'d> Converter<'d> {
+    /// `path` is the json pointer of `schema`, for the errors.
+    fn field_type(&mut self, schema: &Value, path: &str) -> Result<FieldType, OpenApiError> {
+        let obj = match schema {
+            Value::Object(obj) => obj,
+            // `true` allows anything
+            Value::Bool(true) => return Ok(FieldType::Unknown),
+            _ => return Err(error(path, "expected a schema object")),
+        };
+
+        if let Some(reference) = obj.get("$ref") {
+            return self.reference(reference, path);
+        }
+
+        let ty = match (obj.get("oneOf"), obj.get("anyOf"), obj.get("allOf")) {
+            (Some(variants), _, _) => self.union(variants, &format!("{path}/oneOf"))?,
+            (_, Some(variants), _) => self.union(variants, &format!("{path}/anyOf"))?,
+            (_, _, Some(subschemas)) => self.all_of(obj, subschemas, path)?,
+            _ => self.typed(obj, path)?,
+        };
+
+        // openapi 3.0. 3.1 has `"type": ["string", "null"]` instead
+        match obj.get("nullable") {
+            Some(Value::Bool(true)) => Ok(optional(ty)),
+            _ => Ok(ty),
+        }
+    }
+
+    /// the definition a `{"$ref": "#/json/pointer"}` points to.
+    fn reference(&mut self, reference: &Value, path: &str) -> Result<FieldType, OpenApiError> {
+        let (pointer, definition) = self.resolve(reference, path)?;
+        if self.components {
+            if let Some(name) = pointer.strip_prefix("/components/schemas/") {
+                if !name.contains('/') {
+                    return Ok(FieldType::Ref(unescape(name)));
+                }
+            }
+        }
+        self.expand(pointer, definition)
+    }
+
+    /// the json pointer in the `reference` and the schema it points to.
+    fn resolve<'r>(
+        &self,
+        reference: &'r Value,
+        path: &str,
+    ) -> Result<(&'r str, &'d Value), OpenApiError> {
+        let path = format!("{path}/$ref");
+        let reference = reference
+            .as_str()
+            .ok_or_else(|| error(&path, "expected a string"))?;
+        let pointer = reference.strip_prefix('#').ok_or_else(|| {
+            error(
+                &path,
+                &format!("{reference:?} is not a reference into the same document"),
+            )
+        })?;
+
+        let definition = self
+            .document
+            .pointer(pointer)
+            .ok_or_else(|| error(&path, &format!("{reference:?} points to nothing")))?;
+        Ok((pointer, definition))
+    }
+
+    /// the type of the `definition` at `pointer`, in place of a reference to it.
+    fn expand(&mut self, pointer: &str, definition: &Value) -> Result<FieldType, OpenApiError> {
+        if self.resolving.iter().any(|resolving| resolving == pointer) {
+            return Ok(FieldType::Unknown);
+        }
+        self.resolving.push(pointer.to_string());
+        let ty = self.field_type(definition, &format!("#{pointer}"));
+        self.resolving.pop();
+        ty
+    }
+
+    /// `oneOf` or `anyOf`
+    fn union(&mut self, variants: &Value, path: &str) -> Result<FieldType, OpenApiError> {
+        let variants = variants
+            .as_array()
+            .ok_or_else(|| error(path, "expected an array"))?;
+        let mut tys = vec![];
+        for (idx, variant) in variants.iter().enumerate() {
+            tys.push(self.field_type(variant, &format!("{path}/{idx}"))?);
+        }
+        Ok(union(tys))
+    }
+
+    /// the properties of all the `subschemas` and of `obj` itself, which are required if any of them requires them.
+    fn all_of(
+        &mut self,
+        obj: &Map<String, Value>,
+        subschemas: &Value,
+        path: &str,
+    ) -> Result<FieldType, OpenApiError> {
+        let subschemas = subschemas
+            .as_array()
+            .ok_or_else(|| error(&format!("{path}/allOf"), "expected an array"))?;
+
+        let mut tys = vec![];
+        for (idx, subschema) in subschemas.iter().enumerate() {
+            let path = format!("{path}/allOf/{idx}");
+            // the properties of the schema referred to are needed, not a reference to its type.
+            let ty = match subschema.get("$ref") {
+                Some(reference) => {
+                    let (pointer, definition) = self.resolve(reference, &path)?;
+                    self.expand(pointer, definition)?
+                }
+                None => self.field_type(subschema, &path)?,
+            };
+            tys.push((ty, path));
+        }
+        if obj.contains_key("properties") {
+            tys.push((self.object(obj, path)?, path.to_string()));
+        }
+        // eg: `allOf: [{"$ref": "#/components/schemas/Id"}]` to add a description to a reference
+        if let [(ty, _)] = tys.as_slice() {
+            if !matches!(ty, FieldType::Object(_) | FieldType::EmptyObject) {
+                return Ok(ty.clone());
+            }
+        }
+
+        let mut fields: Vec<Field> = vec![];
+        for (ty, path) in tys {
+            let more = match ty {
+                FieldType::Object(more) => more,
+                // `any` (eg: a recursive reference) adds no properties
+                FieldType::EmptyObject | FieldType::Unknown => vec![],
+                ty => {
+                    return Err(error(
+                        &path,
+                        &format!("expected an object schema, not {ty}"),
+                    ))
+                }
+            };
+            for field in more {
+                match fields
+                    .iter_mut()
+                    .find(|existing| existing.name == field.name)
+                {
+                    Some(existing) if matches!(existing.ty, FieldType::Optional(_)) => {
+                        existing.ty = field.ty
+                    }
+                    Some(_) => {}
+                    None => fields.push(field),
+                }
+            }
+        }
+        for field in &mut fields {
+            if required(obj).contains(&field.name.as_str()) {
+                if let FieldType::Optional(ty) = &field.ty {
+                    field.ty = (**ty).clone();
+                }
+            }
+        }
+
+        Ok(match fields.is_empty() {
+            true => FieldType::EmptyObject,
+            false => FieldType::Object(fields),
+        })
+    }
+
+    /// a schema with a `type` (or an array of them). the type is inferred from
+    /// `properties` or `items` when it is left out, and anything is allowed without them.
+    fn typed(&mut self, obj: &Map<String, Value>, path: &str) -> Result<FieldType, OpenApiError> {
+        let types = match obj.get("type") {
+            Some(Value::String(ty)) => vec![ty.as_str()],
+            Some(Value::Array(tys)) => tys
+                .iter()
+                .map(|ty| {
+                    ty.as_str()
+                        .ok_or_else(|| error(&format!("{path}/type"), "expected a string"))
+                })
+                .collect::<Result<_, _>>()?,
+            Some(_) => {
+                return Err(error(
+                    &format!("{path}/type"),
+                    "expected a string or an array of strings",
+                ))
+            }
+            None if obj.contains_key("properties") || obj.contains_key("additionalProperties") => {
+                vec!["object"]
+            }
+            None if obj.contains_key("items") => vec!["array"],
+            None => return Ok(FieldType::Unknown),
+        };
+
+        let mut tys = vec![];
+        for ty in types {
+            tys.push(match ty {
+                "string" => match obj.get("format").and_then(Value::as_str) {
+                    Some("date-time") => FieldType::Format(StringFormat::DateTime),
+                    Some("date") => FieldType::Format(StringFormat::Date),
+                    Some("uuid") => FieldType::Format(StringFormat::Uuid),
+                    Some("byte") => FieldType::Format(StringFormat::Base64),
+                    _ => FieldType::String,
+                },
+                "integer" => FieldType::Integer,
+                "number" => FieldType::Float,
+                "boolean" => FieldType::Boolean,
+                "null" => FieldType::Unknown,
+                "array" => FieldType::Array(Box::new(match obj.get("items") {
+                    Some(items) => self.field_type(items, &format!("{path}/items"))?,
+                    None => FieldType::Unknown,
+                })),
+                "object" => self.object(obj, path)?,
+                ty => {
+                    return Err(error(
+                        &format!("{path}/type"),
+                        &format!("unknown type {ty:?}"),
+                    ))
+                }
+            });
+        }
+        Ok(union(tys))
+    }
+
+    /// the `properties`, which are optional unless they are `required`.
+    /// a map of the `additionalProperties` without them.
+    fn object(&mut self, obj: &Map<String, Value>, path: &str) -> Result<FieldType, OpenApiError> {
+        let properties = match obj.get("properties") {
+            Some(Value::Object(properties)) => properties,
+            Some(_) => return Err(error(&format!("{path}/properties"), "expected an object")),
+            None => &Map::new(),
+        };
+
+        let mut fields = vec![];
+        for (name, schema) in properties {
+            let path = format!("{path}/properties/{}", escape(name));
+            let ty = self.field_type(schema, &path)?;
+            fields.push(Field {
+                name: name.clone(),
+                ty: match (required(obj).contains(&name.as_str()), ty) {
+                    (true, ty) => ty,
+                    // a reference to a definition that is still being converted. see `from_openapi_schema`
+                    (false, FieldType::Unknown) if schema.get("$ref").is_some() => {
+                        FieldType::Optional(Box::new(FieldType::Unknown))
+                    }
+                    (false, ty) => optional(ty),
+                },
+                description: description(schema),
+                example: None,
+            });
+        }
+
+        Ok(match (fields.is_empty(), obj.get("additionalProperties")) {
+            (false, _) => FieldType::Object(fields),
+            (true, Some(values @ Value::Object(_))) => FieldType::Map(Box::new(
+                self.field_type(values, &format!("{path}/additionalProperties"))?,
+            )),
+            (true, _) => FieldType::EmptyObject,
+        })
+    }
+}
+
+/// the variants with nested unions flattened and duplicates removed.
+/// the union is optional if any variant is optional or `null`.
+fn union(tys: Vec<FieldType>) -> FieldType {
+    fn add(variants: &mut Vec<FieldType>, nullable: &mut bool, ty: FieldType) {
+        match ty {
+            FieldType::Unknown => *nullable = true,
+            FieldType::Optional(ty) => {
+                *nullable = true;
+                add(variants, nullable, *ty);
+            }
+            FieldType::Union(tys) => tys.into_iter().for_each(|ty| add(variants, nullable, ty)),
+            ty if variants.contains(&ty) => {}
+            ty => variants.push(ty),
+        }
+    }
+
+    let (mut variants, mut nullable) = (vec![], false);
+    for ty in tys {
+        add(&mut variants, &mut nullable, ty);
+    }
+    let ty = match variants.len() {
+        0 => return FieldType::Unknown,
+        1 => variants.remove(0),
+        _ => FieldType::Union(variants),
+    };
+    match nullable {
+        true => optional(ty),
+        false => ty,
+    }
+}
+
+fn optional(ty: FieldType) -> FieldType {
+    match ty {
+        FieldType::Optional(_) | FieldType::Unknown => ty,
+        ty => FieldType::Optional(Box::new(ty)),
+    }
+}
+
+/// names of the `required` properties
+fn required(obj: &Map<String, Value>) -> Vec<&str> {
+    obj.get("required")
+        .and_then(Value::as_array)
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default()
+}
+
+fn description(schema: &Value) -> Option<String> {
+    schema
+        .get("description")
+        .and_then(Value::as_str)
+        .map(String::from)
+}
+
+/// `name` as a json pointer segment
+fn escape(name: &str) -> String {
+    name.replace('~', "~0").replace('/', "~1")
+}
+
+/// the name in a json pointer segment
+fn unescape(segment: &str) -> String {
+    segment.replace("~1", "/").replace("~0", "~")
+}
+
+fn error(path: &str, message: &str) -> OpenApiError {
+    OpenApiError {
+        path: path.into(),
+        message: message.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    /// `schema` has the fields of `expected`, in the same order with the `preserve_order` feature.
+    /// without it, the properties of a spec are read sorted by name.
+    fn assert_schema(schema: &Schema, expected: &str) {
+        #[cfg(feature = "preserve_order")]
+        assert_eq!(schema.to_string(), expected);
+        let expected: Schema = expected.parse().unwrap();
+        assert_eq!(schema.to_canonical_string(), expected.to_canonical_string());
+    }
+
+    #[test]
+    fn petstore() {
+        let spec = json!({
+            "openapi": "3.0.3",
+            "info": {"title": "Swagger Petstore", "version": "1.0.0"},
+            "paths": {},
+            "components": {
+                "schemas": {
+                    "Pet": {
+                        "allOf": [
+                            {"$ref": "#/components/schemas/NewPet"},
+                            {"type": "object", "required": ["id"], "properties": {"id": {"type": "integer", "format": "int64"}}}
+                        ]
+                    },
+                    "NewPet": {
+                        "type": "object",
+                        "required": ["name"],
+                        "properties": {
+                            "name": {"type": "string", "description": "name of the pet"},
+                            "tag": {"type": "string", "nullable": true},
+                            "born": {"type": "string", "format": "date"},
+                            "photo": {"type": "string", "format": "byte"},
+                            "weight": {"type": "number"},
+                            "parent": {"$ref": "#/components/schemas/NewPet"},
+                            "owner": {"oneOf": [{"$ref": "#/components/schemas/Person"}, {"type": "string", "format": "uuid"}]},
+                            "scores": {"type": "object", "additionalProperties": {"type": "integer"}}
+                        }
+                    },
+                    "Person": {
+                        "type": "object",
+                        "required": ["id", "vaccinated"],
+                        "properties": {
+                            "id": {"type": ["string", "null"]},
+                            "vaccinated": {"type": "boolean"},
+                            "pets": {"type": "array", "items": {"$ref": "#/components/schemas/Pet"}}
+                        }
+                    },
+                    "Error": {
+                        "required": ["code", "message"],
+                        "properties": {
+                            "code": {"type": "integer"},
+                            "message": {"type": "string"},
+                            "details": {}
+                        }
+                    }
+                }
+            }
+        });
+
+        let schema = from_openapi(&spec).unwrap();
+        assert_schema(
+            &schema,
+            r#"{Pet:{name:str,tag:str?,born:date?,photo:base64?,weight:float?,parent:&NewPet?,owner:(&Person|uuid)?,scores:{*:int}?,id:int},NewPet:{name:str,tag:str?,born:date?,photo:base64?,weight:float?,parent:&NewPet?,owner:(&Person|uuid)?,scores:{*:int}?},Person:{id:str?,vaccinated:bool,pets:[&Pet]?},Error:{code:int,message:str,details:any}}"#,
+        );
+        let Schema::Object(fields) = &schema else {
+            unreachable!()
+        };
+        let field = |fields: &[Field], name: &str| {
+            let field = fields.iter().find(|field| field.name == name);
+            field.unwrap().clone()
+        };
+        let FieldType::Object(new_pet) = field(fields, "NewPet").ty else {
+            unreachable!()
+        };
+        assert_eq!(
+            field(&new_pet, "name").description.as_deref(),
+            Some("name of the pet")
+        );
+
+        assert_schema(
+            &from_openapi_schema(&spec["components"]["schemas"]["Error"]).unwrap(),
+            r#"{code:int,message:str,details:any}"#,
+        );
+        assert_eq!(
+            from_openapi_schema(&json!({
+                "type": "array",
+                "items": {"$ref": "#/$defs/Point"},
+                "$defs": {"Point": {"type": "array", "items": {"type": "number"}}}
+            }))
+            .unwrap()
+            .to_string(),
+            r#"[[float]]"#
+        );
+    }
+
+    #[test]
+    fn references() {
+        let spec = json!({
+            "openapi": "3.0.3",
+            "components": {
+                "schemas": {
+                    "Pet": {
+                        "type": "object",
+                        "required": ["kind"],
+                        "properties": {
+                            "kind": {"$ref": "#/components/schemas/Kind"},
+                            "owner": {"$ref": "#/components/schemas/Owner"},
+                            "friends": {"type": "array", "items": {"$ref": "#/components/schemas/Pet"}}
+                        }
+                    },
+                    "Owner": {
+                        "type": "object",
+                        "required": ["id", "extra"],
+                        "properties": {
+                            "id": {"type": "integer"},
+                            "extra": {"$ref": "#/components/schemas/Extra"}
+                        }
+                    },
+                    "Kind": {"type": "string", "enum": ["cat", "dog"]},
+                    "Node": {
+                        "type": "object",
+                        "properties": {"next": {"$ref": "#/components/schemas/Node"}}
+                    },
+                    "Extra": {
+                        "allOf": [
+                            {"$ref": "#/components/schemas/Owner"},
+                            {"type": "object", "properties": {"z": {"type": "boolean"}}}
+                        ]
+                    },
+                    "Maybe": {"type": "object", "nullable": true, "properties": {"m": {"type": "integer"}}},
+                    "Wrapper": {
+                        "type": "object",
+                        "required": ["maybe"],
+                        "properties": {"maybe": {"$ref": "#/components/schemas/Maybe"}}
+                    }
+                }
+            }
+        });
+
+        // one type per schema, which the others refer to. even through `allOf` and recursion.
+        let schema = from_openapi(&spec).unwrap();
+        assert_schema(
+            &schema,
+            "{Pet:{kind:&Kind,owner:&Owner?,friends:[&Pet]?},Owner:{id:int,extra:&Extra},Kind:str,Node:{next:&Node?},Extra:{id:int,extra:&Extra,z:bool?},Maybe:{m:int?}?,Wrapper:{maybe:&Maybe?}}",
+        );
+        assert_eq!(schema.to_string().parse::<Schema>(), Ok(schema));
+
+        // without components to refer to, a recursive reference is `any`.
+        assert_eq!(
+            from_openapi_schema(&json!({
+                "required": ["parent"],
+                "properties": {"next": {"$ref": "#"}, "parent": {"$ref": "#"}}
+            }))
+            .unwrap()
+            .to_string(),
+            "{next:any?,parent:any}"
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let error = |schema: Value| from_openapi_schema(&schema).unwrap_err().to_string();
+        assert_eq!(
+            error(json!({"type": "string"})),
+            "expected an object or an array schema, not str at #"
+        );
+        assert_eq!(
+            error(json!({"properties": {"a": {"type": "date"}}})),
+            r#"unknown type "date" at #/properties/a/type"#
+        );
+        assert_eq!(
+            error(json!({"properties": {"a/b": {"$ref": "#/$defs/Missing"}}})),
+            r##""#/$defs/Missing" points to nothing at #/properties/a~1b/$ref"##
+        );
+        assert_eq!(
+            error(json!({"items": {"$ref": "other.json#/Pet"}})),
+            r##""other.json#/Pet" is not a reference into the same document at #/items/$ref"##
+        );
+        assert_eq!(
+            error(json!({"allOf": [{"type": "object"}, {"type": "integer"}]})),
+            "expected an object schema, not int at #/allOf/1"
+        );
+        assert_eq!(
+            from_openapi(&json!({"openapi": "3.1.0"}))
+                .unwrap_err()
+                .to_string(),
+            "no components/schemas at #"
+        );
+    }
+}
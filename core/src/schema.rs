@@ -1,499 +1,4375 @@
+use serde::{
+    de::{self, value::MapAccessDeserializer, DeserializeSeed, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
 use serde_json::{Map, Value};
-use std::ops::Deref;
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+    io,
+    ops::Deref,
+    str::FromStr,
+};
 
-#[derive(Debug, Clone, PartialEq)]
+/// (de)serialized as json so that it can be cached or read by other tools without inferring it again.
+/// eg: `{"object": [{"name": "id", "ty": "integer"}]}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Schema {
     Object(Vec<Field>),
     Array(FieldType),
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Field {
     pub name: String,
     pub ty: FieldType,
+    /// only inferred with `SchemaInferenceOptions::extract_descriptions`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// first non-null primitive value of the field.
+    /// only inferred with `SchemaInferenceOptions::examples`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub example: Option<Value>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum FieldType {
     String,
     Integer,
+    /// integer above the i64 range, up to u64::MAX.
+    /// serde_json reads larger (or smaller than i64::MIN) integers as floats, so they are `Float`.
+    BigInteger,
     Float,
     Boolean,
     Unknown,
+    /// `{}`. an object without fields, or a map without entries.
+    EmptyObject,
     Object(Vec<Field>),
     Union(Vec<FieldType>),
     Array(Box<FieldType>),
     Optional(Box<FieldType>),
+    /// object with data for keys (eg: dates or ids) instead of field names.
+    /// only inferred with `SchemaInferenceOptions::detect_maps`
+    Map(Box<FieldType>),
+    /// object field of a primitive type that has the same value every time it appears.
+    /// only inferred with `SchemaInferenceOptions::detect_constants`
+    Constant(Box<FieldType>, Value),
+    /// string that is always in a well known format.
+    /// only inferred with `SchemaInferenceOptions::detect_formats` (or `detect_base64`)
+    Format(StringFormat),
+    /// array with a fixed number of elements of fixed types. eg: `[12.34, 56.78]` coordinates
+    /// only inferred with `SchemaInferenceOptions::detect_tuples`
+    Tuple(Vec<FieldType>),
+    /// the type of the top level field `name`, generated once for every place that refers to it.
+    /// eg: a `$ref` to a schema in the components of an openapi spec, which can also refer to itself.
+    /// only produced by `openapi::from_openapi`
+    Ref(String),
 }
 
-pub fn extract(json: Value) -> Schema {
-    match json {
-        Value::Array(arr) => Schema::Array(array(arr)),
-        Value::Object(obj) => Schema::Object(object(obj)),
-        _ => unreachable!("Valid top level Value will always be object or array"),
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StringFormat {
+    /// rfc 3339. eg: `2024-01-31T09:30:00Z` or `2024-01-31T09:30:00.5+05:30`
+    DateTime,
+    /// eg: `2024-01-31`
+    Date,
+    /// eg: `67e55044-10b1-426f-9247-bb680e5fe0c8`
+    Uuid,
+    /// binary data in the standard base64 alphabet, with padding. eg: `iVBORw0KGgoAAAANSUhEUg==`
+    /// only inferred with `SchemaInferenceOptions::detect_base64`
+    Base64,
 }
 
-fn object(obj: Map<String, Value>) -> Vec<Field> {
-    let mut fields = vec![];
+impl Schema {
+    /// depth-first (pre-order) traversal of every `FieldType` reachable from the root.
+    /// the order is deterministic and follows field/variant order.
+    pub fn iter(&self) -> Iter<'_> {
+        let stack = match self {
+            Schema::Object(fields) => fields.iter().rev().map(|field| &field.ty).collect(),
+            Schema::Array(ty) => vec![ty],
+        };
+        Iter { stack }
+    }
 
-    for (key, value) in obj {
-        fields.push(Field {
-            name: key,
-            ty: field_type(value),
-        });
+    /// the compact schema (see `Display`) with the fields of every object sorted by name
+    /// and the members of every union sorted, so that it only depends on the shape of the data
+    /// and not on the order of the keys or of the values that were merged.
+    /// eg: for snapshot tests of the inferred schema.
+    pub fn to_canonical_string(&self) -> String {
+        let mut schema = self.clone();
+        match &mut schema {
+            Schema::Object(fields) => canonicalize_fields(fields),
+            Schema::Array(ty) => canonicalize(ty),
+        }
+        schema.to_string()
+    }
+}
+
+fn canonicalize(ty: &mut FieldType) {
+    match ty {
+        FieldType::Object(fields) => canonicalize_fields(fields),
+        FieldType::Union(tys) => {
+            tys.iter_mut().for_each(canonicalize);
+            tys.sort_by_cached_key(|ty| ty.to_string());
+        }
+        FieldType::Tuple(tys) => tys.iter_mut().for_each(canonicalize),
+        FieldType::Array(ty)
+        | FieldType::Optional(ty)
+        | FieldType::Map(ty)
+        | FieldType::Constant(ty, _) => canonicalize(ty),
+        _ => {}
     }
+}
 
+fn canonicalize_fields(fields: &mut [Field]) {
     fields
+        .iter_mut()
+        .for_each(|field| canonicalize(&mut field.ty));
+    fields.sort_by(|a, b| a.name.cmp(&b.name));
 }
 
-struct FieldTypeAggregator {
-    ty: Option<FieldType>,
+/// the schema of the json a rust type (de)serializes as, to compare hand written types with inferred ones.
+/// eg: `assert_eq!(User::schema().to_canonical_string(), inferred.to_canonical_string())`
+/// implemented for primitives and std collections, and with `#[derive(JsonSchemaSample)]` (`derive` feature)
+/// for structs and enums. the derive honors the serde attributes that change the shape of the json.
+pub trait JsonSchemaSample {
+    fn field_type() -> FieldType;
+
+    fn schema() -> Schema {
+        match Self::field_type() {
+            FieldType::Object(fields) => Schema::Object(fields),
+            FieldType::EmptyObject => Schema::Object(vec![]),
+            FieldType::Array(ty) => Schema::Array(*ty),
+            ty => Schema::Array(ty),
+        }
+    }
+}
+
+#[cfg(feature = "derive")]
+pub use jsoncodegen_derive::JsonSchemaSample;
+
+macro_rules! json_schema_sample {
+    ($field_type:expr => $($ty:ty),*) => {
+        $(impl JsonSchemaSample for $ty {
+            fn field_type() -> FieldType {
+                $field_type
+            }
+        })*
+    };
 }
 
-impl FieldTypeAggregator {
-    fn new() -> Self {
-        Self { ty: None }
+json_schema_sample!(FieldType::String => String, &str, char);
+json_schema_sample!(FieldType::Integer => i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+json_schema_sample!(FieldType::BigInteger => i128, u128);
+json_schema_sample!(FieldType::Float => f32, f64);
+json_schema_sample!(FieldType::Boolean => bool);
+json_schema_sample!(FieldType::Unknown => Value, ());
+
+impl<T: JsonSchemaSample> JsonSchemaSample for Option<T> {
+    fn field_type() -> FieldType {
+        optional(T::field_type())
     }
+}
 
-    fn add(&mut self, field_type: FieldType) {
-        match self.ty.take() {
-            None => self.ty = Some(field_type),
-            Some(ty) => self.ty = Some(Self::merge(ty, field_type)),
-        };
+impl<T: JsonSchemaSample + ?Sized> JsonSchemaSample for Box<T> {
+    fn field_type() -> FieldType {
+        T::field_type()
     }
+}
 
-    fn finalize(self) -> FieldType {
-        self.ty.unwrap_or(FieldType::Unknown)
+impl<T: JsonSchemaSample> JsonSchemaSample for [T] {
+    fn field_type() -> FieldType {
+        FieldType::Array(Box::new(T::field_type()))
     }
+}
 
-    fn merge(existing: FieldType, new: FieldType) -> FieldType {
-        match (existing, new) {
-            (FieldType::String, FieldType::String) => FieldType::String,
-            (FieldType::Integer, FieldType::Integer) => FieldType::Integer,
-            (FieldType::Float, FieldType::Float) => FieldType::Float,
-            (FieldType::Boolean, FieldType::Boolean) => FieldType::Boolean,
-            (FieldType::Unknown, FieldType::Unknown) => FieldType::Unknown,
+impl<T: JsonSchemaSample, const N: usize> JsonSchemaSample for [T; N] {
+    fn field_type() -> FieldType {
+        <[T]>::field_type()
+    }
+}
 
-            (FieldType::String, FieldType::Integer) | (FieldType::Integer, FieldType::String) => {
-                FieldType::Union(vec![FieldType::String, FieldType::Integer])
-            }
-            (FieldType::String, FieldType::Float) | (FieldType::Float, FieldType::String) => {
-                FieldType::Union(vec![FieldType::String, FieldType::Float])
-            }
-            (FieldType::String, FieldType::Boolean) | (FieldType::Boolean, FieldType::String) => {
-                FieldType::Union(vec![FieldType::String, FieldType::Boolean])
+macro_rules! json_schema_sample_seq {
+    ($($seq:ident)::*) => {
+        impl<T: JsonSchemaSample> JsonSchemaSample for $($seq)::*<T> {
+            fn field_type() -> FieldType {
+                <[T]>::field_type()
             }
-            (FieldType::Integer, FieldType::Float) | (FieldType::Float, FieldType::Integer) => {
-                FieldType::Union(vec![FieldType::Integer, FieldType::Float])
+        }
+    };
+}
+
+json_schema_sample_seq!(Vec);
+json_schema_sample_seq!(std::collections::VecDeque);
+json_schema_sample_seq!(std::collections::HashSet);
+json_schema_sample_seq!(std::collections::BTreeSet);
+
+impl<K, V: JsonSchemaSample> JsonSchemaSample for std::collections::HashMap<K, V> {
+    fn field_type() -> FieldType {
+        FieldType::Map(Box::new(V::field_type()))
+    }
+}
+
+impl<K, V: JsonSchemaSample> JsonSchemaSample for std::collections::BTreeMap<K, V> {
+    fn field_type() -> FieldType {
+        FieldType::Map(Box::new(V::field_type()))
+    }
+}
+
+macro_rules! json_schema_sample_tuple {
+    ($($t:ident),*) => {
+        impl<$($t: JsonSchemaSample),*> JsonSchemaSample for ($($t,)*) {
+            fn field_type() -> FieldType {
+                FieldType::Tuple(vec![$($t::field_type()),*])
             }
-            (FieldType::Integer, FieldType::Boolean) | (FieldType::Boolean, FieldType::Integer) => {
-                FieldType::Union(vec![FieldType::Integer, FieldType::Boolean])
+        }
+    };
+}
+
+json_schema_sample_tuple!(A, B);
+json_schema_sample_tuple!(A, B, C);
+json_schema_sample_tuple!(A, B, C, D);
+json_schema_sample_tuple!(A, B, C, D, E);
+json_schema_sample_tuple!(A, B, C, D, E, F);
+json_schema_sample_tuple!(A, B, C, D, E, F, G);
+json_schema_sample_tuple!(A, B, C, D, E, F, G, H);
+
+impl FieldType {
+    /// the `FieldType`s directly nested inside this one.
+    pub fn dependencies(&self) -> impl Iterator<Item = &FieldType> {
+        let deps: Vec<&FieldType> = match self {
+            FieldType::Object(fields) => fields.iter().map(|field| &field.ty).collect(),
+            FieldType::Union(tys) | FieldType::Tuple(tys) => tys.iter().collect(),
+            FieldType::Array(ty) | FieldType::Optional(ty) | FieldType::Map(ty) => vec![ty],
+            _ => vec![],
+        };
+        deps.into_iter()
+    }
+
+    /// equal apart from the descriptions and examples of the fields of nested objects.
+    pub(crate) fn same_shape(&self, other: &FieldType) -> bool {
+        match (self, other) {
+            (FieldType::Object(fields), FieldType::Object(other_fields)) => {
+                same_fields(fields, other_fields)
             }
-            (FieldType::Float, FieldType::Boolean) | (FieldType::Boolean, FieldType::Float) => {
-                FieldType::Union(vec![FieldType::Float, FieldType::Boolean])
+            (FieldType::Union(tys), FieldType::Union(other_tys))
+            | (FieldType::Tuple(tys), FieldType::Tuple(other_tys)) => {
+                tys.len() == other_tys.len()
+                    && tys
+                        .iter()
+                        .zip(other_tys)
+                        .all(|(ty, other)| ty.same_shape(other))
             }
+            (FieldType::Array(ty), FieldType::Array(other))
+            | (FieldType::Optional(ty), FieldType::Optional(other))
+            | (FieldType::Map(ty), FieldType::Map(other)) => ty.same_shape(other),
+            _ => self == other,
+        }
+    }
+}
 
-            (FieldType::String, FieldType::Object(fields))
-            | (FieldType::Object(fields), FieldType::String) => {
-                FieldType::Union(vec![FieldType::String, FieldType::Object(fields)])
-            }
-            (FieldType::Integer, FieldType::Object(fields))
-            | (FieldType::Object(fields), FieldType::Integer) => {
-                FieldType::Union(vec![FieldType::Integer, FieldType::Object(fields)])
+/// at least half of the field names of both objects together are in both of them.
+/// eg: `{name, children}` and `{name, children, meta}`. see `SchemaInferenceOptions::keep_distinct_objects`
+fn similar_keys(fields: &[Field], other: &[Field]) -> bool {
+    let shared = fields
+        .iter()
+        .filter(|field| other.iter().any(|other| other.name == field.name))
+        .count();
+    let all = fields.len() + other.len() - shared;
+    shared > 0 && shared * 2 >= all
+}
+
+/// same names and types in the same order. see `FieldType::same_shape`
+pub(crate) fn same_fields(fields: &[Field], other: &[Field]) -> bool {
+    fields.len() == other.len()
+        && fields
+            .iter()
+            .zip(other)
+            .all(|(field, other)| field.name == other.name && field.ty.same_shape(&other.ty))
+}
+
+/// compact notation. eg: `{id:int,name:str?,tags:[str],value:int|str,prices:{*:float}}`
+impl Display for Schema {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Schema::Object(fields) => write_fields(f, fields),
+            Schema::Array(ty) => write!(f, "[{ty}]"),
+        }
+    }
+}
+
+impl Display for FieldType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldType::String => write!(f, "str"),
+            FieldType::Integer => write!(f, "int"),
+            FieldType::BigInteger => write!(f, "bigint"),
+            FieldType::Float => write!(f, "float"),
+            FieldType::Boolean => write!(f, "bool"),
+            FieldType::Unknown => write!(f, "any"),
+            FieldType::EmptyObject => write!(f, "{{}}"),
+            FieldType::Object(fields) => write_fields(f, fields),
+            FieldType::Union(tys) => {
+                for (idx, ty) in tys.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, "|")?;
+                    }
+                    write!(f, "{ty}")?;
+                }
+                Ok(())
             }
-            (FieldType::Float, FieldType::Object(fields))
-            | (FieldType::Object(fields), FieldType::Float) => {
-                FieldType::Union(vec![FieldType::Float, FieldType::Object(fields)])
+            FieldType::Array(ty) => write!(f, "[{ty}]"),
+            FieldType::Map(ty) => write!(f, "{{*:{ty}}}"),
+            FieldType::Optional(ty) => match ty.as_ref() {
+                FieldType::Union(_) => write!(f, "({ty})?"),
+                _ => write!(f, "{ty}?"),
+            },
+            FieldType::Constant(_, value) => write!(f, "{value}"),
+            FieldType::Format(StringFormat::DateTime) => write!(f, "datetime"),
+            FieldType::Format(StringFormat::Date) => write!(f, "date"),
+            FieldType::Format(StringFormat::Uuid) => write!(f, "uuid"),
+            FieldType::Format(StringFormat::Base64) => write!(f, "base64"),
+            FieldType::Tuple(tys) => {
+                write!(f, "(")?;
+                for (idx, ty) in tys.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{ty}")?;
+                }
+                write!(f, ")")
             }
-            (FieldType::Boolean, FieldType::Object(fields))
-            | (FieldType::Object(fields), FieldType::Boolean) => {
-                FieldType::Union(vec![FieldType::Boolean, FieldType::Object(fields)])
+            FieldType::Ref(name) => {
+                write!(f, "&")?;
+                write_name(f, name)
             }
+        }
+    }
+}
 
-            (FieldType::String, FieldType::Union(mut tys))
-            | (FieldType::Union(mut tys), FieldType::String) => {
-                if !tys.contains(&FieldType::String) {
-                    tys.push(FieldType::String);
-                }
-                FieldType::Union(tys)
+fn write_fields(f: &mut Formatter<'_>, fields: &[Field]) -> fmt::Result {
+    write!(f, "{{")?;
+    for (idx, field) in fields.iter().enumerate() {
+        if idx > 0 {
+            write!(f, ",")?;
+        }
+        write_name(f, &field.name)?;
+        write!(f, ":{}", field.ty)?;
+    }
+    write!(f, "}}")
+}
+
+/// names that aren't plain words are quoted. eg: `{"first name":str}`
+fn write_name(f: &mut Formatter<'_>, name: &str) -> fmt::Result {
+    match !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        true => write!(f, "{name}"),
+        false => write!(f, "{}", Value::from(name)),
+    }
+}
+
+/// parses the compact notation printed by `Display`. eg: `{id:int,name:str?,tags:[str]}`
+/// whitespace between the tokens is allowed. descriptions and examples aren't part of the notation.
+impl FromStr for Schema {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser { s, pos: 0 };
+        parser.skip_whitespace();
+        let start = parser.pos;
+        let schema = match parser.parse_type()? {
+            FieldType::EmptyObject => Schema::Object(vec![]),
+            FieldType::Object(fields) => Schema::Object(fields),
+            FieldType::Array(ty) => Schema::Array(*ty),
+            _ => return Err(parser.error_at(start, "expected an object or an array")),
+        };
+        parser.end()?;
+        Ok(schema)
+    }
+}
+
+/// parses the compact notation printed by `Display`. eg: `(int|str)?`
+impl FromStr for FieldType {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser { s, pos: 0 };
+        let ty = parser.parse_type()?;
+        parser.end()?;
+        Ok(ty)
+    }
+}
+
+/// the schema string isn't in the compact notation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// byte offset of the problem in the parsed string.
+    pub offset: usize,
+    pub message: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte {}", self.message, self.offset)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// recursive descent over the grammar
+///
+/// ```text
+/// type   := term ('|' term)*
+/// term   := atom '?'?
+/// atom   := keyword | constant | '&' name | '[' type ']' | '(' type (',' type)* ')'
+///         | '{' '}' | '{' '*' ':' type '}' | '{' name ':' type (',' name ':' type)* '}'
+/// ```
+struct Parser<'s> {
+    s: &'s str,
+    pos: usize,
+}
+
+impl<'s> Parser<'s> {
+    fn parse_type(&mut self) -> Result<FieldType, ParseError> {
+        let mut tys = vec![self.parse_term()?];
+        while self.eat('|') {
+            tys.push(self.parse_term()?);
+        }
+        Ok(match tys.len() {
+            1 => tys.remove(0),
+            _ => FieldType::Union(tys),
+        })
+    }
+
+    fn parse_term(&mut self) -> Result<FieldType, ParseError> {
+        let ty = self.parse_atom()?;
+        Ok(match self.eat('?') {
+            true => FieldType::Optional(Box::new(ty)),
+            false => ty,
+        })
+    }
+
+    fn parse_atom(&mut self) -> Result<FieldType, ParseError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        match self.peek() {
+            Some('[') => {
+                self.pos += 1;
+                let ty = self.parse_type()?;
+                self.expect(']')?;
+                Ok(FieldType::Array(Box::new(ty)))
             }
-            (FieldType::Integer, FieldType::Union(mut tys))
-            | (FieldType::Union(mut tys), FieldType::Integer) => {
-                if !tys.contains(&FieldType::Integer) {
-                    tys.push(FieldType::Integer);
+            // a tuple, or a union grouped to be made optional. eg: `(float,float)` or `(int|str)?`
+            Some('(') => {
+                self.pos += 1;
+                let mut tys = vec![self.parse_type()?];
+                while self.eat(',') {
+                    tys.push(self.parse_type()?);
                 }
-                FieldType::Union(tys)
+                self.expect(')')?;
+                Ok(match tys.len() {
+                    1 => tys.remove(0),
+                    _ => FieldType::Tuple(tys),
+                })
             }
-            (FieldType::Float, FieldType::Union(mut tys))
-            | (FieldType::Union(mut tys), FieldType::Float) => {
-                if !tys.contains(&FieldType::Float) {
-                    tys.push(FieldType::Float);
+            Some('{') => {
+                self.pos += 1;
+                if self.eat('}') {
+                    return Ok(FieldType::EmptyObject);
                 }
-                FieldType::Union(tys)
-            }
-            (FieldType::Boolean, FieldType::Union(mut tys))
-            | (FieldType::Union(mut tys), FieldType::Boolean) => {
-                if !tys.contains(&FieldType::Boolean) {
-                    tys.push(FieldType::Boolean);
+                if self.eat('*') {
+                    self.expect(':')?;
+                    let ty = self.parse_type()?;
+                    self.expect('}')?;
+                    return Ok(FieldType::Map(Box::new(ty)));
                 }
-                FieldType::Union(tys)
+                let mut fields: Vec<Field> = vec![];
+                loop {
+                    self.skip_whitespace();
+                    let name_start = self.pos;
+                    let name = self.parse_name()?;
+                    if fields.iter().any(|field| field.name == name) {
+                        return Err(self.error_at(name_start, format!("duplicate field `{name}`")));
+                    }
+                    self.expect(':')?;
+                    fields.push(Field {
+                        name,
+                        ty: self.parse_type()?,
+                        description: None,
+                        example: None,
+                    });
+                    if !self.eat(',') {
+                        break;
+                    }
+                }
+                self.expect('}')?;
+                Ok(FieldType::Object(fields))
             }
-
-            (FieldType::String, FieldType::Array(ty))
-            | (FieldType::Array(ty), FieldType::String) => {
-                FieldType::Union(vec![FieldType::String, FieldType::Array(ty)])
+            Some('&') => {
+                self.pos += 1;
+                Ok(FieldType::Ref(self.parse_name()?))
             }
-            (FieldType::Integer, FieldType::Array(ty))
-            | (FieldType::Array(ty), FieldType::Integer) => {
-                FieldType::Union(vec![FieldType::Integer, FieldType::Array(ty)])
+            Some('"') => {
+                let value = Value::String(self.parse_string()?);
+                Ok(FieldType::Constant(Box::new(FieldType::String), value))
             }
-            (FieldType::Float, FieldType::Array(ty)) | (FieldType::Array(ty), FieldType::Float) => {
-                FieldType::Union(vec![FieldType::Float, FieldType::Array(ty)])
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let text = self.take_while(|c| c.is_ascii_digit() || "+-.eE".contains(c));
+                let n = serde_json::from_str::<serde_json::Number>(text)
+                    .map_err(|_| self.error_at(start, format!("invalid number `{text}`")))?;
+                // floats are always printed with a `.` or an exponent. an integer that doesn't fit
+                // in 64 bits would silently become an approximate float.
+                if n.is_f64() && !text.contains(['.', 'e', 'E']) {
+                    return Err(self.error_at(start, format!("integer `{text}` is out of range")));
+                }
+                let ty = match (n.is_f64(), n.is_i64()) {
+                    (true, _) => FieldType::Float,
+                    (false, true) => FieldType::Integer,
+                    (false, false) => FieldType::BigInteger,
+                };
+                Ok(FieldType::Constant(Box::new(ty), Value::Number(n)))
             }
-            (FieldType::Boolean, FieldType::Array(ty))
-            | (FieldType::Array(ty), FieldType::Boolean) => {
-                FieldType::Union(vec![FieldType::Boolean, FieldType::Array(ty)])
+            Some(c) if c.is_ascii_alphabetic() => {
+                match self.take_while(|c| c.is_ascii_alphanumeric() || c == '_') {
+                    "str" => Ok(FieldType::String),
+                    "int" => Ok(FieldType::Integer),
+                    "bigint" => Ok(FieldType::BigInteger),
+                    "float" => Ok(FieldType::Float),
+                    "bool" => Ok(FieldType::Boolean),
+                    "any" => Ok(FieldType::Unknown),
+                    "datetime" => Ok(FieldType::Format(StringFormat::DateTime)),
+                    "date" => Ok(FieldType::Format(StringFormat::Date)),
+                    "uuid" => Ok(FieldType::Format(StringFormat::Uuid)),
+                    "base64" => Ok(FieldType::Format(StringFormat::Base64)),
+                    "true" => Ok(FieldType::Constant(
+                        Box::new(FieldType::Boolean),
+                        Value::Bool(true),
+                    )),
+                    "false" => Ok(FieldType::Constant(
+                        Box::new(FieldType::Boolean),
+                        Value::Bool(false),
+                    )),
+                    word => Err(self.error_at(start, format!("unknown type `{word}`"))),
+                }
             }
+            Some(c) => Err(self.error_at(start, format!("expected a type, found `{c}`"))),
+            None => Err(self.error_at(start, "expected a type, found the end")),
+        }
+    }
 
-            (FieldType::Optional(ty), FieldType::Unknown)
-            | (FieldType::Unknown, FieldType::Optional(ty)) => FieldType::Optional(ty),
-            (ft, FieldType::Unknown) | (FieldType::Unknown, ft) => {
-                FieldType::Optional(Box::new(ft))
-            }
-            (FieldType::String, FieldType::Optional(ty))
-            | (FieldType::Optional(ty), FieldType::String) => {
-                FieldType::Optional(Box::new(Self::merge(FieldType::String, *ty)))
-            }
-            (FieldType::Integer, FieldType::Optional(ty))
-            | (FieldType::Optional(ty), FieldType::Integer) => {
-                FieldType::Optional(Box::new(Self::merge(FieldType::Integer, *ty)))
+    /// a plain word or a json string. eg: `first_name` or `"first name"`
+    fn parse_name(&mut self) -> Result<String, ParseError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('"') => self.parse_string(),
+            _ => match self.take_while(|c| c.is_ascii_alphanumeric() || c == '_') {
+                "" => Err(self.error("expected a field name")),
+                name => Ok(name.into()),
+            },
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, ParseError> {
+        let start = self.pos;
+        let mut escaped = false;
+        for (idx, c) in self.s[start + 1..].char_indices() {
+            match (escaped, c) {
+                (false, '"') => {
+                    self.pos = start + 1 + idx + 1;
+                    return serde_json::from_str(&self.s[start..self.pos])
+                        .map_err(|err| self.error_at(start, format!("invalid string: {err}")));
+                }
+                (false, '\\') => escaped = true,
+                _ => escaped = false,
             }
-            (FieldType::Float, FieldType::Optional(ty))
-            | (FieldType::Optional(ty), FieldType::Float) => {
-                FieldType::Optional(Box::new(Self::merge(FieldType::Float, *ty)))
+        }
+        Err(self.error_at(start, "unterminated string"))
+    }
+
+    fn take_while(&mut self, pred: impl Fn(char) -> bool) -> &'s str {
+        let start = self.pos;
+        let len = self.s[start..]
+            .find(|c| !pred(c))
+            .unwrap_or(self.s.len() - start);
+        self.pos += len;
+        &self.s[start..self.pos]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.s[self.pos..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.take_while(char::is_whitespace);
+    }
+
+    /// consumes `c` if it is the next token.
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        match self.peek() == Some(c) {
+            true => {
+                self.pos += c.len_utf8();
+                true
             }
-            (FieldType::Boolean, FieldType::Optional(ty))
-            | (FieldType::Optional(ty), FieldType::Boolean) => {
-                FieldType::Optional(Box::new(Self::merge(FieldType::Boolean, *ty)))
+            false => false,
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), ParseError> {
+        match self.eat(c) {
+            true => Ok(()),
+            false => Err(self.unexpected(&format!("`{c}`"))),
+        }
+    }
+
+    fn end(&mut self) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        match self.pos == self.s.len() {
+            true => Ok(()),
+            false => Err(self.unexpected("the end")),
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> ParseError {
+        match self.peek() {
+            Some(c) => self.error(format!("expected {expected}, found `{c}`")),
+            None => self.error(format!("expected {expected}, found the end")),
+        }
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        self.error_at(self.pos, message)
+    }
+
+    fn error_at(&self, offset: usize, message: impl Into<String>) -> ParseError {
+        ParseError {
+            offset,
+            message: message.into(),
+        }
+    }
+}
+
+/// one difference between two schemas. see `diff`
+#[derive(Debug, Clone, PartialEq)]
+pub enum SchemaChange {
+    Added {
+        /// json path. eg: `$.users[].email`
+        path: String,
+        ty: FieldType,
+    },
+    Removed {
+        path: String,
+        ty: FieldType,
+    },
+    /// the value at `path` has another type, apart from becoming optional or required.
+    /// `old` and `new` include the `Optional` of the field.
+    TypeChanged {
+        path: String,
+        old: FieldType,
+        new: FieldType,
+    },
+    /// the value at `path` became optional (`true`) or required (`false`).
+    OptionalityChanged {
+        path: String,
+        optional: bool,
+    },
+}
+
+impl SchemaChange {
+    /// code generated for the old schema can't read json of the new one:
+    /// a field that was always there is missing (or may be) or has another type.
+    pub fn is_breaking(&self) -> bool {
+        match self {
+            SchemaChange::Added { .. } => false,
+            SchemaChange::Removed { ty, .. } => !matches!(ty, FieldType::Optional(_)),
+            SchemaChange::TypeChanged { old, .. } => !matches!(old, FieldType::Optional(_)),
+            SchemaChange::OptionalityChanged { optional, .. } => *optional,
+        }
+    }
+}
+
+/// eg: `changed $.id: int -> str`
+impl Display for SchemaChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaChange::Added { path, ty } => write!(f, "added {path}: {ty}"),
+            SchemaChange::Removed { path, ty } => write!(f, "removed {path}: {ty}"),
+            SchemaChange::TypeChanged { path, old, new } => {
+                write!(f, "changed {path}: {old} -> {new}")
             }
-            (FieldType::Object(fields), FieldType::Optional(ty))
-            | (FieldType::Optional(ty), FieldType::Object(fields)) => {
-                FieldType::Optional(Box::new(Self::merge(FieldType::Object(fields), *ty)))
+            SchemaChange::OptionalityChanged {
+                path,
+                optional: true,
+            } => write!(f, "{path} became optional"),
+            SchemaChange::OptionalityChanged {
+                path,
+                optional: false,
+            } => write!(f, "{path} became required"),
+        }
+    }
+}
+
+/// what changed from the `old` schema to the `new` one, eg: between the schemas inferred
+/// from yesterday's and today's response of an api. the changes inside an object are sorted
+/// by field name, and the order of union members doesn't matter.
+/// descriptions and examples aren't compared.
+pub fn diff(old: &Schema, new: &Schema) -> Vec<SchemaChange> {
+    let root = |schema: &Schema| {
+        let mut ty = match schema.clone() {
+            Schema::Object(fields) => FieldType::Object(fields),
+            Schema::Array(ty) => FieldType::Array(Box::new(ty)),
+        };
+        canonicalize(&mut ty);
+        ty
+    };
+
+    let mut changes = vec![];
+    diff_types("$", &root(old), &root(new), &mut changes);
+    changes
+}
+
+fn diff_types(path: &str, old: &FieldType, new: &FieldType, changes: &mut Vec<SchemaChange>) {
+    fn strip(ty: &FieldType) -> (&FieldType, bool) {
+        match ty {
+            FieldType::Optional(ty) => (ty, true),
+            ty => (ty, false),
+        }
+    }
+    let ((old_ty, old_optional), (new_ty, new_optional)) = (strip(old), strip(new));
+    let start = changes.len();
+
+    match (old_ty, new_ty) {
+        (
+            FieldType::Object(_) | FieldType::EmptyObject,
+            FieldType::Object(_) | FieldType::EmptyObject,
+        ) => {
+            fn fields(ty: &FieldType) -> &[Field] {
+                match ty {
+                    FieldType::Object(fields) => fields,
+                    _ => &[],
+                }
             }
-            (FieldType::Union(union_types), FieldType::Optional(ty))
-            | (FieldType::Optional(ty), FieldType::Union(union_types)) => {
-                FieldType::Optional(Box::new(Self::merge(FieldType::Union(union_types), *ty)))
+            let (old_fields, new_fields) = (fields(old_ty), fields(new_ty));
+            let mut names: Vec<&String> = old_fields
+                .iter()
+                .chain(new_fields)
+                .map(|field| &field.name)
+                .collect();
+            names.sort();
+            names.dedup();
+            for name in names {
+                let path = format!("{path}{}", path_segment(name));
+                let find = |fields: &[Field]| fields.iter().position(|field| &field.name == name);
+                match (find(old_fields), find(new_fields)) {
+                    (Some(old), Some(new)) => {
+                        diff_types(&path, &old_fields[old].ty, &new_fields[new].ty, changes)
+                    }
+                    (Some(old), None) => changes.push(SchemaChange::Removed {
+                        path,
+                        ty: old_fields[old].ty.clone(),
+                    }),
+                    (None, Some(new)) => changes.push(SchemaChange::Added {
+                        path,
+                        ty: new_fields[new].ty.clone(),
+                    }),
+                    (None, None) => {}
+                }
             }
-            (FieldType::Array(arr_ty), FieldType::Optional(op_ty))
-            | (FieldType::Optional(op_ty), FieldType::Array(arr_ty)) => {
-                FieldType::Optional(Box::new(Self::merge(FieldType::Array(arr_ty), *op_ty)))
+        }
+        (FieldType::Array(old_ty), FieldType::Array(new_ty)) => {
+            diff_types(&format!("{path}[]"), old_ty, new_ty, changes)
+        }
+        (FieldType::Map(old_ty), FieldType::Map(new_ty)) => {
+            diff_types(&format!("{path}.*"), old_ty, new_ty, changes)
+        }
+        (FieldType::Tuple(old_tys), FieldType::Tuple(new_tys))
+            if old_tys.len() == new_tys.len() =>
+        {
+            for (idx, (old_ty, new_ty)) in old_tys.iter().zip(new_tys).enumerate() {
+                diff_types(&format!("{path}[{idx}]"), old_ty, new_ty, changes);
             }
+        }
+        _ if !old_ty.same_shape(new_ty) => {
+            changes.push(SchemaChange::TypeChanged {
+                path: path.into(),
+                old: old.clone(),
+                new: new.clone(),
+            });
+            return;
+        }
+        _ => {}
+    }
 
-            (FieldType::Object(existing_fields), FieldType::Object(new_fields)) => {
-                FieldType::Object(Self::merge_obj_fields(existing_fields, new_fields))
-            }
+    if old_optional != new_optional {
+        // before the changes inside the value.
+        changes.insert(
+            start,
+            SchemaChange::OptionalityChanged {
+                path: path.into(),
+                optional: new_optional,
+            },
+        );
+    }
+}
 
-            (FieldType::Object(obj_fields), FieldType::Union(mut union_types))
-            | (FieldType::Union(mut union_types), FieldType::Object(obj_fields)) => {
-                match union_types
-                    .iter_mut()
-                    .filter_map(|ty| match ty {
-                        FieldType::Object(existing_obj_fields) => Some(existing_obj_fields),
-                        _ => None,
-                    })
-                    .next()
-                {
-                    Some(existing_obj_fields) => match obj_fields == *existing_obj_fields {
-                        true => FieldType::Union(union_types),
-                        false => {
-                            let merged_obj_fields =
-                                Self::merge_obj_fields(existing_obj_fields.clone(), obj_fields);
-                            *existing_obj_fields = merged_obj_fields;
-                            FieldType::Union(union_types)
-                        }
-                    },
-                    None => {
-                        union_types.push(FieldType::Object(obj_fields));
-                        FieldType::Union(union_types)
-                    }
-                }
-            }
-            (FieldType::Array(arr_type), FieldType::Union(mut union_types))
-            | (FieldType::Union(mut union_types), FieldType::Array(arr_type)) => match union_types
-                .iter_mut()
-                .filter_map(|ty| match ty {
-                    FieldType::Array(existing_arr_ty) => Some(existing_arr_ty),
-                    _ => None,
-                })
-                .next()
-            {
-                Some(existing_arr_type) => match *existing_arr_type == arr_type {
-                    true => FieldType::Union(union_types),
-                    false => {
-                        let merged_arr_type =
-                            Self::merge(existing_arr_type.deref().deref().clone(), *arr_type);
-                        *existing_arr_type = Box::new(merged_arr_type);
-                        FieldType::Union(union_types)
-                    }
-                },
-                None => {
-                    union_types.push(FieldType::Array(arr_type));
-                    FieldType::Union(union_types)
-                }
-            },
+/// `.key`, or `["key"]` for keys that aren't plain words. eg: `$.cart[].tags[]` or `$["first name"]`
+pub(crate) fn path_segment(key: &str) -> String {
+    match !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        true => format!(".{key}"),
+        false => format!("[{}]", Value::from(key)),
+    }
+}
 
-            (FieldType::Object(obj_fields), FieldType::Array(arr_ty))
-            | (FieldType::Array(arr_ty), FieldType::Object(obj_fields)) => FieldType::Union(vec![
-                FieldType::Object(obj_fields),
-                FieldType::Array(arr_ty),
-            ]),
+pub struct Iter<'s> {
+    stack: Vec<&'s FieldType>,
+}
 
-            (FieldType::Union(existing_types), FieldType::Union(new_types)) => {
-                let mut merged_types = existing_types;
-                for new_type in new_types {
-                    if !merged_types.contains(&new_type) {
-                        merged_types.push(new_type);
-                    }
-                }
-                FieldType::Union(merged_types)
-            }
+impl<'s> Iterator for Iter<'s> {
+    type Item = &'s FieldType;
 
-            (FieldType::Array(existing_ele_type), FieldType::Array(new_ele_type)) => {
-                let merged_ele_type = Self::merge(*existing_ele_type, *new_ele_type);
-                FieldType::Array(Box::new(merged_ele_type))
-            }
+    fn next(&mut self) -> Option<Self::Item> {
+        let ty = self.stack.pop()?;
+        let deps: Vec<&FieldType> = ty.dependencies().collect();
+        self.stack.extend(deps.into_iter().rev());
+        Some(ty)
+    }
+}
 
-            (FieldType::Optional(existing_ty), FieldType::Optional(new_ty)) => {
-                FieldType::Optional(Box::new(Self::merge(*existing_ty, *new_ty)))
+#[derive(Debug, Clone, Default)]
+pub struct SchemaInferenceOptions {
+    /// dotted paths of fields to leave out of the schema. eg: `user._links`
+    /// `*` matches any single field name and `**` matches any number of nested field names.
+    /// array elements don't add a path segment, so `users.id` matches `id` in every element of `users`
+    pub exclude: Vec<String>,
+    pub field_order: FieldOrder,
+    /// objects of different shapes that end up in a union (eg: `[{"a": 1}, [1], {"b": 2}]`)
+    /// stay separate members instead of being merged into one object with optional fields.
+    /// objects that share most of their keys are still merged, so `{"a": 1}` and `{"a": null, "b": 2}`
+    /// become a single `{a:int?,b:int?}`
+    pub keep_distinct_objects: bool,
+    /// primitive fields of objects that have the same value in every sample (at least two)
+    /// become `FieldType::Constant`. eg: `"version": "1.0"` in every element of an array
+    pub detect_constants: bool,
+    /// a sibling string `{key}_description` or `{key}.$description` becomes the description of `key`
+    /// instead of a field of its own. eg: `{"id": 1, "id_description": "primary key"}`
+    pub extract_descriptions: bool,
+    /// objects whose keys are all data (start with a digit or are uuids. eg: dates or ids)
+    /// become `FieldType::Map` of the merged type of their values.
+    pub detect_maps: bool,
+    /// the first string, number or boolean value of each field becomes its `Field::example`.
+    pub examples: bool,
+    /// strings that are always date-times, dates or uuids become `FieldType::Format`
+    pub detect_formats: bool,
+    /// strings that are always base64 become `FieldType::Format(StringFormat::Base64)`.
+    /// separate from `detect_formats` because plain words can be valid base64 too:
+    /// only strings of at least 16 characters that aren't all letters or all hex digits (eg: hashes) count.
+    pub detect_base64: bool,
+    /// arrays of 2 to 8 elements whose positions keep their own types become `FieldType::Tuple`.
+    /// eg: `"point": [1.5, 2.5]` or `"pair": ["id", 42]` in every element of an array.
+    /// it takes at least two arrays of the same length to tell a tuple from a list.
+    pub detect_tuples: bool,
+    /// document with the definitions that `{"$ref": "#/definitions/Address"}` objects point to
+    /// (often the json itself). a reference is replaced by the definition before its type is inferred.
+    /// a reference to a definition that doesn't exist becomes `null`.
+    /// a reference back to a definition that is being resolved (a recursive type)
+    /// becomes `FieldType::Ref`, named after the last part of the pointer (eg: `&Node`).
+    pub resolve_refs: Option<Value>,
+    /// objects and arrays nested deeper than this (the top level value is at depth 1) are an error
+    /// of `try_extract_with`, `Schema::try_from_values_with`, `Schema::from_reader_with` and
+    /// `FieldTypeAggregator::try_add_value`, and a panic of the functions without `try_`. unbounded when `None`.
+    pub max_depth: Option<usize>,
+}
+
+/// order of the fields of an object.
+/// in either case, keys first seen in a later element of an array are appended after the ones already seen,
+/// except in the objects merged by `SchemaInferenceOptions::keep_distinct_objects`, which are sorted again.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FieldOrder {
+    /// keys of every object sorted by name.
+    #[default]
+    Alphabetical,
+    /// keys in the order they appear in the json.
+    /// not canonical: the same data with reordered keys produces a different schema.
+    /// a `serde_json::Value` only keeps the order of its keys with the `preserve_order` feature,
+    /// which enables serde_json's. without it, the keys of a `Value` are sorted.
+    Insertion,
+}
+
+impl SchemaInferenceOptions {
+    /// the options that are inferred from all the values of a field at once. see `refine`
+    fn keep_samples(&self) -> bool {
+        self.detect_constants || self.detect_tuples
+    }
+
+    fn excluded(&self, path: &[String]) -> bool {
+        self.exclude.iter().any(|pattern| {
+            let pattern: Vec<&str> = pattern.split('.').collect();
+            glob_match(&pattern, path)
+        })
+    }
+}
+
+fn glob_match(pattern: &[&str], path: &[String]) -> bool {
+    match (pattern, path) {
+        ([], []) => true,
+        (["**", rest @ ..], _) => {
+            glob_match(rest, path) || (!path.is_empty() && glob_match(pattern, &path[1..]))
+        }
+        ([segment, pattern_rest @ ..], [name, path_rest @ ..]) => {
+            (*segment == "*" || segment == name) && glob_match(pattern_rest, path_rest)
+        }
+        _ => false,
+    }
+}
+
+pub fn extract(json: Value) -> Schema {
+    extract_with(json, &SchemaInferenceOptions::default())
+}
+
+/// panics if `json` isn't an object or an array, or is nested deeper than
+/// `SchemaInferenceOptions::max_depth`. see `try_extract_with`
+pub fn extract_with(json: Value, options: &SchemaInferenceOptions) -> Schema {
+    try_extract_with(json, options).unwrap_or_else(|err| panic!("{err}"))
+}
+
+/// `extract_with`, or an error if `json` isn't an object or an array, or is nested deeper than
+/// `SchemaInferenceOptions::max_depth` instead of inferring its schema with unbounded recursion.
+pub fn try_extract_with(
+    json: Value,
+    options: &SchemaInferenceOptions,
+) -> Result<Schema, ExtractError> {
+    if !(json.is_object() || json.is_array()) {
+        return Err(ExtractError::InvalidTopLevel);
+    }
+    if let Some(max_depth) = options.max_depth {
+        check_depth(&json, max_depth, 1, &Location::Root)?;
+    }
+
+    let samples = options.keep_samples().then(|| json.clone());
+    let json = match &options.resolve_refs {
+        Some(document) => resolve_refs(json, document),
+        None => json,
+    };
+
+    let mut schema = match json {
+        Value::Array(arr) => Schema::Array(array(arr, options, &[])),
+        Value::Object(obj) => Schema::Object(object(entries(obj, options, &[]), options, &[])),
+        _ => unreachable!("checked above"),
+    };
+
+    if let Some(json) = samples {
+        refine(&mut schema, &[&json], options);
+    }
+
+    Ok(schema)
+}
+
+/// the json is nested deeper than `SchemaInferenceOptions::max_depth`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DepthError {
+    pub max_depth: usize,
+    /// path of the first value that is too deep. eg: `a.b[0].c`
+    pub path: String,
+}
+
+impl Display for DepthError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "json is nested deeper than {} levels at {}",
+            self.max_depth, self.path
+        )
+    }
+}
+
+impl std::error::Error for DepthError {}
+
+/// why `try_extract_with` has no schema for the json.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtractError {
+    /// the top level value is a primitive. only objects and arrays have a schema.
+    InvalidTopLevel,
+    TooDeep(DepthError),
+}
+
+impl Display for ExtractError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ExtractError::InvalidTopLevel => {
+                write!(f, "the top level json value must be an object or an array")
             }
+            ExtractError::TooDeep(err) => write!(f, "{err}"),
         }
     }
+}
 
-    fn merge_obj_fields(mut existing_fields: Vec<Field>, mut new_fields: Vec<Field>) -> Vec<Field> {
-        existing_fields = existing_fields
-            .into_iter()
-            .map(|mut existing_field| {
-                match new_fields
-                    .iter()
-                    .find(|new_field| existing_field.name == new_field.name)
-                {
-                    Some(_) => existing_field,
-                    None => {
-                        existing_field.ty = FieldType::Optional(Box::new(existing_field.ty));
-                        existing_field
-                    }
-                }
-            })
-            .collect();
+impl std::error::Error for ExtractError {}
 
-        new_fields = new_fields
-            .into_iter()
-            .map(|mut new_field| {
-                match existing_fields
-                    .iter()
-                    .find(|existing_field| existing_field.name == new_field.name)
-                {
-                    Some(_) => new_field,
-                    None => {
-                        new_field.ty = FieldType::Optional(Box::new(new_field.ty));
-                        new_field
-                    }
-                }
-            })
-            .collect();
+impl From<DepthError> for ExtractError {
+    fn from(err: DepthError) -> Self {
+        ExtractError::TooDeep(err)
+    }
+}
 
-        let mut merged_fields = existing_fields;
-        for new_field in new_fields {
-            match merged_fields.iter_mut().find(|f| f.name == new_field.name) {
-                Some(field) => field.ty = Self::merge(field.ty.clone(), new_field.ty),
-                None => merged_fields.push(new_field),
+/// where a value is in its document. only turned into a path for a `DepthError`
+#[derive(Clone, Copy)]
+enum Location<'a> {
+    Root,
+    Key(&'a Location<'a>, &'a str),
+    Index(&'a Location<'a>, usize),
+}
+
+impl Location<'_> {
+    /// the top level value is at depth 1
+    fn depth(&self) -> usize {
+        match self {
+            Location::Root => 1,
+            Location::Key(parent, _) | Location::Index(parent, _) => parent.depth() + 1,
+        }
+    }
+
+    /// `DepthError` of the value at `rest` (eg: `[".b", "[0]"]`) under this location.
+    fn depth_error(&self, max_depth: usize, rest: &[String]) -> DepthError {
+        let mut segments = vec![];
+        let mut location = self;
+        loop {
+            match location {
+                Location::Root => break,
+                Location::Key(parent, key) => {
+                    segments.push(format!(".{key}"));
+                    location = parent;
+                }
+                Location::Index(parent, idx) => {
+                    segments.push(format!("[{idx}]"));
+                    location = parent;
+                }
             }
         }
-        merged_fields
+        segments.reverse();
+        segments.extend_from_slice(rest);
+        DepthError {
+            max_depth,
+            path: segments.concat().trim_start_matches('.').into(),
+        }
     }
 }
 
-fn array(arr: Vec<Value>) -> FieldType {
-    let mut agg = FieldTypeAggregator::new();
+/// `json` is at `depth` and `location` of its document.
+/// iterative, so that it doesn't overflow the stack on the input it is meant to reject.
+fn check_depth(
+    json: &Value,
+    max_depth: usize,
+    depth: usize,
+    location: &Location,
+) -> Result<(), DepthError> {
+    if (json.is_object() || json.is_array()) && depth > max_depth {
+        return Err(location.depth_error(max_depth, &[]));
+    }
 
-    for value in arr {
-        let field_type = field_type(value);
-        agg.add(field_type);
+    // the children of every object and array on the way from `json` to the current value.
+    let mut stack = vec![children(json)];
+    let mut path: Vec<String> = vec![];
+
+    while let Some(level) = stack.last_mut() {
+        let Some((segment, value)) = level.next() else {
+            stack.pop();
+            path.pop();
+            continue;
+        };
+        if !(value.is_object() || value.is_array()) {
+            continue;
+        }
+
+        path.push(segment);
+        if depth + stack.len() > max_depth {
+            return Err(location.depth_error(max_depth, &path));
+        }
+        stack.push(children(value));
     }
 
-    agg.finalize()
+    Ok(())
 }
 
-fn field_type(value: Value) -> FieldType {
+/// path segment (eg: `.key` or `[0]`) and value of every element of an object or array.
+fn children(value: &Value) -> Box<dyn Iterator<Item = (String, &Value)> + '_> {
     match value {
-        Value::Null => FieldType::Unknown,
-        Value::Bool(_) => FieldType::Boolean,
-        Value::Number(n) => match n.is_f64() {
-            true => FieldType::Float,
-            false => FieldType::Integer,
-        },
-        Value::String(_) => FieldType::String,
-        Value::Array(arr) => FieldType::Array(Box::new(array(arr))),
-        Value::Object(obj) => FieldType::Object(object(obj)),
+        Value::Object(obj) => Box::new(obj.iter().map(|(key, value)| (format!(".{key}"), value))),
+        Value::Array(arr) => Box::new(
+            arr.iter()
+                .enumerate()
+                .map(|(idx, value)| (format!("[{idx}]"), value)),
+        ),
+        _ => Box::new(std::iter::empty()),
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use pretty_assertions::assert_eq;
+impl Schema {
+    /// schema of several json documents merged as if they were the elements of one array.
+    /// eg: example responses of an api where each one only has some of the optional fields.
+    /// fields missing from some of the documents are optional.
+    pub fn from_values(values: impl IntoIterator<Item = Value>) -> Schema {
+        Schema::from_values_with(values, &SchemaInferenceOptions::default())
+    }
 
-    fn json(text: &str) -> Value {
-        serde_json::from_str(text).unwrap()
+    /// objects merge into a `Schema::Object` and arrays into a `Schema::Array` of their merged elements.
+    /// any other mix is a `Schema::Array` of the merged type of the values.
+    /// the documents are only kept in memory for `detect_constants`.
+    /// panics if a value is nested deeper than `SchemaInferenceOptions::max_depth`. see `Schema::try_from_values_with`
+    pub fn from_values_with(
+        values: impl IntoIterator<Item = Value>,
+        options: &SchemaInferenceOptions,
+    ) -> Schema {
+        Schema::try_from_values_with(values, options).unwrap_or_else(|err| panic!("{err}"))
     }
 
-    #[test]
-    fn empty() {
-        assert_eq!(extract(json("{}")), Schema::Object(vec![]));
-        assert_eq!(extract(json("[]")), Schema::Array(FieldType::Unknown));
+    /// `Schema::from_values_with`, or an error if a value is nested deeper than `SchemaInferenceOptions::max_depth`
+    pub fn try_from_values_with(
+        values: impl IntoIterator<Item = Value>,
+        options: &SchemaInferenceOptions,
+    ) -> Result<Schema, DepthError> {
+        let mut samples = vec![];
+        let mut agg = FieldTypeAggregator::with_options(options);
+        for value in values {
+            if options.keep_samples() {
+                samples.push(value.clone());
+            }
+            agg.try_add_value(value)?;
+        }
+
+        Ok(documents_schema(agg.finalize(), &samples, options))
     }
 
-    #[test]
-    fn array() {
-        let json = json(
-            r#"
-                [
-                    "mixed", null, true, 123, 123.23,
-                    ["nested", "arr"], ["arr2"], [123], [true, 27, [22.34]],
-                    {"k1": "v1", "k3": true}, {"k1": 23, "k3": false}, {"k2": "v2", "k3": true}
-                ]
-                "#,
-        );
+    /// `Schema::from_reader_with` using the default `SchemaInferenceOptions`.
+    pub fn from_reader(reader: impl io::Read) -> serde_json::Result<Schema> {
+        Schema::from_reader_with(reader, &SchemaInferenceOptions::default())
+    }
 
-        let schema = extract(json);
+    /// schema of the json read from `reader`, inferred while it is read
+    /// without building a `serde_json::Value` of it, so memory doesn't grow with the size of the json
+    /// (unless `detect_constants` or `detect_tuples` need all of it, or `resolve_refs` needs each object whole).
+    /// several documents one after the other (eg: json lines) are merged like `Schema::from_values`.
+    pub fn from_reader_with(
+        reader: impl io::Read,
+        options: &SchemaInferenceOptions,
+    ) -> serde_json::Result<Schema> {
+        let mut de = serde_json::Deserializer::from_reader(io::BufReader::new(reader));
+        let mut samples = vec![];
+        let mut agg = FieldTypeAggregator::with_options(options);
+        loop {
+            agg.add(next_document(
+                &mut de,
+                &mut samples,
+                options,
+                &Location::Root,
+            )?);
+            // only whitespace left
+            if de.end().is_ok() {
+                break;
+            }
+        }
 
-        assert_eq!(
-            schema,
-            Schema::Array(FieldType::Optional(Box::new(FieldType::Union(vec![
-                FieldType::String,
-                FieldType::Boolean,
-                FieldType::Integer,
-                FieldType::Float,
-                FieldType::Array(Box::new(FieldType::Union(vec![
-                    FieldType::String,
-                    FieldType::Integer,
-                    FieldType::Boolean,
-                    FieldType::Array(Box::new(FieldType::Float))
-                ]))),
-                FieldType::Object(vec![
-                    Field {
-                        name: "k1".into(),
-                        ty: FieldType::Optional(Box::new(FieldType::Union(vec![
-                            FieldType::String,
-                            FieldType::Integer
-                        ])))
-                    },
-                    Field {
-                        name: "k3".into(),
-                        ty: FieldType::Boolean
-                    },
-                    Field {
-                        name: "k2".into(),
-                        ty: FieldType::Optional(Box::new(FieldType::String))
-                    },
-                ])
-            ]))))
-        );
+        Ok(documents_schema(agg.finalize(), &samples, options))
     }
+}
 
-    #[test]
-    fn object() {
-        let json = json(
-            r#"
-                {
-                    "a": "amogus",
-                    "b": 123,
-                    "c": 45.67,
-                    "d": true,
-                    "e": null,
-                    "f": {"n": "nested"},
-                    "g": [1, 2],
-                    "h": [
-                        "mixed", null, true, 123, 123.23,
-                        ["nested", "arr"], ["arr2"], [123], [true, 27, [22.34]],
-                        {"k1": "v1", "k3": true}, {"k1": 23, "k3": false}, {"k2": "v2", "k3": true}
-                    ]
-                }
-                "#,
-        );
+impl Schema {
+    /// schema of `json` whose `$ref`s point into `definitions`. see `SchemaInferenceOptions::resolve_refs`
+    pub fn from_with_refs(
+        json: Value,
+        definitions: Value,
+        options: SchemaInferenceOptions,
+    ) -> Schema {
+        extract_with(
+            json,
+            &SchemaInferenceOptions {
+                resolve_refs: Some(definitions),
+                ..options
+            },
+        )
+    }
+}
 
-        let schema = extract(json);
+impl Schema {
+    /// `Schema::from_ndjson_with` using the default `SchemaInferenceOptions`.
+    pub fn from_ndjson(reader: impl io::Read) -> serde_json::Result<Schema> {
+        Schema::from_ndjson_with(reader, &SchemaInferenceOptions::default())
+    }
 
-        assert_eq!(
-            schema,
-            Schema::Object(vec![
-                Field {
-                    name: "a".into(),
-                    ty: FieldType::String
-                },
-                Field {
-                    name: "b".into(),
-                    ty: FieldType::Integer
-                },
-                Field {
-                    name: "c".into(),
-                    ty: FieldType::Float
-                },
-                Field {
-                    name: "d".into(),
-                    ty: FieldType::Boolean
-                },
-                Field {
+    /// schema of newline delimited json (json lines): a `Schema::Array` of the merged type of the documents.
+    /// blank lines are skipped and errors point at the line of the malformed document.
+    /// like `Schema::from_reader`, the documents are inferred while they are read.
+    /// every document is at depth 2 (an element of the root array) for `SchemaInferenceOptions::max_depth`.
+    pub fn from_ndjson_with(
+        reader: impl io::Read,
+        options: &SchemaInferenceOptions,
+    ) -> serde_json::Result<Schema> {
+        let mut de = serde_json::Deserializer::from_reader(io::BufReader::new(reader));
+        let mut elements = vec![];
+        let mut agg = FieldTypeAggregator::with_options(options);
+        // until only whitespace is left
+        let mut idx = 0;
+        while de.end().is_err() {
+            let location = Location::Index(&Location::Root, idx);
+            agg.add(next_document(&mut de, &mut elements, options, &location)?);
+            idx += 1;
+        }
+
+        let mut schema = Schema::Array(agg.finalize());
+        if options.keep_samples() {
+            refine(&mut schema, &[&Value::Array(elements)], options);
+        }
+        Ok(schema)
+    }
+}
+
+/// `ty` is the merged type of all the top level documents. see `Schema::from_values`
+fn documents_schema(ty: FieldType, samples: &[Value], options: &SchemaInferenceOptions) -> Schema {
+    let mut schema = match ty {
+        FieldType::Object(fields) => Schema::Object(fields),
+        FieldType::EmptyObject => Schema::Object(vec![]),
+        FieldType::Array(ty) => Schema::Array(*ty),
+        ty => Schema::Array(ty),
+    };
+
+    if options.keep_samples() {
+        refine(&mut schema, &samples.iter().collect::<Vec<_>>(), options);
+    }
+
+    schema
+}
+
+/// type of the next top level document of `de`, which is at `location` for `max_depth`.
+/// the document is kept in `samples` if it is needed after inference. see `refine`
+fn next_document<'de, R: serde_json::de::Read<'de>>(
+    de: &mut serde_json::Deserializer<R>,
+    samples: &mut Vec<Value>,
+    options: &SchemaInferenceOptions,
+    location: &Location,
+) -> serde_json::Result<FieldType> {
+    match options.keep_samples() {
+        true => DocumentSeed {
+            options,
+            samples,
+            location,
+        }
+        .deserialize(de),
+        false => Ok(FieldTypeSeed {
+            options,
+            path: &[],
+            depth: location.depth(),
+            location,
+        }
+        .deserialize(de)?
+        .0),
+    }
+}
+
+/// type of one top level document that is kept in `samples`.
+/// the elements of an array are read, kept and merged one at a time. anything else is read whole.
+struct DocumentSeed<'s, 'o, 'l> {
+    options: &'o SchemaInferenceOptions,
+    samples: &'s mut Vec<Value>,
+    location: &'l Location<'l>,
+}
+
+impl DocumentSeed<'_, '_, '_> {
+    fn value<E: de::Error>(self, value: Value) -> Result<FieldType, E> {
+        if let Some(max_depth) = self.options.max_depth {
+            check_depth(&value, max_depth, self.location.depth(), self.location)
+                .map_err(E::custom)?;
+        }
+        self.samples.push(value.clone());
+        Ok(field_type(value, self.options, &[]))
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for DocumentSeed<'_, '_, '_> {
+    type Value = FieldType;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<FieldType, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for DocumentSeed<'_, '_, '_> {
+    type Value = FieldType;
+
+    fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "a json value")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<FieldType, A::Error> {
+        let depth = self.location.depth();
+        let mut agg = FieldTypeAggregator::with_options(self.options);
+        let mut elements = vec![];
+        if let Some(max_depth) = self
+            .options
+            .max_depth
+            .filter(|&max_depth| depth > max_depth)
+        {
+            return Err(de::Error::custom(self.location.depth_error(max_depth, &[])));
+        }
+        while let Some(element) = seq.next_element::<Value>()? {
+            if let Some(max_depth) = self.options.max_depth {
+                let location = Location::Index(self.location, elements.len());
+                check_depth(&element, max_depth, depth + 1, &location)
+                    .map_err(de::Error::custom)?;
+            }
+            elements.push(element.clone());
+            agg.add(field_type(element, self.options, &[]));
+        }
+        self.samples.push(Value::Array(elements));
+        Ok(FieldType::Array(Box::new(agg.finalize())))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<FieldType, A::Error> {
+        let value = Value::deserialize(MapAccessDeserializer::new(map))?;
+        self.value(value)
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<FieldType, E> {
+        self.value(v.into())
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<FieldType, E> {
+        self.value(v.into())
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<FieldType, E> {
+        self.value(v.into())
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<FieldType, E> {
+        self.value(v.into())
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<FieldType, E> {
+        self.value(v.into())
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<FieldType, E> {
+        self.value(Value::Null)
+    }
+}
+
+/// `field_type` of a json value, inferred while it is read
+/// without building a `Value` of its elements and fields.
+/// primitive values are yielded too. see `object`
+#[derive(Clone, Copy)]
+struct FieldTypeSeed<'o, 'p> {
+    options: &'o SchemaInferenceOptions,
+    path: &'p [String],
+    /// `location.depth()`, kept so that it isn't counted again for every value.
+    depth: usize,
+    location: &'p Location<'p>,
+}
+
+impl FieldTypeSeed<'_, '_> {
+    fn primitive(self, value: Value) -> (FieldType, Option<Value>) {
+        (
+            field_type(value.clone(), self.options, self.path),
+            Some(value),
+        )
+    }
+
+    /// an error if this object or array is nested deeper than `max_depth`.
+    fn check_depth<E: de::Error>(&self) -> Result<(), E> {
+        match self.options.max_depth {
+            Some(max_depth) if self.depth > max_depth => {
+                Err(E::custom(self.location.depth_error(max_depth, &[])))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for FieldTypeSeed<'_, '_> {
+    type Value = (FieldType, Option<Value>);
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for FieldTypeSeed<'_, '_> {
+    type Value = (FieldType, Option<Value>);
+
+    fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "a json value")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        self.check_depth()?;
+        let mut agg = FieldTypeAggregator::with_options(self.options);
+        for idx in 0.. {
+            let location = Location::Index(self.location, idx);
+            let element = FieldTypeSeed {
+                options: self.options,
+                path: self.path,
+                depth: self.depth + 1,
+                location: &location,
+            };
+            match seq.next_element_seed(element)? {
+                Some((ty, _)) => agg.add(ty),
+                None => break,
+            }
+        }
+        Ok((FieldType::Array(Box::new(agg.finalize())), None))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        self.check_depth()?;
+        if self.options.resolve_refs.is_some() {
+            // a `{"$ref": ..}` is only told apart from other objects once it is read whole.
+            let value = Value::deserialize(MapAccessDeserializer::new(map))?;
+            if let Some(max_depth) = self.options.max_depth {
+                check_depth(&value, max_depth, self.depth, self.location)
+                    .map_err(de::Error::custom)?;
+            }
+            return Ok((field_type(value, self.options, self.path), None));
+        }
+
+        let mut entries = vec![];
+        // a repeated key replaces the earlier value, like in a `serde_json::Map`
+        let mut indices: HashMap<String, usize> = HashMap::new();
+        while let Some(key) = map.next_key::<String>()? {
+            let path = value_path(self.path, &key, self.options);
+            let location = Location::Key(self.location, &key);
+            let (ty, value) = map.next_value_seed(FieldTypeSeed {
+                options: self.options,
+                path: &path,
+                depth: self.depth + 1,
+                location: &location,
+            })?;
+            match indices.get(&key) {
+                Some(&idx) => entries[idx] = (key, ty, value),
+                None => {
+                    indices.insert(key.clone(), entries.len());
+                    entries.push((key, ty, value));
+                }
+            }
+        }
+        Ok((object_type(entries, self.options, self.path), None))
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E> {
+        Ok(self.primitive(v.into()))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(self.primitive(v.into()))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(self.primitive(v.into()))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(self.primitive(v.into()))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(self.primitive(v.into()))
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E> {
+        Ok(self.primitive(Value::Null))
+    }
+}
+
+/// the parts of the schema that are inferred from all the `values` of a field at once
+/// rather than merged one value at a time: tuples and constants.
+/// `values` are the top level documents.
+fn refine(schema: &mut Schema, values: &[&Value], options: &SchemaInferenceOptions) {
+    // the samples still have their references.
+    let resolved: Vec<Value>;
+    let values: Vec<&Value> = match &options.resolve_refs {
+        Some(document) => {
+            resolved = values
+                .iter()
+                .map(|value| resolve_refs((*value).clone(), document))
+                .collect();
+            resolved.iter().collect()
+        }
+        None => values.to_vec(),
+    };
+    let values = values.as_slice();
+
+    match schema {
+        Schema::Object(fields) => {
+            if options.detect_tuples {
+                tuple_fields(fields, values, options, &[]);
+            }
+            if options.detect_constants {
+                constant_fields(fields, values);
+            }
+        }
+        Schema::Array(ty) => {
+            let elements: Vec<&Value> = values
+                .iter()
+                .filter_map(|v| v.as_array())
+                .flatten()
+                .collect();
+            if options.detect_tuples {
+                tuples(ty, &elements, options, &[]);
+            }
+            if options.detect_constants {
+                constants(ty, &elements);
+            }
+        }
+    }
+}
+
+/// arrays can't be tuples with more elements than this.
+const MAX_TUPLE_LEN: usize = 8;
+
+/// replace the arrays nested in `ty` that look like tuples with `FieldType::Tuple`.
+/// `values` are all the json values that were merged into `ty`.
+fn tuples(
+    ty: &mut FieldType,
+    values: &[&Value],
+    options: &SchemaInferenceOptions,
+    path: &[String],
+) {
+    match ty {
+        FieldType::Object(fields) => tuple_fields(fields, values, options, path),
+        FieldType::Array(element_ty) => {
+            let arrays: Vec<&Vec<Value>> = values.iter().filter_map(|v| v.as_array()).collect();
+            match tuple(&arrays, options, path) {
+                Some(tuple) => *ty = tuple,
+                None => {
+                    let elements: Vec<&Value> = arrays.into_iter().flatten().collect();
+                    tuples(element_ty, &elements, options, path);
+                }
+            }
+        }
+        FieldType::Map(ty) => {
+            let entries: Vec<&Value> = values
+                .iter()
+                .filter_map(|v| v.as_object())
+                .flat_map(|obj| obj.values())
+                .collect();
+            tuples(ty, &entries, options, path);
+        }
+        FieldType::Optional(ty) => {
+            let present: Vec<&Value> = values.iter().copied().filter(|v| !v.is_null()).collect();
+            tuples(ty, &present, options, path);
+        }
+        _ => {}
+    }
+}
+
+fn tuple_fields(
+    fields: &mut [Field],
+    values: &[&Value],
+    options: &SchemaInferenceOptions,
+    path: &[String],
+) {
+    for field in fields {
+        let field_values: Vec<&Value> = values.iter().filter_map(|v| v.get(&field.name)).collect();
+        let mut field_path = path.to_vec();
+        field_path.push(field.name.clone());
+        tuples(&mut field.ty, &field_values, options, &field_path);
+    }
+}
+
+/// `FieldType::Tuple` of the `arrays` if there are at least two of them, they all have the same length
+/// and each position keeps a single type across all of them.
+/// a single array (eg: `["a", "b"]` or `[1, "x", 2]`) is as likely to be a list as a tuple.
+fn tuple(
+    arrays: &[&Vec<Value>],
+    options: &SchemaInferenceOptions,
+    path: &[String],
+) -> Option<FieldType> {
+    let len = arrays.first()?.len();
+    if arrays.len() < 2
+        || !(2..=MAX_TUPLE_LEN).contains(&len)
+        || arrays.iter().any(|arr| arr.len() != len)
+    {
+        return None;
+    }
+
+    let mut positions = vec![];
+    for idx in 0..len {
+        let values: Vec<&Value> = arrays.iter().map(|arr| &arr[idx]).collect();
+        let mut agg = FieldTypeAggregator::with_options(options);
+        for value in &values {
+            agg.add(value_type((*value).clone(), options, path));
+        }
+        let mut ty = agg.finalize();
+        // a position of mixed types isn't a tuple position.
+        if is_union(&ty) {
+            return None;
+        }
+        tuples(&mut ty, &values, options, path);
+        positions.push(ty);
+    }
+
+    Some(FieldType::Tuple(positions))
+}
+
+fn is_union(ty: &FieldType) -> bool {
+    match ty {
+        FieldType::Union(_) => true,
+        FieldType::Optional(ty) => is_union(ty),
+        _ => false,
+    }
+}
+
+/// mark the constant fields of the objects nested in `ty`.
+/// `values` are all the json values that were merged into `ty`.
+fn constants(ty: &mut FieldType, values: &[&Value]) {
+    match ty {
+        FieldType::Object(fields) => constant_fields(fields, values),
+        FieldType::Array(ty) => {
+            let elements: Vec<&Value> = values
+                .iter()
+                .filter_map(|v| v.as_array())
+                .flatten()
+                .collect();
+            constants(ty, &elements);
+        }
+        FieldType::Optional(ty) => {
+            let present: Vec<&Value> = values.iter().copied().filter(|v| !v.is_null()).collect();
+            constants(ty, &present);
+        }
+        FieldType::Tuple(tys) => {
+            for (idx, ty) in tys.iter_mut().enumerate() {
+                let position: Vec<&Value> = values
+                    .iter()
+                    .filter_map(|v| v.as_array()?.get(idx))
+                    .collect();
+                constants(ty, &position);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// primitive fields become `FieldType::Constant` if all of their (at least two) values are equal.
+fn constant_fields(fields: &mut [Field], values: &[&Value]) {
+    for field in fields {
+        let field_values: Vec<&Value> = values.iter().filter_map(|v| v.get(&field.name)).collect();
+        match (&field.ty, field_values.as_slice()) {
+            (
+                FieldType::String
+                | FieldType::Integer
+                | FieldType::BigInteger
+                | FieldType::Float
+                | FieldType::Boolean,
+                [first, rest @ ..],
+            ) if !rest.is_empty() && rest.iter().all(|v| v == first) => {
+                field.ty = FieldType::Constant(Box::new(field.ty.clone()), (*first).clone())
+            }
+            _ => constants(&mut field.ty, &field_values),
+        }
+    }
+}
+
+/// the fields of an object from its `entries`: (key, type of the value, the value if it is a primitive)
+fn object(
+    mut entries: Vec<(String, FieldType, Option<Value>)>,
+    options: &SchemaInferenceOptions,
+    path: &[String],
+) -> Vec<Field> {
+    let mut fields = vec![];
+
+    if options.field_order == FieldOrder::Alphabetical {
+        entries.sort_by(|(a, ..), (b, ..)| a.cmp(b));
+    }
+
+    // (field name, description)
+    let mut descriptions: Vec<(String, String)> = vec![];
+    if options.extract_descriptions {
+        let keys: Vec<String> = entries.iter().map(|(key, ..)| key.clone()).collect();
+        entries.retain(|(key, _, value)| {
+            let name = key
+                .strip_suffix("_description")
+                .or_else(|| key.strip_suffix(".$description"));
+            match (name, value) {
+                (Some(name), Some(Value::String(text))) if keys.iter().any(|key| key == name) => {
+                    descriptions.push((name.into(), text.clone()));
+                    false
+                }
+                _ => true,
+            }
+        });
+    }
+
+    for (key, ty, value) in entries {
+        let mut field_path = path.to_vec();
+        field_path.push(key.clone());
+        if options.excluded(&field_path) {
+            continue;
+        }
+
+        fields.push(Field {
+            ty,
+            description: descriptions
+                .iter()
+                .find(|(name, _)| *name == key)
+                .map(|(_, description)| description.clone()),
+            example: value.filter(|value| options.examples && !value.is_null()),
+            name: key,
+        });
+    }
+
+    fields
+}
+
+/// the entries of `obj` for `object`.
+fn entries(
+    obj: Map<String, Value>,
+    options: &SchemaInferenceOptions,
+    path: &[String],
+) -> Vec<(String, FieldType, Option<Value>)> {
+    obj.into_iter()
+        .map(|(key, value)| {
+            let primitive = match value {
+                Value::Array(_) | Value::Object(_) => None,
+                ref value => Some(value.clone()),
+            };
+            let ty = value_type(value, options, &value_path(path, &key, options));
+            (key, ty, primitive)
+        })
+        .collect()
+}
+
+/// path of the value of `key`. like array elements, the values of a map don't add a path segment.
+fn value_path(path: &[String], key: &str, options: &SchemaInferenceOptions) -> Vec<String> {
+    let mut path = path.to_vec();
+    if !(options.detect_maps && is_data(key)) {
+        path.push(key.into());
+    }
+    path
+}
+
+/// type of an object from its `entries`. see `object`
+fn object_type(
+    entries: Vec<(String, FieldType, Option<Value>)>,
+    options: &SchemaInferenceOptions,
+    path: &[String],
+) -> FieldType {
+    match entries.is_empty() {
+        true => FieldType::EmptyObject,
+        false if options.detect_maps && entries.iter().all(|(key, ..)| is_data(key)) => {
+            let mut agg = FieldTypeAggregator::with_options(options);
+            for (_, ty, _) in entries {
+                agg.add(ty);
+            }
+            FieldType::Map(Box::new(agg.finalize()))
+        }
+        false => FieldType::Object(object(entries, options, path)),
+    }
+}
+
+static DEFAULT_OPTIONS: SchemaInferenceOptions = SchemaInferenceOptions {
+    exclude: Vec::new(),
+    field_order: FieldOrder::Alphabetical,
+    keep_distinct_objects: false,
+    detect_constants: false,
+    extract_descriptions: false,
+    detect_maps: false,
+    examples: false,
+    detect_formats: false,
+    detect_base64: false,
+    detect_tuples: false,
+    resolve_refs: None,
+    max_depth: None,
+};
+
+/// `ty` that may be missing or `null`. never nests one `Optional` in another.
+fn optional(ty: FieldType) -> FieldType {
+    match ty {
+        FieldType::Optional(_) => ty,
+        ty => FieldType::Optional(Box::new(ty)),
+    }
+}
+
+/// `str` or a string with a format.
+fn is_string(ty: &FieldType) -> bool {
+    matches!(ty, FieldType::String | FieldType::Format(_))
+}
+
+/// a union never has both `T` and `T?` as members. the optional members are unwrapped
+/// and the whole union becomes optional instead. eg: `(int?|int|str)` -> `(int|str)?`
+fn hoist_optional(ty: FieldType) -> FieldType {
+    match ty {
+        FieldType::Union(types) if types.iter().any(|ty| matches!(ty, FieldType::Optional(_))) => {
+            let mut members = vec![];
+            for ty in types {
+                let tys = match ty {
+                    FieldType::Optional(ty) => match *ty {
+                        FieldType::Union(tys) => tys,
+                        ty => vec![ty],
+                    },
+                    ty => vec![ty],
+                };
+                for ty in tys {
+                    if !members.contains(&ty) {
+                        members.push(ty);
+                    }
+                }
+            }
+            optional(match members.len() {
+                1 => members.remove(0),
+                _ => FieldType::Union(members),
+            })
+        }
+        ty => ty,
+    }
+}
+
+/// merges the types of any number of json values into one, the same way the elements of an array are.
+/// values can be added one at a time, so the whole input never has to be in memory at once.
+/// eg: one value per line of a json lines file.
+pub struct FieldTypeAggregator<'o> {
+    ty: Option<FieldType>,
+    options: &'o SchemaInferenceOptions,
+}
+
+impl FieldTypeAggregator<'_> {
+    /// aggregator using the default `SchemaInferenceOptions`.
+    pub fn new() -> Self {
+        Self::with_options(&DEFAULT_OPTIONS)
+    }
+}
+
+impl Default for FieldTypeAggregator<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'o> FieldTypeAggregator<'o> {
+    /// `exclude` paths are relative to each added value.
+    pub fn with_options(options: &'o SchemaInferenceOptions) -> Self {
+        Self { ty: None, options }
+    }
+
+    pub fn add(&mut self, field_type: FieldType) {
+        match self.ty.take() {
+            None => self.ty = Some(field_type),
+            Some(ty) => self.ty = Some(self.merge(ty, field_type)),
+        };
+    }
+
+    /// panics if `value` is nested deeper than `SchemaInferenceOptions::max_depth`. see `try_add_value`
+    pub fn add_value(&mut self, value: Value) {
+        self.try_add_value(value)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// `add_value`, or an error if `value` is nested deeper than `SchemaInferenceOptions::max_depth`
+    pub fn try_add_value(&mut self, value: Value) -> Result<(), DepthError> {
+        if let Some(max_depth) = self.options.max_depth {
+            check_depth(&value, max_depth, 1, &Location::Root)?;
+        }
+        self.add(field_type(value, self.options, &[]));
+        Ok(())
+    }
+
+    /// the merged type. `Unknown` if nothing was added.
+    pub fn finalize(mut self) -> FieldType {
+        let ty = self.ty.take().unwrap_or(FieldType::Unknown);
+        match self.options.keep_distinct_objects {
+            true => self.merge_object_unions(ty),
+            false => ty,
+        }
+    }
+
+    /// with `keep_distinct_objects`, objects of different shapes are kept apart while merging,
+    /// so that it doesn't matter whether the other types come before or after them.
+    /// those that turned out not to be mixed with other types are merged into one object here.
+    fn merge_object_unions(&self, ty: FieldType) -> FieldType {
+        match ty {
+            FieldType::Union(tys) => {
+                let tys: Vec<FieldType> = tys
+                    .into_iter()
+                    .map(|ty| self.merge_object_unions(ty))
+                    .collect();
+                match tys.len() > 1 && tys.iter().all(|ty| matches!(ty, FieldType::Object(_))) {
+                    true => tys
+                        .into_iter()
+                        .reduce(|existing, new| match (existing, new) {
+                            (FieldType::Object(existing), FieldType::Object(new)) => {
+                                FieldType::Object(self.merge_sorted_obj_fields(existing, new))
+                            }
+                            _ => unreachable!("all the union members are objects"),
+                        })
+                        .unwrap_or(FieldType::Unknown),
+                    false => FieldType::Union(tys),
+                }
+            }
+            FieldType::Object(fields) => FieldType::Object(
+                fields
+                    .into_iter()
+                    .map(|mut field| {
+                        field.ty = self.merge_object_unions(field.ty);
+                        field
+                    })
+                    .collect(),
+            ),
+            FieldType::Tuple(tys) => FieldType::Tuple(
+                tys.into_iter()
+                    .map(|ty| self.merge_object_unions(ty))
+                    .collect(),
+            ),
+            FieldType::Array(ty) => FieldType::Array(Box::new(self.merge_object_unions(*ty))),
+            FieldType::Optional(ty) => FieldType::Optional(Box::new(self.merge_object_unions(*ty))),
+            FieldType::Map(ty) => FieldType::Map(Box::new(self.merge_object_unions(*ty))),
+            ty => ty,
+        }
+    }
+
+    fn merge(&self, existing: FieldType, new: FieldType) -> FieldType {
+        hoist_optional(self.merge_types(existing, new))
+    }
+
+    fn merge_types(&self, existing: FieldType, new: FieldType) -> FieldType {
+        match (existing, new) {
+            (
+                FieldType::Constant(existing_ty, existing_value),
+                FieldType::Constant(new_ty, new_value),
+            ) if existing_ty == new_ty && existing_value == new_value => {
+                FieldType::Constant(existing_ty, existing_value)
+            }
+            (FieldType::Constant(ty, _), other) | (other, FieldType::Constant(ty, _)) => {
+                self.merge(*ty, other)
+            }
+
+            // nothing is known about the referred type, except that it is the same for the same name.
+            (FieldType::Ref(name), FieldType::Ref(other)) if name == other => FieldType::Ref(name),
+            (FieldType::Ref(name), FieldType::Unknown)
+            | (FieldType::Unknown, FieldType::Ref(name)) => optional(FieldType::Ref(name)),
+            (FieldType::Ref(name), FieldType::Optional(ty))
+            | (FieldType::Optional(ty), FieldType::Ref(name)) => {
+                optional(self.merge(FieldType::Ref(name), *ty))
+            }
+            (FieldType::Ref(name), FieldType::Union(mut tys))
+            | (FieldType::Union(mut tys), FieldType::Ref(name)) => {
+                if !tys.contains(&FieldType::Ref(name.clone())) {
+                    tys.push(FieldType::Ref(name));
+                }
+                FieldType::Union(tys)
+            }
+            (FieldType::Ref(name), other) | (other, FieldType::Ref(name)) => {
+                FieldType::Union(vec![other, FieldType::Ref(name)])
+            }
+
+            (FieldType::Tuple(existing), FieldType::Tuple(new)) if existing.len() == new.len() => {
+                FieldType::Tuple(
+                    existing
+                        .into_iter()
+                        .zip(new)
+                        .map(|(existing, new)| self.merge(existing, new))
+                        .collect(),
+                )
+            }
+            (FieldType::Tuple(tys), FieldType::Unknown)
+            | (FieldType::Unknown, FieldType::Tuple(tys)) => optional(FieldType::Tuple(tys)),
+            (FieldType::Tuple(tys), FieldType::Optional(ty))
+            | (FieldType::Optional(ty), FieldType::Tuple(tys)) => {
+                optional(self.merge(FieldType::Tuple(tys), *ty))
+            }
+            // with a tuple of another length or anything else, it is just an array of its elements.
+            (FieldType::Tuple(tys), other) | (other, FieldType::Tuple(tys)) => {
+                let element = tys
+                    .into_iter()
+                    .reduce(|existing, new| self.merge(existing, new))
+                    .unwrap_or(FieldType::Unknown);
+                self.merge(FieldType::Array(Box::new(element)), other)
+            }
+
+            (FieldType::Format(format), FieldType::Format(other_format)) => {
+                match format == other_format {
+                    true => FieldType::Format(format),
+                    false => FieldType::String,
+                }
+            }
+            (FieldType::Format(_), FieldType::String)
+            | (FieldType::String, FieldType::Format(_)) => FieldType::String,
+            (FieldType::Format(format), FieldType::Unknown)
+            | (FieldType::Unknown, FieldType::Format(format)) => {
+                optional(FieldType::Format(format))
+            }
+            (FieldType::Format(format), FieldType::Optional(ty))
+            | (FieldType::Optional(ty), FieldType::Format(format)) => {
+                optional(self.merge(FieldType::Format(format), *ty))
+            }
+            // a union has at most one string member.
+            (FieldType::Format(format), FieldType::Union(mut union_types))
+            | (FieldType::Union(mut union_types), FieldType::Format(format)) => {
+                match union_types.iter().position(is_string) {
+                    Some(idx) => {
+                        let existing = union_types.remove(idx);
+                        union_types.insert(idx, self.merge(existing, FieldType::Format(format)));
+                    }
+                    None => union_types.push(FieldType::Format(format)),
+                }
+                FieldType::Union(union_types)
+            }
+            (FieldType::Format(format), other) | (other, FieldType::Format(format)) => {
+                FieldType::Union(vec![FieldType::Format(format), other])
+            }
+
+            (FieldType::EmptyObject, FieldType::EmptyObject) => FieldType::EmptyObject,
+            // an empty object is an object with all of its fields missing, or a map without entries.
+            (FieldType::EmptyObject, FieldType::Object(fields))
+            | (FieldType::Object(fields), FieldType::EmptyObject) => {
+                FieldType::Object(self.merge_obj_fields(vec![], fields))
+            }
+            (FieldType::EmptyObject, FieldType::Map(ty))
+            | (FieldType::Map(ty), FieldType::EmptyObject) => FieldType::Map(ty),
+            (FieldType::EmptyObject, FieldType::Unknown)
+            | (FieldType::Unknown, FieldType::EmptyObject) => optional(FieldType::EmptyObject),
+            (FieldType::EmptyObject, FieldType::Optional(ty))
+            | (FieldType::Optional(ty), FieldType::EmptyObject) => {
+                optional(self.merge(FieldType::EmptyObject, *ty))
+            }
+            (FieldType::EmptyObject, FieldType::Union(mut union_types))
+            | (FieldType::Union(mut union_types), FieldType::EmptyObject) => {
+                match union_types.iter().position(|ty| {
+                    matches!(
+                        ty,
+                        FieldType::Object(_) | FieldType::Map(_) | FieldType::EmptyObject
+                    )
+                }) {
+                    Some(idx) => {
+                        let existing = union_types.remove(idx);
+                        union_types.insert(idx, self.merge(existing, FieldType::EmptyObject));
+                    }
+                    None => union_types.push(FieldType::EmptyObject),
+                }
+                FieldType::Union(union_types)
+            }
+            (FieldType::EmptyObject, other) | (other, FieldType::EmptyObject) => {
+                FieldType::Union(vec![other, FieldType::EmptyObject])
+            }
+            (FieldType::Object(fields), FieldType::Union(mut union_types))
+            | (FieldType::Union(mut union_types), FieldType::Object(fields))
+                if union_types.contains(&FieldType::EmptyObject) =>
+            {
+                union_types.retain(|ty| *ty != FieldType::EmptyObject);
+                self.merge(
+                    FieldType::Union(union_types),
+                    FieldType::Object(self.merge_obj_fields(vec![], fields)),
+                )
+            }
+
+            (FieldType::Map(existing_ty), FieldType::Map(new_ty)) => {
+                FieldType::Map(Box::new(self.merge(*existing_ty, *new_ty)))
+            }
+            // the keys of the object are just more entries of the map.
+            (FieldType::Map(ty), FieldType::Object(fields))
+            | (FieldType::Object(fields), FieldType::Map(ty)) => FieldType::Map(Box::new(
+                fields
+                    .into_iter()
+                    .fold(*ty, |ty, field| self.merge(ty, field.ty)),
+            )),
+            (FieldType::Map(ty), FieldType::Unknown) | (FieldType::Unknown, FieldType::Map(ty)) => {
+                optional(FieldType::Map(ty))
+            }
+            (FieldType::Map(map_ty), FieldType::Optional(ty))
+            | (FieldType::Optional(ty), FieldType::Map(map_ty)) => {
+                optional(self.merge(FieldType::Map(map_ty), *ty))
+            }
+            (FieldType::Map(map_ty), FieldType::Union(mut union_types))
+            | (FieldType::Union(mut union_types), FieldType::Map(map_ty)) => {
+                match union_types.iter().position(|ty| {
+                    matches!(
+                        ty,
+                        FieldType::Map(_) | FieldType::Object(_) | FieldType::EmptyObject
+                    )
+                }) {
+                    Some(idx) => {
+                        let existing = union_types.remove(idx);
+                        union_types.insert(idx, self.merge(existing, FieldType::Map(map_ty)));
+                    }
+                    None => union_types.push(FieldType::Map(map_ty)),
+                }
+                FieldType::Union(union_types)
+            }
+            (FieldType::Map(ty), other) | (other, FieldType::Map(ty)) => {
+                FieldType::Union(vec![other, FieldType::Map(ty)])
+            }
+
+            (FieldType::String, FieldType::String) => FieldType::String,
+            (FieldType::Integer, FieldType::Integer) => FieldType::Integer,
+            (FieldType::BigInteger, FieldType::BigInteger) => FieldType::BigInteger,
+            (FieldType::Float, FieldType::Float) => FieldType::Float,
+            (FieldType::Boolean, FieldType::Boolean) => FieldType::Boolean,
+            (FieldType::Unknown, FieldType::Unknown) => FieldType::Unknown,
+
+            (FieldType::String, FieldType::Integer) | (FieldType::Integer, FieldType::String) => {
+                FieldType::Union(vec![FieldType::String, FieldType::Integer])
+            }
+            (FieldType::String, FieldType::Float) | (FieldType::Float, FieldType::String) => {
+                FieldType::Union(vec![FieldType::String, FieldType::Float])
+            }
+            (FieldType::String, FieldType::Boolean) | (FieldType::Boolean, FieldType::String) => {
+                FieldType::Union(vec![FieldType::String, FieldType::Boolean])
+            }
+            (FieldType::Integer, FieldType::Float) | (FieldType::Float, FieldType::Integer) => {
+                FieldType::Union(vec![FieldType::Integer, FieldType::Float])
+            }
+            (FieldType::Integer, FieldType::Boolean) | (FieldType::Boolean, FieldType::Integer) => {
+                FieldType::Union(vec![FieldType::Integer, FieldType::Boolean])
+            }
+            (FieldType::Integer, FieldType::BigInteger)
+            | (FieldType::BigInteger, FieldType::Integer) => FieldType::BigInteger,
+            (FieldType::String, FieldType::BigInteger)
+            | (FieldType::BigInteger, FieldType::String) => {
+                FieldType::Union(vec![FieldType::String, FieldType::BigInteger])
+            }
+            (FieldType::BigInteger, FieldType::Float)
+            | (FieldType::Float, FieldType::BigInteger) => {
+                FieldType::Union(vec![FieldType::BigInteger, FieldType::Float])
+            }
+            (FieldType::BigInteger, FieldType::Boolean)
+            | (FieldType::Boolean, FieldType::BigInteger) => {
+                FieldType::Union(vec![FieldType::BigInteger, FieldType::Boolean])
+            }
+            (FieldType::Float, FieldType::Boolean) | (FieldType::Boolean, FieldType::Float) => {
+                FieldType::Union(vec![FieldType::Float, FieldType::Boolean])
+            }
+
+            (FieldType::String, FieldType::Object(fields))
+            | (FieldType::Object(fields), FieldType::String) => {
+                FieldType::Union(vec![FieldType::String, FieldType::Object(fields)])
+            }
+            (FieldType::Integer, FieldType::Object(fields))
+            | (FieldType::Object(fields), FieldType::Integer) => {
+                FieldType::Union(vec![FieldType::Integer, FieldType::Object(fields)])
+            }
+            (FieldType::BigInteger, FieldType::Object(fields))
+            | (FieldType::Object(fields), FieldType::BigInteger) => {
+                FieldType::Union(vec![FieldType::BigInteger, FieldType::Object(fields)])
+            }
+            (FieldType::Float, FieldType::Object(fields))
+            | (FieldType::Object(fields), FieldType::Float) => {
+                FieldType::Union(vec![FieldType::Float, FieldType::Object(fields)])
+            }
+            (FieldType::Boolean, FieldType::Object(fields))
+            | (FieldType::Object(fields), FieldType::Boolean) => {
+                FieldType::Union(vec![FieldType::Boolean, FieldType::Object(fields)])
+            }
+
+            (FieldType::String, FieldType::Union(mut tys))
+            | (FieldType::Union(mut tys), FieldType::String) => {
+                match tys.iter().position(is_string) {
+                    Some(idx) => tys[idx] = FieldType::String,
+                    None => tys.push(FieldType::String),
+                }
+                FieldType::Union(tys)
+            }
+            (FieldType::Integer, FieldType::Union(mut tys))
+            | (FieldType::Union(mut tys), FieldType::Integer) => {
+                if !tys.contains(&FieldType::Integer) && !tys.contains(&FieldType::BigInteger) {
+                    tys.push(FieldType::Integer);
+                }
+                FieldType::Union(tys)
+            }
+            (FieldType::BigInteger, FieldType::Union(mut tys))
+            | (FieldType::Union(mut tys), FieldType::BigInteger) => {
+                tys.retain(|ty| *ty != FieldType::Integer);
+                if !tys.contains(&FieldType::BigInteger) {
+                    tys.push(FieldType::BigInteger);
+                }
+                FieldType::Union(tys)
+            }
+            (FieldType::Float, FieldType::Union(mut tys))
+            | (FieldType::Union(mut tys), FieldType::Float) => {
+                if !tys.contains(&FieldType::Float) {
+                    tys.push(FieldType::Float);
+                }
+                FieldType::Union(tys)
+            }
+            (FieldType::Boolean, FieldType::Union(mut tys))
+            | (FieldType::Union(mut tys), FieldType::Boolean) => {
+                if !tys.contains(&FieldType::Boolean) {
+                    tys.push(FieldType::Boolean);
+                }
+                FieldType::Union(tys)
+            }
+
+            (FieldType::String, FieldType::Array(ty))
+            | (FieldType::Array(ty), FieldType::String) => {
+                FieldType::Union(vec![FieldType::String, FieldType::Array(ty)])
+            }
+            (FieldType::Integer, FieldType::Array(ty))
+            | (FieldType::Array(ty), FieldType::Integer) => {
+                FieldType::Union(vec![FieldType::Integer, FieldType::Array(ty)])
+            }
+            (FieldType::BigInteger, FieldType::Array(ty))
+            | (FieldType::Array(ty), FieldType::BigInteger) => {
+                FieldType::Union(vec![FieldType::BigInteger, FieldType::Array(ty)])
+            }
+            (FieldType::Float, FieldType::Array(ty)) | (FieldType::Array(ty), FieldType::Float) => {
+                FieldType::Union(vec![FieldType::Float, FieldType::Array(ty)])
+            }
+            (FieldType::Boolean, FieldType::Array(ty))
+            | (FieldType::Array(ty), FieldType::Boolean) => {
+                FieldType::Union(vec![FieldType::Boolean, FieldType::Array(ty)])
+            }
+
+            (FieldType::Optional(ty), FieldType::Unknown)
+            | (FieldType::Unknown, FieldType::Optional(ty)) => FieldType::Optional(ty),
+            (ft, FieldType::Unknown) | (FieldType::Unknown, ft) => optional(ft),
+            (FieldType::String, FieldType::Optional(ty))
+            | (FieldType::Optional(ty), FieldType::String) => {
+                optional(self.merge(FieldType::String, *ty))
+            }
+            (FieldType::Integer, FieldType::Optional(ty))
+            | (FieldType::Optional(ty), FieldType::Integer) => {
+                optional(self.merge(FieldType::Integer, *ty))
+            }
+            (FieldType::BigInteger, FieldType::Optional(ty))
+            | (FieldType::Optional(ty), FieldType::BigInteger) => {
+                optional(self.merge(FieldType::BigInteger, *ty))
+            }
+            (FieldType::Float, FieldType::Optional(ty))
+            | (FieldType::Optional(ty), FieldType::Float) => {
+                optional(self.merge(FieldType::Float, *ty))
+            }
+            (FieldType::Boolean, FieldType::Optional(ty))
+            | (FieldType::Optional(ty), FieldType::Boolean) => {
+                optional(self.merge(FieldType::Boolean, *ty))
+            }
+            (FieldType::Object(fields), FieldType::Optional(ty))
+            | (FieldType::Optional(ty), FieldType::Object(fields)) => {
+                optional(self.merge(FieldType::Object(fields), *ty))
+            }
+            (FieldType::Union(union_types), FieldType::Optional(ty))
+            | (FieldType::Optional(ty), FieldType::Union(union_types)) => {
+                optional(self.merge(FieldType::Union(union_types), *ty))
+            }
+            (FieldType::Array(arr_ty), FieldType::Optional(op_ty))
+            | (FieldType::Optional(op_ty), FieldType::Array(arr_ty)) => {
+                optional(self.merge(FieldType::Array(arr_ty), *op_ty))
+            }
+
+            (FieldType::Object(existing_fields), FieldType::Object(new_fields))
+                if self.options.keep_distinct_objects
+                    && !similar_keys(&existing_fields, &new_fields) =>
+            {
+                // merged in `finalize` unless other types turn up. see `merge_object_unions`
+                FieldType::Union(vec![
+                    FieldType::Object(existing_fields),
+                    FieldType::Object(new_fields),
+                ])
+            }
+            (FieldType::Object(existing_fields), FieldType::Object(new_fields))
+                if self.options.keep_distinct_objects =>
+            {
+                FieldType::Object(self.merge_sorted_obj_fields(existing_fields, new_fields))
+            }
+            (FieldType::Object(existing_fields), FieldType::Object(new_fields)) => {
+                FieldType::Object(self.merge_obj_fields(existing_fields, new_fields))
+            }
+
+            (FieldType::Object(obj_fields), FieldType::Union(mut union_types))
+            | (FieldType::Union(mut union_types), FieldType::Object(obj_fields))
+                if self.options.keep_distinct_objects =>
+            {
+                // objects with (mostly) the same keys are the same kind of object, whatever the types of their values.
+                // eg: `{"a": 1}` and `{"a": "x", "b": 2}` become `{a:int|str,b:int?}`
+                // instead of two types differing in a couple of fields.
+                match union_types.iter_mut().find_map(|ty| match ty {
+                    FieldType::Object(existing) if similar_keys(existing, &obj_fields) => {
+                        Some(existing)
+                    }
+                    _ => None,
+                }) {
+                    Some(existing) => {
+                        *existing =
+                            self.merge_sorted_obj_fields(std::mem::take(existing), obj_fields)
+                    }
+                    None => union_types.push(FieldType::Object(obj_fields)),
+                }
+                FieldType::Union(union_types)
+            }
+            (FieldType::Object(obj_fields), FieldType::Union(mut union_types))
+            | (FieldType::Union(mut union_types), FieldType::Object(obj_fields)) => {
+                match union_types
+                    .iter_mut()
+                    .filter_map(|ty| match ty {
+                        FieldType::Object(existing_obj_fields) => Some(existing_obj_fields),
+                        _ => None,
+                    })
+                    .next()
+                {
+                    Some(existing_obj_fields) => match obj_fields == *existing_obj_fields {
+                        true => FieldType::Union(union_types),
+                        false => {
+                            let merged_obj_fields =
+                                self.merge_obj_fields(existing_obj_fields.clone(), obj_fields);
+                            *existing_obj_fields = merged_obj_fields;
+                            FieldType::Union(union_types)
+                        }
+                    },
+                    None => {
+                        union_types.push(FieldType::Object(obj_fields));
+                        FieldType::Union(union_types)
+                    }
+                }
+            }
+            (FieldType::Array(arr_type), FieldType::Union(mut union_types))
+            | (FieldType::Union(mut union_types), FieldType::Array(arr_type)) => match union_types
+                .iter_mut()
+                .filter_map(|ty| match ty {
+                    FieldType::Array(existing_arr_ty) => Some(existing_arr_ty),
+                    _ => None,
+                })
+                .next()
+            {
+                Some(existing_arr_type) => match *existing_arr_type == arr_type {
+                    true => FieldType::Union(union_types),
+                    false => {
+                        let merged_arr_type =
+                            self.merge(existing_arr_type.deref().deref().clone(), *arr_type);
+                        **existing_arr_type = merged_arr_type;
+                        FieldType::Union(union_types)
+                    }
+                },
+                None => {
+                    union_types.push(FieldType::Array(arr_type));
+                    FieldType::Union(union_types)
+                }
+            },
+
+            (FieldType::Object(obj_fields), FieldType::Array(arr_ty))
+            | (FieldType::Array(arr_ty), FieldType::Object(obj_fields)) => FieldType::Union(vec![
+                FieldType::Object(obj_fields),
+                FieldType::Array(arr_ty),
+            ]),
+
+            (FieldType::Union(existing_types), FieldType::Union(new_types))
+                if self.options.keep_distinct_objects
+                    && new_types
+                        .iter()
+                        .any(|ty| matches!(ty, FieldType::Object(_))) =>
+            {
+                let (objects, others): (Vec<FieldType>, Vec<FieldType>) = new_types
+                    .into_iter()
+                    .partition(|ty| matches!(ty, FieldType::Object(_)));
+                objects.into_iter().fold(
+                    self.merge(FieldType::Union(existing_types), FieldType::Union(others)),
+                    |merged, object| self.merge(merged, object),
+                )
+            }
+            (FieldType::Union(existing_types), FieldType::Union(new_types)) => {
+                let mut merged_types = existing_types;
+                for new_type in new_types {
+                    match merged_types.iter().position(is_string) {
+                        Some(idx) if is_string(&new_type) => {
+                            let existing = merged_types.remove(idx);
+                            merged_types.insert(idx, self.merge(existing, new_type));
+                        }
+                        _ if !merged_types.iter().any(|ty| ty.same_shape(&new_type)) => {
+                            merged_types.push(new_type)
+                        }
+                        _ => {}
+                    }
+                }
+                if merged_types.contains(&FieldType::BigInteger) {
+                    merged_types.retain(|ty| *ty != FieldType::Integer);
+                }
+                FieldType::Union(merged_types)
+            }
+
+            (FieldType::Array(existing_ele_type), FieldType::Array(new_ele_type)) => {
+                let merged_ele_type = self.merge(*existing_ele_type, *new_ele_type);
+                FieldType::Array(Box::new(merged_ele_type))
+            }
+
+            (FieldType::Optional(existing_ty), FieldType::Optional(new_ty)) => {
+                optional(self.merge(*existing_ty, *new_ty))
+            }
+        }
+    }
+
+    /// `merge_obj_fields`, sorted by name again unless `FieldOrder::Insertion`.
+    /// with `keep_distinct_objects`, so that the fields don't depend on which object came first.
+    fn merge_sorted_obj_fields(
+        &self,
+        existing_fields: Vec<Field>,
+        new_fields: Vec<Field>,
+    ) -> Vec<Field> {
+        let mut fields = self.merge_obj_fields(existing_fields, new_fields);
+        if self.options.field_order == FieldOrder::Alphabetical {
+            fields.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+        fields
+    }
+
+    fn merge_obj_fields(
+        &self,
+        mut existing_fields: Vec<Field>,
+        mut new_fields: Vec<Field>,
+    ) -> Vec<Field> {
+        existing_fields = existing_fields
+            .into_iter()
+            .map(|mut existing_field| {
+                match new_fields
+                    .iter()
+                    .find(|new_field| existing_field.name == new_field.name)
+                {
+                    Some(_) => existing_field,
+                    None => {
+                        existing_field.ty = optional(existing_field.ty);
+                        existing_field
+                    }
+                }
+            })
+            .collect();
+
+        new_fields = new_fields
+            .into_iter()
+            .map(|mut new_field| {
+                match existing_fields
+                    .iter()
+                    .find(|existing_field| existing_field.name == new_field.name)
+                {
+                    Some(_) => new_field,
+                    None => {
+                        new_field.ty = optional(new_field.ty);
+                        new_field
+                    }
+                }
+            })
+            .collect();
+
+        let mut merged_fields = existing_fields;
+        for new_field in new_fields {
+            match merged_fields.iter_mut().find(|f| f.name == new_field.name) {
+                Some(field) => {
+                    field.ty = self.merge(field.ty.clone(), new_field.ty);
+                    if field.description.is_none() {
+                        field.description = new_field.description;
+                    }
+                    if field.example.is_none() {
+                        field.example = new_field.example;
+                    }
+                }
+                None => merged_fields.push(new_field),
+            }
+        }
+        merged_fields
+    }
+}
+
+impl Extend<Value> for FieldTypeAggregator<'_> {
+    fn extend<T: IntoIterator<Item = Value>>(&mut self, values: T) {
+        for value in values {
+            self.add_value(value);
+        }
+    }
+}
+
+impl FromIterator<Value> for FieldTypeAggregator<'_> {
+    fn from_iter<T: IntoIterator<Item = Value>>(values: T) -> Self {
+        let mut agg = Self::new();
+        agg.extend(values);
+        agg
+    }
+}
+
+fn array(arr: Vec<Value>, options: &SchemaInferenceOptions, path: &[String]) -> FieldType {
+    let mut agg = FieldTypeAggregator::with_options(options);
+
+    for value in arr {
+        let field_type = value_type(value, options, path);
+        agg.add(field_type);
+    }
+
+    agg.finalize()
+}
+
+fn field_type(value: Value, options: &SchemaInferenceOptions, path: &[String]) -> FieldType {
+    match &options.resolve_refs {
+        Some(document) => value_type(resolve_refs(value, document), options, path),
+        None => value_type(value, options, path),
+    }
+}
+
+/// `field_type` of a value whose references are resolved already.
+fn value_type(value: Value, options: &SchemaInferenceOptions, path: &[String]) -> FieldType {
+    // the references that `resolve_refs` leaves point back to a definition the value is part of.
+    if options.resolve_refs.is_some() {
+        if let Some(pointer) = reference(&value) {
+            let name = pointer.rsplit('/').next().unwrap_or_default();
+            return FieldType::Ref(name.replace("~1", "/").replace("~0", "~"));
+        }
+    }
+
+    match value {
+        Value::Null => FieldType::Unknown,
+        Value::Bool(_) => FieldType::Boolean,
+        Value::Number(n) => match (n.is_f64(), n.is_i64()) {
+            (true, _) => FieldType::Float,
+            (false, true) => FieldType::Integer,
+            (false, false) => FieldType::BigInteger,
+        },
+        Value::String(s) => match string_format(&s, options) {
+            Some(format) => FieldType::Format(format),
+            None => FieldType::String,
+        },
+        Value::Array(arr) => FieldType::Array(Box::new(array(arr, options, path))),
+        Value::Object(obj) => object_type(entries(obj, options, path), options, path),
+    }
+}
+
+/// the json pointer of a `{"$ref": "#/json/pointer"}` into the same document.
+fn reference(value: &Value) -> Option<&str> {
+    value
+        .as_object()
+        .filter(|obj| obj.len() == 1)?
+        .get("$ref")?
+        .as_str()?
+        .strip_prefix('#')
+}
+
+/// `value` with every reference replaced by its definition. see `SchemaInferenceOptions::resolve_refs`
+fn resolve_refs(value: Value, document: &Value) -> Value {
+    RefResolver {
+        document,
+        resolving: vec![],
+        resolved: HashMap::new(),
+        outermost: None,
+    }
+    .resolve(value)
+}
+
+/// resolves each definition of the document once, however often it is referred to.
+struct RefResolver<'d> {
+    document: &'d Value,
+    /// pointers of the definitions being resolved, innermost last.
+    resolving: Vec<String>,
+    /// definitions with their references resolved, by pointer.
+    resolved: HashMap<String, Value>,
+    /// index in `resolving` of the outermost definition referred back to.
+    outermost: Option<usize>,
+}
+
+impl RefResolver<'_> {
+    fn resolve(&mut self, value: Value) -> Value {
+        if let Some(pointer) = reference(&value) {
+            let pointer = pointer.to_string();
+            return self.definition(pointer).unwrap_or(value);
+        }
+
+        match value {
+            Value::Array(arr) => {
+                Value::Array(arr.into_iter().map(|value| self.resolve(value)).collect())
+            }
+            Value::Object(obj) => Value::Object(
+                obj.into_iter()
+                    .map(|(key, value)| (key, self.resolve(value)))
+                    .collect(),
+            ),
+            value => value,
+        }
+    }
+
+    /// the resolved definition at `pointer`. `None` if it is being resolved, ie: the reference is recursive.
+    fn definition(&mut self, pointer: String) -> Option<Value> {
+        if let Some(idx) = self.resolving.iter().position(|p| *p == pointer) {
+            self.outermost = Some(self.outermost.map_or(idx, |outermost| outermost.min(idx)));
+            return None;
+        }
+        if let Some(definition) = self.resolved.get(&pointer) {
+            return Some(definition.clone());
+        }
+        let Some(definition) = self.document.pointer(&pointer).cloned() else {
+            return Some(Value::Null);
+        };
+
+        let depth = self.resolving.len();
+        let outer = self.outermost.take();
+        self.resolving.push(pointer);
+        let definition = self.resolve(definition);
+        let pointer = self.resolving.pop().unwrap_or_default();
+
+        // a definition that refers back to an enclosing one resolves differently elsewhere.
+        let inner = self.outermost.filter(|&idx| idx < depth);
+        if inner.is_none() {
+            self.resolved.insert(pointer, definition.clone());
+        }
+        self.outermost = match (outer, inner) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        Some(definition)
+    }
+}
+
+/// key that is data rather than a field name. eg: `2024-01-31`, `42` or a uuid
+fn is_data(key: &str) -> bool {
+    is_uuid(key) || key.starts_with(|c: char| c.is_ascii_digit())
+}
+
+fn is_uuid(text: &str) -> bool {
+    text.len() == 36
+        && text.char_indices().all(|(idx, c)| match idx {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+fn string_format(text: &str, options: &SchemaInferenceOptions) -> Option<StringFormat> {
+    match text.split_at_checked(10) {
+        _ if options.detect_base64 && is_base64(text) => Some(StringFormat::Base64),
+        _ if !options.detect_formats => None,
+        _ if is_uuid(text) => Some(StringFormat::Uuid),
+        Some((date, "")) if is_date(date) => Some(StringFormat::Date),
+        Some((date, time)) if is_date(date) && is_time(time) => Some(StringFormat::DateTime),
+        _ => None,
+    }
+}
+
+/// see `SchemaInferenceOptions::detect_base64`
+fn is_base64(text: &str) -> bool {
+    let data = text.trim_end_matches('=');
+    text.len() >= 16
+        && text.len().is_multiple_of(4)
+        && text.len() - data.len() <= 2
+        && data
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'+' || b == b'/')
+        && !data.bytes().all(|b| b.is_ascii_alphabetic())
+        && !data.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// `YYYY-MM-DD`
+fn is_date(text: &str) -> bool {
+    matches!(
+        text.as_bytes(),
+        [y1, y2, y3, y4, b'-', m1, m2, b'-', d1, d2]
+            if [y1, y2, y3, y4, m1, m2, d1, d2].iter().all(|b| b.is_ascii_digit())
+                && matches!((m1, m2), (b'0', b'1'..=b'9') | (b'1', b'0'..=b'2'))
+                && matches!((d1, d2), (b'0', b'1'..=b'9') | (b'1'..=b'2', _) | (b'3', b'0'..=b'1'))
+    )
+}
+
+/// `THH:MM:SS`, optional fraction of a second and `Z` or a `+HH:MM` offset.
+fn is_time(text: &str) -> bool {
+    let two_digits = |text: &str, max: u8| {
+        text.len() == 2
+            && text.bytes().all(|b| b.is_ascii_digit())
+            && text.parse::<u8>().is_ok_and(|n| n <= max)
+    };
+
+    let Some(text) = text.strip_prefix(['T', 't']) else {
+        return false;
+    };
+    let (Some(hh), Some(mm), Some(ss), Some(rest)) = (
+        text.get(0..2),
+        text.get(3..5),
+        text.get(6..8),
+        text.get(8..),
+    ) else {
+        return false;
+    };
+    if !(two_digits(hh, 23) && two_digits(mm, 59) && two_digits(ss, 60))
+        || &text[2..3] != ":"
+        || &text[5..6] != ":"
+    {
+        return false;
+    }
+    let offset = match rest.strip_prefix('.') {
+        Some(fraction) => {
+            let digits = fraction.bytes().take_while(u8::is_ascii_digit).count();
+            if digits == 0 {
+                return false;
+            }
+            &fraction[digits..]
+        }
+        None => rest,
+    };
+    match offset.as_bytes() {
+        [b'Z' | b'z'] => true,
+        [b'+' | b'-', _, _, b':', _, _] => {
+            two_digits(&offset[1..3], 23) && two_digits(&offset[4..6], 59)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn json(text: &str) -> Value {
+        serde_json::from_str(text).unwrap()
+    }
+
+    /// json whose schemas are printed (or serialized) and read back. see `parse_round_trip` and `serde_round_trip`
+    const ROUND_TRIP: &[&str] = &[
+        r#"{"a": [{"b": 1}], "c": [[1]], "a_description": "list of b"}"#,
+        r#"[{"a": 1}, {"a": 2, "b": true}]"#,
+        r#"[{"at": [1.5, 2.5]}, {"at": [3.5, 4.5]}, {"at": null}]"#,
+        r#"[{}, 1, {"a": 1}, null]"#,
+        r#"[{}, null]"#,
+        r#"{"x": {}}"#,
+        r#"{}"#,
+        r#"[1, "a", null]"#,
+        r#"[{"v": "1.0", "n": 1}, {"v": "1.0", "n": 2}]"#,
+        r#"[{"k": 1, "f": 1.5, "b": false, "big": 18446744073709551615}, {"k": 1, "f": 1.5, "b": false, "big": 18446744073709551615}]"#,
+        r#"[{"huge": 18446744073709551616, "tiny": -9223372036854775809}, {"huge": 18446744073709551616, "tiny": -9223372036854775809}]"#,
+        r#"{"prices": {"2024-01-30": {"open": 1.5}, "2024-01-31": {"open": 2}}}"#,
+        r#"{"at": "2024-01-31T09:30:00Z", "on": "2024-01-31", "id": "67e55044-10b1-426f-9247-bb680e5fe0c8", "png": "iVBORw0KGgoAAAANSUhEUg=="}"#,
+        r#"{"first name": "a", "": 1, "q\"uote": [[]], "é": {"$ref": null}}"#,
+        r#"[[1, "a"], [2, "b"]]"#,
+        r#"[[1, "a"], null, [[1], {"a": 1}], "x"]"#,
+    ];
+
+    #[test]
+    fn empty() {
+        assert_eq!(extract(json("{}")), Schema::Object(vec![]));
+        assert_eq!(extract(json("[]")), Schema::Array(FieldType::Unknown));
+    }
+
+    #[test]
+    fn from_values() {
+        let values = vec![
+            json(r#"{"id": 1, "name": "a"}"#),
+            json(r#"{"id": 2, "email": null}"#),
+            json(r#"{"id": 3, "email": "c@example.com"}"#),
+        ];
+        assert_eq!(
+            Schema::from_values(values).to_string(),
+            "{id:int,name:str?,email:str?}"
+        );
+
+        let values = vec![json(r#"[{"a": 1}]"#), json(r#"[{"a": 2, "b": true}]"#)];
+        assert_eq!(Schema::from_values(values).to_string(), "[{a:int,b:bool?}]");
+
+        let values = vec![
+            json(r#"{"version": "1", "n": 1}"#),
+            json(r#"{"version": "1", "n": 2}"#),
+        ];
+        let options = SchemaInferenceOptions {
+            detect_constants: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            Schema::from_values_with(values, &options).to_string(),
+            r#"{n:int,version:"1"}"#
+        );
+
+        assert_eq!(
+            Schema::from_values(vec![]),
+            Schema::Array(FieldType::Unknown)
+        );
+
+        // same fields as the elements of one array, but the root stays an object.
+        let docs = [
+            r#"{"a": 1, "b": {"c": 1}}"#,
+            r#"{"b": {"c": 2, "d": 1}}"#,
+            r#"{"a": 3}"#,
+        ];
+        let Schema::Array(FieldType::Object(fields)) =
+            extract(json(&format!("[{}]", docs.join(","))))
+        else {
+            unreachable!()
+        };
+        assert_eq!(Schema::from_values(docs.map(json)), Schema::Object(fields));
+    }
+
+    /// json array of `len` elements, generated while it is read.
+    struct GeneratedArray {
+        idx: usize,
+        len: usize,
+        buf: io::Cursor<Vec<u8>>,
+    }
+
+    impl GeneratedArray {
+        fn new(len: usize) -> Self {
+            Self {
+                idx: 0,
+                len,
+                buf: io::Cursor::new(b"[".to_vec()),
+            }
+        }
+    }
+
+    impl io::Read for GeneratedArray {
+        fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+            let n = io::Read::read(&mut self.buf, out)?;
+            if n > 0 || self.idx > self.len {
+                return Ok(n);
+            }
+
+            let idx = self.idx;
+            let element = match idx % 3 {
+                _ if idx == self.len => "]".to_string(),
+                0 => format!(r#"{{"id": {idx}, "tags": ["a"], "score": 1.5}}"#),
+                1 => format!(r#"{{"id": {idx}, "tags": [], "name": "x"}}"#),
+                _ => format!(r#"{{"id": {idx}, "score": null}}"#),
+            };
+            let sep = match idx == 0 || idx == self.len {
+                true => "",
+                false => ",",
+            };
+            self.buf = io::Cursor::new(format!("{sep}{element}").into_bytes());
+            self.idx += 1;
+            self.read(out)
+        }
+    }
+
+    #[test]
+    fn from_reader() {
+        let mut text = String::new();
+        io::Read::read_to_string(&mut GeneratedArray::new(3_000), &mut text).unwrap();
+        assert_eq!(
+            Schema::from_reader(text.as_bytes()).unwrap(),
+            extract(json(&text))
+        );
+
+        // the json is never in memory as a whole.
+        assert_eq!(
+            Schema::from_reader(GeneratedArray::new(1_000_000)).unwrap(),
+            extract(json(&text))
+        );
+
+        // json lines are merged like the elements of an array.
+        let lines = "{\"a\": 1}\n{\"a\": 2, \"b\": true}\n";
+        assert_eq!(
+            Schema::from_reader(lines.as_bytes()).unwrap().to_string(),
+            "{a:int,b:bool?}"
+        );
+
+        let options = SchemaInferenceOptions {
+            detect_constants: true,
+            ..Default::default()
+        };
+        let text = r#"[{"v": 1, "n": 1}, {"v": 1, "n": 2}]"#;
+        assert_eq!(
+            Schema::from_reader_with(text.as_bytes(), &options).unwrap(),
+            extract_with(json(text), &options)
+        );
+
+        // fields are inferred while they are read, the same as from a `Value`.
+        // with `FieldOrder::Insertion`, the keys of a `Value` are only in their order in the json
+        // with the `preserve_order` feature.
+        #[cfg(feature = "preserve_order")]
+        {
+            let options = SchemaInferenceOptions {
+                field_order: FieldOrder::Insertion,
+                extract_descriptions: true,
+                detect_maps: true,
+                examples: true,
+                detect_formats: true,
+                exclude: vec!["user.**.secret".into()],
+                ..Default::default()
+            };
+            let text = r#"[
+                {"user": {"id": 7, "id_description": "primary key", "login": {"secret": "x", "at": "2024-01-31"}},
+                 "scores": {"2024-01-31": 1, "2024-02-01": 2.5}, "b": null, "a": true, "a": "x"},
+                {"user": {"id": 8}, "scores": {}, "b": [1, {"c": "d"}]}
+            ]"#;
+            assert_eq!(
+                Schema::from_reader_with(text.as_bytes(), &options).unwrap(),
+                extract_with(json(text), &options)
+            );
+        }
+
+        assert!(Schema::from_reader("[1, 2".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn from_ndjson() {
+        let text = "{\"a\": 1}\n\n{\"a\": 2, \"b\": [1, 2]}\r\n  \n{\"b\": []}\n\n";
+        assert_eq!(
+            Schema::from_ndjson(text.as_bytes()).unwrap().to_string(),
+            "[{a:int?,b:[int?]?}]"
+        );
+        assert_eq!(
+            Schema::from_ndjson("".as_bytes()).unwrap(),
+            Schema::Array(FieldType::Unknown)
+        );
+
+        let err = Schema::from_ndjson("{\"a\": 1}\n\n{\"a\": }\n".as_bytes()).unwrap_err();
+        assert_eq!(err.line(), 3);
+    }
+
+    #[test]
+    fn canonical_string() {
+        let options = SchemaInferenceOptions {
+            field_order: FieldOrder::Insertion,
+            ..Default::default()
+        };
+        // streamed, so that the keys stay in their order without the `preserve_order` feature.
+        let schema = |text: &str| Schema::from_reader_with(text.as_bytes(), &options).unwrap();
+        let a = schema(r#"{"b": [1, "x", {"d": 1, "c": true}], "a": null}"#);
+        let b = schema(r#"{"a": null, "b": [{"c": false, "d": 2}, "y", 2]}"#);
+
+        assert_ne!(a.to_string(), b.to_string());
+        assert_eq!(a.to_canonical_string(), b.to_canonical_string());
+        assert_eq!(
+            a.to_canonical_string(),
+            "{a:any,b:[int|str|{c:bool,d:int}]}"
+        );
+    }
+
+    #[test]
+    #[allow(dead_code)]
+    fn json_schema_sample() {
+        use jsoncodegen_derive::JsonSchemaSample;
+
+        #[derive(JsonSchemaSample)]
+        #[serde(rename_all = "camelCase")]
+        struct User {
+            user_id: u64,
+            email: Option<String>,
+            #[serde(rename = "type")]
+            kind: Kind,
+            tags: Vec<String>,
+            location: (f64, f64),
+            #[serde(flatten)]
+            audit: Audit,
+            #[serde(skip)]
+            cache: Vec<u8>,
+        }
+        #[derive(JsonSchemaSample)]
+        enum Kind {
+            Admin,
+            Member,
+        }
+        #[derive(JsonSchemaSample)]
+        struct Audit {
+            created_at: String,
+        }
+        #[derive(JsonSchemaSample)]
+        #[serde(untagged)]
+        enum Id {
+            Number(i64),
+            Text(String),
+        }
+        #[derive(JsonSchemaSample)]
+        enum Shape {
+            Circle { radius: f64 },
+            Square(f64),
+        }
+
+        assert_eq!(
+            User::schema().to_canonical_string(),
+            "{created_at:str,email:str?,location:(float,float),tags:[str],type:str,userId:int}"
+        );
+        assert_eq!(
+            <std::collections::HashMap<String, Id>>::field_type().to_string(),
+            "{*:str|int}"
+        );
+        assert_eq!(
+            Shape::field_type().to_string(),
+            "{Circle:{radius:float}?,Square:float?}"
+        );
+
+        let inferred = extract_with(
+            json(
+                r#"[
+                    {"userId": 1, "email": null, "type": "Admin", "tags": ["x"], "location": [1.5, 2.5], "created_at": "today"},
+                    {"userId": 2, "email": "b@example.com", "type": "Member", "tags": ["y", "z"], "location": [3.5, 4.5], "created_at": "today"}
+                ]"#,
+            ),
+            &SchemaInferenceOptions {
+                detect_tuples: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            <Vec<User>>::schema().to_canonical_string(),
+            inferred.to_canonical_string()
+        );
+    }
+
+    #[test]
+    fn iter() {
+        let schema = extract(json(r#"{"a": [1, "x"], "b": {"c": true}, "d": null}"#));
+
+        assert_eq!(
+            schema.iter().collect::<Vec<_>>(),
+            vec![
+                &FieldType::Array(Box::new(FieldType::Union(vec![
+                    FieldType::String,
+                    FieldType::Integer
+                ]))),
+                &FieldType::Union(vec![FieldType::String, FieldType::Integer]),
+                &FieldType::String,
+                &FieldType::Integer,
+                &FieldType::Object(vec![Field {
+                    name: "c".into(),
+                    ty: FieldType::Boolean,
+                    description: None,
+                    example: None,
+                }]),
+                &FieldType::Boolean,
+                &FieldType::Unknown,
+            ]
+        );
+    }
+
+    #[test]
+    fn exclude() {
+        let json = json(
+            r#"
+                {
+                    "_links": {"self": "/"},
+                    "user": {"id": 1, "_links": {"self": "/user"}, "meta": {"internal_debug": true, "v": 2}},
+                    "items": [{"id": 1, "internal_debug": "x"}]
+                }
+                "#,
+        );
+        let options = SchemaInferenceOptions {
+            exclude: vec![
+                "user._links".into(),
+                "*.internal_debug".into(),
+                "**.meta.internal_debug".into(),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            extract_with(json, &options),
+            Schema::Object(vec![
+                Field {
+                    name: "_links".into(),
+                    ty: FieldType::Object(vec![Field {
+                        name: "self".into(),
+                        ty: FieldType::String,
+                        description: None,
+                        example: None,
+                    }]),
+                    description: None,
+                    example: None,
+                },
+                Field {
+                    name: "items".into(),
+                    ty: FieldType::Array(Box::new(FieldType::Object(vec![Field {
+                        name: "id".into(),
+                        ty: FieldType::Integer,
+                        description: None,
+                        example: None,
+                    }]))),
+                    description: None,
+                    example: None,
+                },
+                Field {
+                    name: "user".into(),
+                    ty: FieldType::Object(vec![
+                        Field {
+                            name: "id".into(),
+                            ty: FieldType::Integer,
+                            description: None,
+                            example: None,
+                        },
+                        Field {
+                            name: "meta".into(),
+                            ty: FieldType::Object(vec![Field {
+                                name: "v".into(),
+                                ty: FieldType::Integer,
+                                description: None,
+                                example: None,
+                            }]),
+                            description: None,
+                            example: None,
+                        },
+                    ]),
+                    description: None,
+                    example: None,
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn big_integer() {
+        assert_eq!(
+            extract(json("[1, 18446744073709551615, -2]")),
+            Schema::Array(FieldType::BigInteger)
+        );
+        assert_eq!(
+            extract(json(r#"[1, "a", 18446744073709551615]"#)),
+            Schema::Array(FieldType::Union(vec![
+                FieldType::String,
+                FieldType::BigInteger
+            ]))
+        );
+        assert_eq!(
+            extract(json(r#"[[1, "a"], [18446744073709551615, true]]"#)),
+            Schema::Array(FieldType::Array(Box::new(FieldType::Union(vec![
+                FieldType::String,
+                FieldType::BigInteger,
+                FieldType::Boolean
+            ]))))
+        );
+        // beyond u64 (or below i64), the number is already a float when it gets here.
+        // see `FieldType::BigInteger`
+        assert_eq!(
+            extract(json("[123456789012345678901234567890]")),
+            Schema::Array(FieldType::Float)
+        );
+        assert_eq!(
+            extract(json("[-9223372036854775809]")),
+            Schema::Array(FieldType::Float)
+        );
+        assert_eq!(
+            extract(json("[-9223372036854775808]")),
+            Schema::Array(FieldType::Integer)
+        );
+    }
+
+    #[test]
+    fn array() {
+        let json = json(
+            r#"
+                [
+                    "mixed", null, true, 123, 123.23,
+                    ["nested", "arr"], ["arr2"], [123], [true, 27, [22.34]],
+                    {"k1": "v1", "k3": true}, {"k1": 23, "k3": false}, {"k2": "v2", "k3": true}
+                ]
+                "#,
+        );
+
+        let schema = extract(json);
+
+        assert_eq!(
+            schema,
+            Schema::Array(FieldType::Optional(Box::new(FieldType::Union(vec![
+                FieldType::String,
+                FieldType::Boolean,
+                FieldType::Integer,
+                FieldType::Float,
+                FieldType::Array(Box::new(FieldType::Union(vec![
+                    FieldType::String,
+                    FieldType::Integer,
+                    FieldType::Boolean,
+                    FieldType::Array(Box::new(FieldType::Float))
+                ]))),
+                FieldType::Object(vec![
+                    Field {
+                        name: "k1".into(),
+                        ty: FieldType::Optional(Box::new(FieldType::Union(vec![
+                            FieldType::String,
+                            FieldType::Integer
+                        ]))),
+                        description: None,
+                        example: None,
+                    },
+                    Field {
+                        name: "k3".into(),
+                        ty: FieldType::Boolean,
+                        description: None,
+                        example: None,
+                    },
+                    Field {
+                        name: "k2".into(),
+                        ty: FieldType::Optional(Box::new(FieldType::String)),
+                        description: None,
+                        example: None,
+                    },
+                ])
+            ]))))
+        );
+    }
+
+    #[test]
+    fn object() {
+        let json = json(
+            r#"
+                {
+                    "a": "amogus",
+                    "b": 123,
+                    "c": 45.67,
+                    "d": true,
+                    "e": null,
+                    "f": {"n": "nested"},
+                    "g": [1, 2],
+                    "h": [
+                        "mixed", null, true, 123, 123.23,
+                        ["nested", "arr"], ["arr2"], [123], [true, 27, [22.34]],
+                        {"k1": "v1", "k3": true}, {"k1": 23, "k3": false}, {"k2": "v2", "k3": true}
+                    ]
+                }
+                "#,
+        );
+
+        let schema = extract(json);
+
+        assert_eq!(
+            schema,
+            Schema::Object(vec![
+                Field {
+                    name: "a".into(),
+                    ty: FieldType::String,
+                    description: None,
+                    example: None,
+                },
+                Field {
+                    name: "b".into(),
+                    ty: FieldType::Integer,
+                    description: None,
+                    example: None,
+                },
+                Field {
+                    name: "c".into(),
+                    ty: FieldType::Float,
+                    description: None,
+                    example: None,
+                },
+                Field {
+                    name: "d".into(),
+                    ty: FieldType::Boolean,
+                    description: None,
+                    example: None,
+                },
+                Field {
                     name: "e".into(),
-                    ty: FieldType::Unknown
+                    ty: FieldType::Unknown,
+                    description: None,
+                    example: None,
+                },
+                Field {
+                    name: "f".into(),
+                    ty: FieldType::Object(vec![Field {
+                        name: "n".into(),
+                        ty: FieldType::String,
+                        description: None,
+                        example: None,
+                    }]),
+                    description: None,
+                    example: None,
+                },
+                Field {
+                    name: "g".into(),
+                    ty: FieldType::Array(Box::new(FieldType::Integer)),
+                    description: None,
+                    example: None,
+                },
+                Field {
+                    name: "h".into(),
+                    ty: FieldType::Array(Box::new(FieldType::Optional(Box::new(
+                        FieldType::Union(vec![
+                            FieldType::String,
+                            FieldType::Boolean,
+                            FieldType::Integer,
+                            FieldType::Float,
+                            FieldType::Array(Box::new(FieldType::Union(vec![
+                                FieldType::String,
+                                FieldType::Integer,
+                                FieldType::Boolean,
+                                FieldType::Array(Box::new(FieldType::Float))
+                            ]))),
+                            FieldType::Object(vec![
+                                Field {
+                                    name: "k1".into(),
+                                    ty: FieldType::Optional(Box::new(FieldType::Union(vec![
+                                        FieldType::String,
+                                        FieldType::Integer
+                                    ]))),
+                                    description: None,
+                                    example: None,
+                                },
+                                Field {
+                                    name: "k3".into(),
+                                    ty: FieldType::Boolean,
+                                    description: None,
+                                    example: None,
+                                },
+                                Field {
+                                    name: "k2".into(),
+                                    ty: FieldType::Optional(Box::new(FieldType::String)),
+                                    description: None,
+                                    example: None,
+                                },
+                            ])
+                        ])
+                    )))),
+                    description: None,
+                    example: None,
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn field_order() {
+        let text = r#"[{"z": 1, "a": {"y": true, "b": null}}, {"m": "x", "z": 2}]"#;
+        let json = || json(text);
+        let field = |name: &str, ty: FieldType| Field {
+            name: name.into(),
+            ty,
+            description: None,
+            example: None,
+        };
+
+        assert_eq!(
+            extract(json()),
+            Schema::Array(FieldType::Object(vec![
+                field(
+                    "a",
+                    FieldType::Optional(Box::new(FieldType::Object(vec![
+                        field("b", FieldType::Unknown),
+                        field("y", FieldType::Boolean),
+                    ])))
+                ),
+                field("z", FieldType::Integer),
+                field("m", FieldType::Optional(Box::new(FieldType::String))),
+            ]))
+        );
+
+        let options = SchemaInferenceOptions {
+            field_order: FieldOrder::Insertion,
+            ..Default::default()
+        };
+        let insertion = Schema::Array(FieldType::Object(vec![
+            field("z", FieldType::Integer),
+            field(
+                "a",
+                FieldType::Optional(Box::new(FieldType::Object(vec![
+                    field("y", FieldType::Boolean),
+                    field("b", FieldType::Unknown),
+                ]))),
+            ),
+            field("m", FieldType::Optional(Box::new(FieldType::String))),
+        ]));
+        // the keys of a streamed document are always in their order in the json.
+        assert_eq!(
+            Schema::from_reader_with(text.as_bytes(), &options).unwrap(),
+            insertion
+        );
+        #[cfg(feature = "preserve_order")]
+        assert_eq!(extract_with(json(), &options), insertion);
+    }
+
+    #[test]
+    fn keep_distinct_objects() {
+        let json = || json(r#"[{"a": 1}, [1], {"b": 2}]"#);
+        let object = |name: &str, ty: FieldType| {
+            FieldType::Object(vec![Field {
+                name: name.into(),
+                ty,
+                description: None,
+                example: None,
+            }])
+        };
+
+        assert_eq!(
+            extract(json()),
+            Schema::Array(FieldType::Union(vec![
+                FieldType::Object(vec![
+                    Field {
+                        name: "a".into(),
+                        ty: FieldType::Optional(Box::new(FieldType::Integer)),
+                        description: None,
+                        example: None,
+                    },
+                    Field {
+                        name: "b".into(),
+                        ty: FieldType::Optional(Box::new(FieldType::Integer)),
+                        description: None,
+                        example: None,
+                    },
+                ]),
+                FieldType::Array(Box::new(FieldType::Integer)),
+            ]))
+        );
+
+        assert_eq!(
+            extract_with(
+                json(),
+                &SchemaInferenceOptions {
+                    keep_distinct_objects: true,
+                    ..Default::default()
+                }
+            ),
+            Schema::Array(FieldType::Union(vec![
+                object("a", FieldType::Integer),
+                FieldType::Array(Box::new(FieldType::Integer)),
+                object("b", FieldType::Integer),
+            ]))
+        );
+
+        // the same keys with values of other types used to be separate members: `{a:int}|{a:any}|{a:str}`
+        assert_eq!(
+            extract_with(
+                serde_json::json!([{"a": 1}, 5, {"a": null}, {"a": "x"}, {"b": 2}]),
+                &SchemaInferenceOptions {
+                    keep_distinct_objects: true,
+                    ..Default::default()
+                }
+            )
+            .to_string(),
+            "[int|{a:(str|int)?}|{b:int}]"
+        );
+        // and so did objects with a field more or less
+        assert_eq!(
+            extract_with(
+                serde_json::json!([
+                    {"name": "x", "children": []},
+                    5,
+                    {"name": "y", "children": [1], "meta": 1},
+                    {"other": 1},
+                ]),
+                &SchemaInferenceOptions {
+                    keep_distinct_objects: true,
+                    ..Default::default()
+                }
+            )
+            .to_string(),
+            "[int|{children:[int?],meta:int?,name:str}|{other:int}]"
+        );
+        // with the same fields whether or not anything else came between them
+        assert_eq!(
+            extract_with(
+                serde_json::json!([
+                    {"name": "x", "children": []},
+                    {"name": "y", "children": [1], "meta": 1},
+                ]),
+                &SchemaInferenceOptions {
+                    keep_distinct_objects: true,
+                    ..Default::default()
+                }
+            )
+            .to_string(),
+            "[{children:[int?],meta:int?,name:str}]"
+        );
+    }
+
+    #[test]
+    fn keep_distinct_objects_in_any_order() {
+        let options = SchemaInferenceOptions {
+            keep_distinct_objects: true,
+            ..Default::default()
+        };
+        let object = |name: &str| {
+            FieldType::Object(vec![Field {
+                name: name.into(),
+                ty: FieldType::Integer,
+                description: None,
+                example: None,
+            }])
+        };
+        let optional = |name: &str| Field {
+            name: name.into(),
+            ty: FieldType::Optional(Box::new(FieldType::Integer)),
+            description: None,
+            example: None,
+        };
+        // the order of the union members follows the elements, the members themselves don't.
+        let members = |json: Value| match extract_with(json, &options) {
+            Schema::Array(FieldType::Union(mut tys)) => {
+                tys.sort_by_key(|ty| format!("{ty:?}"));
+                tys
+            }
+            schema => panic!("expected an array of a union, not {schema:?}"),
+        };
+        let mut expected = vec![FieldType::String, object("a"), object("b")];
+        expected.sort_by_key(|ty| format!("{ty:?}"));
+
+        let elements = [
+            serde_json::json!({"a": 1}),
+            serde_json::json!({"b": 2}),
+            serde_json::json!("s"),
+        ];
+        for order in [
+            [0, 1, 2],
+            [0, 2, 1],
+            [1, 0, 2],
+            [1, 2, 0],
+            [2, 0, 1],
+            [2, 1, 0],
+        ] {
+            let json = Value::Array(order.iter().map(|&i| elements[i].clone()).collect());
+            assert_eq!(members(json), expected, "{order:?}");
+        }
+
+        // without other types, they are one object as usual.
+        assert_eq!(
+            extract_with(serde_json::json!([{"a": 1}, {"b": 2}]), &options),
+            Schema::Array(FieldType::Object(vec![optional("a"), optional("b")]))
+        );
+        // also when the objects come from different arrays.
+        assert_eq!(
+            extract_with(
+                serde_json::json!([{"v": [{"a": 1}, "s"]}, {"v": [{"b": 2}, "s"]}]),
+                &options
+            ),
+            Schema::Array(FieldType::Object(vec![Field {
+                name: "v".into(),
+                ty: FieldType::Array(Box::new(FieldType::Union(vec![
+                    FieldType::String,
+                    object("a"),
+                    object("b"),
+                ]))),
+                description: None,
+                example: None,
+            }]))
+        );
+    }
+
+    #[test]
+    fn aggregator() {
+        let values = vec![
+            json(r#"{"id": 1, "tags": ["a"]}"#),
+            json(r#"{"id": 2, "name": "x"}"#),
+            json("null"),
+        ];
+        let Schema::Array(expected) = extract(Value::Array(values.clone())) else {
+            unreachable!()
+        };
+
+        let mut agg = FieldTypeAggregator::new();
+        for value in values.clone() {
+            agg.add_value(value);
+        }
+        assert_eq!(agg.finalize(), expected);
+
+        let agg: FieldTypeAggregator = values.into_iter().collect();
+        assert_eq!(agg.finalize(), expected);
+
+        let options = SchemaInferenceOptions {
+            exclude: vec!["tags".into()],
+            ..Default::default()
+        };
+        let mut agg = FieldTypeAggregator::with_options(&options);
+        agg.extend([json(r#"{"id": 1, "tags": ["a"]}"#)]);
+        assert_eq!(
+            agg.finalize(),
+            FieldType::Object(vec![Field {
+                name: "id".into(),
+                ty: FieldType::Integer,
+                description: None,
+                example: None,
+            }])
+        );
+    }
+
+    #[test]
+    fn detect_constants() {
+        let options = SchemaInferenceOptions {
+            detect_constants: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            extract_with(
+                json(
+                    r#"[
+                        {"v": "1.0", "id": 1, "tag": {"kind": "a"}, "opt": 1},
+                        {"v": "1.0", "id": 2, "tag": {"kind": "a"}, "opt": null}
+                    ]"#
+                ),
+                &options
+            ),
+            Schema::Array(FieldType::Object(vec![
+                Field {
+                    name: "id".into(),
+                    ty: FieldType::Integer,
+                    description: None,
+                    example: None,
+                },
+                Field {
+                    name: "opt".into(),
+                    ty: FieldType::Optional(Box::new(FieldType::Integer)),
+                    description: None,
+                    example: None,
+                },
+                Field {
+                    name: "tag".into(),
+                    ty: FieldType::Object(vec![Field {
+                        name: "kind".into(),
+                        ty: FieldType::Constant(Box::new(FieldType::String), json(r#""a""#)),
+                        description: None,
+                        example: None,
+                    }]),
+                    description: None,
+                    example: None,
+                },
+                Field {
+                    name: "v".into(),
+                    ty: FieldType::Constant(Box::new(FieldType::String), json(r#""1.0""#)),
+                    description: None,
+                    example: None,
+                },
+            ]))
+        );
+
+        // a single sample says nothing about the other values a field can have.
+        assert_eq!(
+            extract_with(json(r#"{"v": "1.0", "a": [{"x": 1}]}"#), &options),
+            extract(json(r#"{"v": "1.0", "a": [{"x": 1}]}"#))
+        );
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            extract(json(
+                r#"{"id": 1, "first name": "a", "tags": [], "value": [1, "x", null], "nested": [{"a": 1.5}, {"b": true}]}"#
+            ))
+            .to_string(),
+            r#"{"first name":str,id:int,nested:[{a:float?,b:bool?}],tags:[any],value:[(str|int)?]}"#
+        );
+        assert_eq!(extract(json("[1, 2]")).to_string(), "[int]");
+    }
+
+    #[test]
+    fn extract_descriptions() {
+        let options = SchemaInferenceOptions {
+            extract_descriptions: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            extract_with(
+                json(
+                    r#"[
+                        {"id": 1, "id_description": "primary key", "orphan_description": "x"},
+                        {"id": 2, "name": "a", "name.$description": "display name"}
+                    ]"#
+                ),
+                &options
+            ),
+            Schema::Array(FieldType::Object(vec![
+                Field {
+                    name: "id".into(),
+                    ty: FieldType::Integer,
+                    description: Some("primary key".into()),
+                    example: None,
                 },
                 Field {
-                    name: "f".into(),
-                    ty: FieldType::Object(vec![Field {
-                        name: "n".into(),
-                        ty: FieldType::String
-                    }])
+                    name: "orphan_description".into(),
+                    ty: FieldType::Optional(Box::new(FieldType::String)),
+                    description: None,
+                    example: None,
                 },
                 Field {
-                    name: "g".into(),
-                    ty: FieldType::Array(Box::new(FieldType::Integer))
+                    name: "name".into(),
+                    ty: FieldType::Optional(Box::new(FieldType::String)),
+                    description: Some("display name".into()),
+                    example: None,
                 },
+            ]))
+        );
+    }
+
+    #[test]
+    fn detect_maps() {
+        let options = SchemaInferenceOptions {
+            detect_maps: true,
+            ..Default::default()
+        };
+        let prices = (1..=50)
+            .map(|day| {
+                format!(
+                    r#""2024-{:02}-{:02}": {{"open": 1.5, "close": {day}}}"#,
+                    1 + (day - 1) / 28,
+                    1 + (day - 1) % 28
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+
+        assert_eq!(
+            extract_with(
+                json(&format!(
+                    r#"{{"prices": {{{prices}}}, "user": {{"1": "a"}}}}"#
+                )),
+                &options
+            ),
+            Schema::Object(vec![
                 Field {
-                    name: "h".into(),
-                    ty: FieldType::Array(Box::new(FieldType::Optional(Box::new(
-                        FieldType::Union(vec![
-                            FieldType::String,
-                            FieldType::Boolean,
-                            FieldType::Integer,
-                            FieldType::Float,
-                            FieldType::Array(Box::new(FieldType::Union(vec![
-                                FieldType::String,
-                                FieldType::Integer,
-                                FieldType::Boolean,
-                                FieldType::Array(Box::new(FieldType::Float))
-                            ]))),
-                            FieldType::Object(vec![
-                                Field {
-                                    name: "k1".into(),
-                                    ty: FieldType::Optional(Box::new(FieldType::Union(vec![
-                                        FieldType::String,
-                                        FieldType::Integer
-                                    ])))
-                                },
-                                Field {
-                                    name: "k3".into(),
-                                    ty: FieldType::Boolean
-                                },
-                                Field {
-                                    name: "k2".into(),
-                                    ty: FieldType::Optional(Box::new(FieldType::String))
-                                },
-                            ])
-                        ])
-                    ))))
+                    name: "prices".into(),
+                    ty: FieldType::Map(Box::new(FieldType::Object(vec![
+                        Field {
+                            name: "close".into(),
+                            ty: FieldType::Integer,
+                            description: None,
+                            example: None,
+                        },
+                        Field {
+                            name: "open".into(),
+                            ty: FieldType::Float,
+                            description: None,
+                            example: None,
+                        },
+                    ]))),
+                    description: None,
+                    example: None,
+                },
+                Field {
+                    name: "user".into(),
+                    ty: FieldType::Map(Box::new(FieldType::String)),
+                    description: None,
+                    example: None,
+                },
+            ])
+        );
+
+        assert_eq!(
+            extract_with(json(r#"{"2024-01-01": 1}"#), &Default::default()),
+            Schema::Object(vec![Field {
+                name: "2024-01-01".into(),
+                ty: FieldType::Integer,
+                description: None,
+                example: None,
+            }])
+        );
+    }
+
+    #[test]
+    fn examples() {
+        let options = SchemaInferenceOptions {
+            examples: true,
+            keep_distinct_objects: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            extract_with(
+                json(
+                    r#"[
+                        {"id": null, "tags": ["a"], "v": {"n": 1}},
+                        {"id": 7, "tags": ["b"], "v": {"n": 2}},
+                        {"id": 8, "tags": ["c"], "v": [1]}
+                    ]"#
+                ),
+                &options
+            ),
+            Schema::Array(FieldType::Object(vec![
+                Field {
+                    name: "id".into(),
+                    ty: FieldType::Optional(Box::new(FieldType::Integer)),
+                    description: None,
+                    example: Some(7.into()),
+                },
+                Field {
+                    name: "tags".into(),
+                    ty: FieldType::Array(Box::new(FieldType::String)),
+                    description: None,
+                    example: None,
+                },
+                Field {
+                    name: "v".into(),
+                    // objects that only differ in their examples are the same union member.
+                    ty: FieldType::Union(vec![
+                        FieldType::Object(vec![Field {
+                            name: "n".into(),
+                            ty: FieldType::Integer,
+                            description: None,
+                            example: Some(1.into()),
+                        }]),
+                        FieldType::Array(Box::new(FieldType::Integer)),
+                    ]),
+                    description: None,
+                    example: None,
+                },
+            ]))
+        );
+    }
+
+    #[test]
+    fn optional_union_members() {
+        let aggregator = FieldTypeAggregator::new();
+        let opt = |ty| FieldType::Optional(Box::new(ty));
+
+        assert_eq!(
+            aggregator.merge(
+                FieldType::Union(vec![opt(FieldType::Integer), FieldType::Integer]),
+                FieldType::String
+            ),
+            opt(FieldType::Union(vec![
+                FieldType::Integer,
+                FieldType::String
+            ]))
+        );
+        assert_eq!(
+            aggregator.merge(
+                FieldType::Union(vec![FieldType::Integer, FieldType::String]),
+                FieldType::Union(vec![opt(FieldType::Integer), FieldType::Boolean])
+            ),
+            opt(FieldType::Union(vec![
+                FieldType::Integer,
+                FieldType::String,
+                FieldType::Boolean
+            ]))
+        );
+        assert_eq!(
+            aggregator.merge(
+                FieldType::Union(vec![opt(FieldType::String), FieldType::Integer]),
+                FieldType::Union(vec![FieldType::String])
+            ),
+            opt(FieldType::Union(vec![
+                FieldType::String,
+                FieldType::Integer
+            ]))
+        );
+        assert_eq!(
+            extract(json(r#"[{"a": 1}, {"b": 2}, null, "s", 3]"#)),
+            Schema::Array(opt(FieldType::Union(vec![
+                FieldType::String,
+                FieldType::Object(vec![
+                    Field {
+                        name: "a".into(),
+                        ty: opt(FieldType::Integer),
+                        description: None,
+                        example: None,
+                    },
+                    Field {
+                        name: "b".into(),
+                        ty: opt(FieldType::Integer),
+                        description: None,
+                        example: None,
+                    },
+                ]),
+                FieldType::Integer,
+            ])))
+        );
+    }
+
+    #[test]
+    fn max_depth() {
+        let options = SchemaInferenceOptions {
+            max_depth: Some(3),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            try_extract_with(json(r#"{"a": [{"b": 1}], "c": [[1]]}"#), &options)
+                .unwrap()
+                .to_string(),
+            "{a:[{b:int}],c:[[int]]}"
+        );
+        assert_eq!(
+            try_extract_with(json(r#"{"a": [1], "b": {"c": [{"d": 1}]}}"#), &options),
+            Err(ExtractError::TooDeep(DepthError {
+                max_depth: 3,
+                path: "b.c[0]".into(),
+            }))
+        );
+        assert_eq!(
+            try_extract_with(json("42"), &options),
+            Err(ExtractError::InvalidTopLevel)
+        );
+
+        // far deeper than serde_json would parse, or than inference could recurse.
+        let mut deep = Value::Null;
+        for _ in 0..5000 {
+            deep = Value::Array(vec![deep]);
+        }
+        let err = try_extract_with(
+            deep,
+            &SchemaInferenceOptions {
+                max_depth: Some(64),
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ExtractError::TooDeep(DepthError {
+                max_depth: 64,
+                path: "[0]".repeat(64),
+            })
+        );
+
+        let too_deep = || DepthError {
+            max_depth: 3,
+            path: "[1].b.c".into(),
+        };
+        let documents = || vec![json(r#"{"a": [1]}"#), json(r#"{"b": {"c": [{"d": 1}]}}"#)];
+        assert_eq!(
+            Schema::try_from_values_with(vec![Value::Array(documents())], &options),
+            Err(too_deep())
+        );
+        let mut agg = FieldTypeAggregator::with_options(&options);
+        assert_eq!(
+            agg.try_add_value(Value::Array(documents())),
+            Err(too_deep())
+        );
+
+        // streamed, with and without the samples kept for `detect_constants`
+        for detect_constants in [false, true] {
+            let options = SchemaInferenceOptions {
+                detect_constants,
+                ..options.clone()
+            };
+            let text = r#"[{"a": [1]}, {"b": {"c": [{"d": 1}]}}]"#;
+            let err = Schema::from_reader_with(text.as_bytes(), &options).unwrap_err();
+            assert!(
+                err.to_string().starts_with(&too_deep().to_string()),
+                "{err}"
+            );
+
+            let text = "{\"a\": [1]}\n{\"b\": {\"c\": [{\"d\": 1}]}}\n";
+            let err = Schema::from_ndjson_with(text.as_bytes(), &options).unwrap_err();
+            assert!(
+                err.to_string().starts_with(&too_deep().to_string()),
+                "{err}"
+            );
+
+            assert_eq!(
+                Schema::from_reader_with(r#"{"a": [{"b": 1}], "c": [[1]]}"#.as_bytes(), &options)
+                    .unwrap()
+                    .to_string(),
+                "{a:[{b:int}],c:[[int]]}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_round_trip() {
+        let options = SchemaInferenceOptions {
+            detect_constants: true,
+            detect_maps: true,
+            detect_formats: true,
+            detect_base64: true,
+            detect_tuples: true,
+            ..Default::default()
+        };
+        for input in ROUND_TRIP {
+            for options in [&SchemaInferenceOptions::default(), &options] {
+                let schema = extract_with(json(input), options);
+                assert_eq!(schema.to_string().parse(), Ok(schema.clone()), "{input}");
+            }
+        }
+
+        assert_eq!(
+            " { a : ( int | str ) ? , b : [ { * : any } ] } ".parse::<Schema>(),
+            "{a:(int|str)?,b:[{*:any}]}".parse()
+        );
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let options = SchemaInferenceOptions {
+            detect_constants: true,
+            detect_maps: true,
+            detect_formats: true,
+            detect_base64: true,
+            detect_tuples: true,
+            extract_descriptions: true,
+            examples: true,
+            ..Default::default()
+        };
+        for input in ROUND_TRIP {
+            let schema = extract_with(json(input), &options);
+            let text = serde_json::to_string(&schema).unwrap();
+            let parsed: Schema = serde_json::from_str(&text).unwrap();
+            assert_eq!(parsed.to_string(), schema.to_string(), "{input}");
+            assert_eq!(parsed, schema, "{input}");
+        }
+
+        assert_eq!(
+            serde_json::to_value(extract(json(r#"{"id": 1, "tags": [null, "a"]}"#))).unwrap(),
+            serde_json::json!({"object": [
+                {"name": "id", "ty": "integer"},
+                {"name": "tags", "ty": {"array": {"optional": "string"}}},
+            ]})
+        );
+    }
+
+    #[test]
+    fn parse_error() {
+        for (input, offset, message) in [
+            ("{a:int", 6, "expected `}`, found the end"),
+            ("{a:int}x", 7, "expected the end, found `x`"),
+            ("{a:integer}", 3, "unknown type `integer`"),
+            ("{a int}", 3, "expected `:`, found `i`"),
+            ("{:int}", 1, "expected a field name"),
+            ("{a:int,a:str}", 7, "duplicate field `a`"),
+            (r#"{a:int, "a":str}"#, 8, "duplicate field `a`"),
+            ("[str|]", 5, "expected a type, found `]`"),
+            (r#"{"a:int}"#, 1, "unterminated string"),
+            ("int", 0, "expected an object or an array"),
+            (
+                "{a:18446744073709551616}",
+                3,
+                "integer `18446744073709551616` is out of range",
+            ),
+            (
+                "[-9223372036854775809]",
+                1,
+                "integer `-9223372036854775809` is out of range",
+            ),
+        ] {
+            assert_eq!(
+                input.parse::<Schema>(),
+                Err(ParseError {
+                    offset,
+                    message: message.into(),
+                }),
+                "{input}"
+            );
+        }
+        assert_eq!(
+            "[{b:1.2.3}]".parse::<Schema>().unwrap_err().to_string(),
+            "invalid number `1.2.3` at byte 4"
+        );
+    }
+
+    #[test]
+    fn schema_diff() {
+        let schema = |json_str: &str| extract(json(json_str));
+        let old = schema(
+            r#"[{"id": 1, "name": "a", "tags": ["x"], "address": {"city": "b", "zip": 1}, "note": null}, {"id": 2, "name": "c", "tags": ["y"], "address": {"city": "d", "zip": 2}, "note": "n"}]"#,
+        );
+        let new = schema(
+            r#"[{"id": "1", "tags": ["x", null], "address": {"city": "b", "zip": 1, "street": "e"}, "email": "f"}, {"id": "2", "name": "c", "tags": [], "address": {"city": "d", "zip": "2"}, "email": "g"}]"#,
+        );
+
+        let changes = diff(&old, &new);
+        assert_eq!(
+            changes
+                .iter()
+                .map(|change| format!("{change} {}", change.is_breaking()))
+                .collect::<Vec<_>>(),
+            vec![
+                "added $[].address.street: str? false",
+                "changed $[].address.zip: int -> int|str true",
+                "added $[].email: str false",
+                "changed $[].id: int -> str true",
+                "$[].name became optional true",
+                "removed $[].note: str? false",
+                "$[].tags[] became optional true",
+            ]
+        );
+        assert_eq!(
+            changes[1],
+            SchemaChange::TypeChanged {
+                path: "$[].address.zip".into(),
+                old: FieldType::Integer,
+                new: FieldType::Union(vec![FieldType::Integer, FieldType::String]),
+            }
+        );
+
+        // the order of keys and of union members doesn't matter.
+        assert_eq!(
+            diff(
+                &schema(r#"{"b": [1, "x"], "a": 1}"#),
+                &schema(r#"{"a": 2, "b": ["x", 1]}"#)
+            ),
+            vec![]
+        );
+        assert_eq!(
+            diff(&schema(r#"{"a": 1}"#), &schema(r#"[{"a": 1}]"#)),
+            vec![SchemaChange::TypeChanged {
+                path: "$".into(),
+                old: FieldType::Object(vec![Field {
+                    name: "a".into(),
+                    ty: FieldType::Integer,
+                    description: None,
+                    example: None,
+                }]),
+                new: FieldType::Array(Box::new(FieldType::Object(vec![Field {
+                    name: "a".into(),
+                    ty: FieldType::Integer,
+                    description: None,
+                    example: None,
+                }]))),
+            }]
+        );
+        assert_eq!(
+            diff(
+                &schema(r#"{"a": {"first name": null}}"#),
+                &schema(r#"{"a": {}}"#)
+            ),
+            vec![SchemaChange::Removed {
+                path: r#"$.a["first name"]"#.into(),
+                ty: FieldType::Unknown,
+            }]
+        );
+    }
+
+    #[test]
+    fn resolve_refs() {
+        let definitions = serde_json::json!({"definitions": {
+            "Address": {"city": "Berlin", "zip": 10115},
+            "Node": {"name": "a", "next": {"$ref": "#/definitions/Node"}},
+            "A": {"b": {"$ref": "#/definitions/B"}},
+            "B": {"a": {"$ref": "#/definitions/A"}, "n": 1},
+        }});
+        let schema = |json: &str| {
+            Schema::from_with_refs(
+                serde_json::from_str(json).unwrap(),
+                definitions.clone(),
+                SchemaInferenceOptions::default(),
+            )
+            .to_string()
+        };
+
+        assert_eq!(
+            schema(
+                r##"{"home": {"$ref": "#/definitions/Address"}, "work": [{"$ref": "#/definitions/Address"}]}"##
+            ),
+            "{home:{city:str,zip:int},work:[{city:str,zip:int}]}"
+        );
+        // the recursive reference refers back to the definition.
+        assert_eq!(
+            schema(r##"{"head": {"$ref": "#/definitions/Node"}}"##),
+            "{head:{name:str,next:&Node}}"
+        );
+        assert_eq!(
+            schema(
+                r##"[{"head": {"$ref": "#/definitions/Node"}}, {"head": {"name": "b", "next": null}}]"##
+            ),
+            "[{head:{name:str,next:&Node?}}]"
+        );
+        // `B` refers back to `A` inside `A` only.
+        assert_eq!(
+            schema(r##"{"x": {"$ref": "#/definitions/A"}}"##),
+            "{x:{b:{a:&A,n:int}}}"
+        );
+        assert_eq!(
+            schema(r##"{"y": {"$ref": "#/definitions/B"}}"##),
+            "{y:{a:{b:&B},n:int}}"
+        );
+        assert_eq!(
+            schema(
+                r##"{"a": {"$ref": "#/definitions/Missing"}, "b": {"$ref": "https://example.com/b.json"}}"##
+            ),
+            r#"{a:any,b:{"$ref":str}}"#
+        );
+        // merged with the values written inline.
+        assert_eq!(
+            schema(r##"[{"$ref": "#/definitions/Address"}, {"city": "Paris"}]"##),
+            "[{city:str,zip:int?}]"
+        );
+        // without the option a reference is an ordinary object.
+        assert_eq!(
+            extract(serde_json::json!({"home": {"$ref": "#/definitions/Address"}})).to_string(),
+            r#"{home:{"$ref":str}}"#
+        );
+    }
+
+    #[test]
+    fn tuples() {
+        let schema = |json: &str| {
+            extract_with(
+                serde_json::from_str(json).unwrap(),
+                &SchemaInferenceOptions {
+                    detect_tuples: true,
+                    detect_constants: true,
+                    ..Default::default()
                 },
+            )
+            .to_string()
+        };
+
+        assert_eq!(
+            schema(r#"[{"at": [1.5, 2.5]}, {"at": [3.5, 4.5]}, {"at": null}]"#),
+            "[{at:(float,float)?}]"
+        );
+        assert_eq!(
+            schema(r#"[{"pair": ["id", 42]}, {"pair": ["n", 7]}]"#),
+            "[{pair:(str,int)}]"
+        );
+        // a single array doesn't show that the positions keep their types.
+        assert_eq!(
+            schema(r#"{"pair": ["id", 42], "list": ["a", "b"]}"#),
+            "{list:[str],pair:[str|int]}"
+        );
+        assert_eq!(schema(r#"{"id": [1, "x", 2]}"#), "{id:[str|int]}");
+        assert_eq!(schema("[1, 2.5]"), "[int|float]");
+        assert_eq!(schema(r#"{"v": [1, 2.5]}"#), "{v:[int|float]}");
+        assert_eq!(
+            schema(r#"{"v": [{"a": 1}, [1], {"b": 2}]}"#),
+            "{v:[{a:int?,b:int?}|[int]]}"
+        );
+        // positions of mixed types, different lengths or too many elements.
+        assert_eq!(schema(r#"[["a", 1], [1, "a"]]"#), "[[str|int]]");
+        assert_eq!(schema(r#"[[1, 2], [1, 2, 3]]"#), "[[int]]");
+        assert_eq!(
+            schema("[[1, 2, 3, 4, 5, 6, 7, 8, 9], [1, 2, 3, 4, 5, 6, 7, 8, 9]]"),
+            "[[int]]"
+        );
+        // a line of points: the outer array is a list, the points are tuples.
+        assert_eq!(
+            schema(r#"{"line": [[1.5, 2.5], [3.5, 4.5]]}"#),
+            "{line:[(float,float)]}"
+        );
+        assert_eq!(
+            schema(r#"[["v", {"version": 1, "n": 1}], ["v", {"version": 1, "n": 2}]]"#),
+            "[(str,{n:int,version:1})]"
+        );
+
+        let aggregator = FieldTypeAggregator::new();
+        let tuple = |tys| FieldType::Tuple(tys);
+        assert_eq!(
+            aggregator.merge(
+                tuple(vec![FieldType::Float, FieldType::Integer]),
+                tuple(vec![FieldType::Float, FieldType::Unknown])
+            ),
+            tuple(vec![
+                FieldType::Float,
+                FieldType::Optional(Box::new(FieldType::Integer))
             ])
         );
+        assert_eq!(
+            aggregator.merge(
+                tuple(vec![FieldType::String, FieldType::String]),
+                FieldType::Array(Box::new(FieldType::Integer))
+            ),
+            FieldType::Array(Box::new(FieldType::Union(vec![
+                FieldType::String,
+                FieldType::Integer
+            ])))
+        );
+    }
+
+    #[test]
+    fn string_formats() {
+        let schema = |json: &str| {
+            extract_with(
+                serde_json::from_str(json).unwrap(),
+                &SchemaInferenceOptions {
+                    detect_formats: true,
+                    ..Default::default()
+                },
+            )
+            .to_string()
+        };
+
+        assert_eq!(
+            schema(
+                r#"{"a": "2024-01-31T09:30:00Z", "b": "2024-01-31", "c": "67E55044-10b1-426f-9247-bb680e5fe0c8"}"#
+            ),
+            "{a:datetime,b:date,c:uuid}"
+        );
+        assert_eq!(
+            schema(r#"["2024-01-31T09:30:00.123+05:30", "1985-04-12t23:20:50.52z", null]"#),
+            "[datetime?]"
+        );
+        assert_eq!(
+            schema(
+                r#"["2024-13-01", "2024-01-32", "2024-01-31T24:00:00Z", "2024-01-31T09:30:00", "2024-01-31T09:30:00.Z", "2024-01-31 "]"#
+            ),
+            "[str]"
+        );
+        assert_eq!(schema(r#"["2024-01-31", "2024-01-31T09:30:00Z"]"#), "[str]");
+        assert_eq!(schema(r#"["2024-01-31", "x"]"#), "[str]");
+        assert_eq!(schema(r#"["2024-01-31", 1, "x"]"#), "[str|int]");
+        assert_eq!(schema(r#"[1, "x", "2024-01-31"]"#), "[str|int]");
+        assert_eq!(schema(r#"[[1, "2024-01-31"], ["x", 2]]"#), "[[str|int]]");
+        assert_eq!(
+            extract(json(r#"{"a": "2024-01-31"}"#)).to_string(),
+            "{a:str}"
+        );
+    }
+
+    #[test]
+    fn base64() {
+        let schema = |json: &str, detect_formats| {
+            extract_with(
+                serde_json::from_str(json).unwrap(),
+                &SchemaInferenceOptions {
+                    detect_base64: true,
+                    detect_formats,
+                    ..Default::default()
+                },
+            )
+            .to_string()
+        };
+
+        assert_eq!(
+            schema(
+                r#"[{"png": "iVBORw0KGgoAAAANSUhEUg==", "key": "c2VjcmV0LWtleS0xMjM0NTY3OA==", "at": "2024-01-31"}, {"png": "R0lGODlhAQABAIAAAAUEBA==", "key": null, "at": "2024-02-01"}]"#,
+                true
+            ),
+            "[{at:date,key:base64?,png:base64}]"
+        );
+        assert_eq!(
+            schema(
+                r#"{"at": "2024-01-31", "png": "iVBORw0KGgoAAAANSUhEUg=="}"#,
+                false
+            ),
+            "{at:str,png:base64}"
+        );
+        // too short, not a multiple of 4, bad padding or alphabet, only letters or only hex digits.
+        for text in [
+            "SGVsbG8=",
+            "iVBORw0KGgoAAAANSUhEUg=",
+            "iVBORw0KGgoAAAANSUhE===",
+            "iVBORw0KGgo-AAANSUhEUg==",
+            "PasswordResetTokenExpired",
+            "HelloWorldFooBar",
+            "d41d8cd98f00b204e9800998ecf8427e",
+            "1234567890123456",
+        ] {
+            assert_eq!(
+                schema(&format!("{{\"a\": \"{text}\"}}"), true),
+                "{a:str}",
+                "{text}"
+            );
+        }
+        // consistently across samples.
+        assert_eq!(
+            schema(r#"["iVBORw0KGgoAAAANSUhEUg==", "plain text"]"#, true),
+            "[str]"
+        );
+    }
+
+    #[test]
+    fn no_nested_optionals() {
+        let field = |ty| {
+            Schema::Array(FieldType::Object(vec![Field {
+                name: "a".into(),
+                ty,
+                description: None,
+                example: None,
+            }]))
+        };
+
+        assert_eq!(
+            extract(json(r#"[{"a": 1}, {}, {}]"#)),
+            field(FieldType::Optional(Box::new(FieldType::Integer)))
+        );
+        assert_eq!(
+            extract(json(r#"[{"a": null}, {}, {"a": [1]}]"#)),
+            field(FieldType::Optional(Box::new(FieldType::Array(Box::new(
+                FieldType::Integer
+            )))))
+        );
+        assert_eq!(
+            extract_with(
+                json(r#"[{"a": null}, {}, {"a": {"1": 1}}]"#),
+                &SchemaInferenceOptions {
+                    detect_maps: true,
+                    ..Default::default()
+                }
+            ),
+            field(FieldType::Optional(Box::new(FieldType::Map(Box::new(
+                FieldType::Integer
+            )))))
+        );
+    }
+
+    #[test]
+    fn empty_object() {
+        for (input, schema) in [
+            (r#"[{}, {}]"#, "[{}]"),
+            (r#"[{}, {"a": 1}]"#, "[{a:int?}]"),
+            (r#"[{"a": 1}, {}]"#, "[{a:int?}]"),
+            (r#"[{}, null]"#, "[{}?]"),
+            (r#"[{}, 1, {"a": 1}]"#, "[int|{a:int?}]"),
+            (r#"[1, {"a": 1}, {}]"#, "[int|{a:int?}]"),
+            (r#"{"x": {}}"#, "{x:{}}"),
+        ] {
+            assert_eq!(extract(json(input)).to_string(), schema, "{input}");
+        }
+
+        assert_eq!(
+            extract(json(r#"{"x": {}}"#)),
+            Schema::Object(vec![Field {
+                name: "x".into(),
+                ty: FieldType::EmptyObject,
+                description: None,
+                example: None,
+            }])
+        );
     }
 }
@@ -0,0 +1,167 @@
+use serde::Deserialize;
+use serde_json::{Map, Number, Value};
+
+/// the documents of a yaml stream converted to json.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Yaml {
+    /// one per `---` separated document. empty documents are left out.
+    pub documents: Vec<Value>,
+    /// yaml values that have no json equivalent and were converted with a loss.
+    /// eg: `spec.bucket: !Ref tag ignored`
+    pub warnings: Vec<String>,
+}
+
+/// parse a (multi document) yaml stream. eg: kubernetes manifests or ci configs.
+/// `<<` merge keys are applied. tags (eg: `!Ref` in cloudformation templates) are dropped,
+/// keys that aren't strings become their text and `.nan` / `.inf` become `null`, with a warning for each.
+pub fn from_str(text: &str) -> Result<Yaml, serde_yaml::Error> {
+    let mut yaml = Yaml {
+        documents: vec![],
+        warnings: vec![],
+    };
+
+    for document in serde_yaml::Deserializer::from_str(text) {
+        let mut value = serde_yaml::Value::deserialize(document)?;
+        value.apply_merge()?;
+        if value.is_null() {
+            continue;
+        }
+        yaml.documents
+            .push(json(value, &mut String::new(), &mut yaml.warnings));
+    }
+
+    Ok(yaml)
+}
+
+/// `path` of the value (eg: `spec.containers[0]`) for the warnings.
+fn json(value: serde_yaml::Value, path: &mut String, warnings: &mut Vec<String>) -> Value {
+    match value {
+        serde_yaml::Value::Null => Value::Null,
+        serde_yaml::Value::Bool(b) => Value::Bool(b),
+        serde_yaml::Value::Number(n) => match (n.as_i64(), n.as_u64(), n.as_f64()) {
+            (Some(n), _, _) => Value::from(n),
+            (_, Some(n), _) => Value::from(n),
+            (_, _, Some(n)) => match Number::from_f64(n) {
+                Some(n) => Value::Number(n),
+                None => {
+                    warnings.push(format!("{}: {n} is not a json number", location(path)));
+                    Value::Null
+                }
+            },
+            _ => Value::Null,
+        },
+        serde_yaml::Value::String(text) => Value::String(text),
+        serde_yaml::Value::Sequence(seq) => Value::Array(
+            seq.into_iter()
+                .enumerate()
+                .map(|(idx, value)| {
+                    let len = path.len();
+                    path.push_str(&format!("[{idx}]"));
+                    let value = json(value, path, warnings);
+                    path.truncate(len);
+                    value
+                })
+                .collect(),
+        ),
+        serde_yaml::Value::Mapping(mapping) => {
+            let mut obj = Map::new();
+            for (key, value) in mapping {
+                let key = match key {
+                    serde_yaml::Value::String(key) => key,
+                    serde_yaml::Value::Null => "null".into(),
+                    serde_yaml::Value::Bool(b) => b.to_string(),
+                    serde_yaml::Value::Number(n) => n.to_string(),
+                    key => {
+                        let text = serde_yaml::to_string(&key)
+                            .unwrap_or_default()
+                            .trim_end()
+                            .to_string();
+                        warnings.push(format!("{}: key {text:?} is not a string", location(path)));
+                        text
+                    }
+                };
+
+                let len = path.len();
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(&key);
+                let value = json(value, path, warnings);
+                path.truncate(len);
+
+                obj.insert(key, value);
+            }
+            Value::Object(obj)
+        }
+        serde_yaml::Value::Tagged(tagged) => {
+            warnings.push(format!("{}: {} tag ignored", location(path), tagged.tag));
+            json(tagged.value, path, warnings)
+        }
+    }
+}
+
+fn location(path: &str) -> &str {
+    match path.is_empty() {
+        true => "(root)",
+        false => path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn documents() {
+        let yaml = from_str(
+            "
+kind: Service
+---
+defaults: &defaults
+  image: nginx
+  ports: [80, 443]
+kind: Pod
+spec:
+  <<: *defaults
+  data: !!binary aGVsbG8=
+  bucket: !Ref MyBucket
+  ratio: .nan
+  1: one
+  [a, b]: pair
+---
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            yaml.documents,
+            vec![
+                serde_json::json!({"kind": "Service"}),
+                serde_json::json!({"defaults": {"image": "nginx", "ports": [80, 443]}, "kind": "Pod", "spec": {
+                    "data": "aGVsbG8=",
+                    "bucket": "MyBucket",
+                    "ratio": null,
+                    "1": "one",
+                    "- a\n- b": "pair",
+                    "image": "nginx",
+                    "ports": [80, 443],
+                }}),
+            ]
+        );
+        assert_eq!(
+            yaml.warnings,
+            vec![
+                "spec: key \"- a\\n- b\" is not a string",
+                "spec.bucket: !Ref tag ignored",
+                "spec.ratio: NaN is not a json number",
+            ]
+        );
+    }
+
+    #[test]
+    fn error() {
+        let err = from_str("a: 1\nb: [1, 2\n").unwrap_err();
+        assert_eq!(err.location().map(|location| location.line()), Some(3));
+    }
+}
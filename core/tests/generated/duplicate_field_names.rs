@@ -0,0 +1,26 @@
+use serde::{Serialize, Deserialize};
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Root {
+    #[serde(rename = "123foo")]
+    pub foo: isize,
+    #[serde(rename = "Self")]
+    pub self_: bool,
+    #[serde(rename = "fooあ")]
+    pub foo2: isize,
+    #[serde(rename = "self")]
+    pub self_2: isize,
+    #[serde(rename = "self_")]
+    pub self_3: String,
+    pub r#type: Type,
+    #[serde(rename = "userName")]
+    pub user_name: String,
+    #[serde(rename = "user_name")]
+    pub user_name2: String,
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Type {
+    #[serde(rename = "Type")]
+    pub r#type: isize,
+    #[serde(rename = "type")]
+    pub type2: isize,
+}
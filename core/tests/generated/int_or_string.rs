@@ -0,0 +1,10 @@
+use serde::{Serialize, Deserialize};
+pub type Root = Vec<Item>;
+#[serde_with::serde_as]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Item {
+    #[serde_as(as = "serde_with::PickFirst<(_, serde_with::DisplayFromStr)>")]
+    pub id: isize,
+    #[serde_as(as = "Vec<serde_with::PickFirst<(_, serde_with::DisplayFromStr)>>")]
+    pub ids: Vec<isize>,
+}
@@ -0,0 +1,28 @@
+use serde::{Serialize, Deserialize};
+#[derive(Serialize, Deserialize, Debug)]
+pub struct From {
+    pub a: isize,
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Result {
+    pub ok: bool,
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Root {
+    pub from: From,
+    pub result: Result,
+}
+impl ::core::convert::From<Root> for serde_json::Map<String, serde_json::Value> {
+    fn from(value: Root) -> Self {
+        match serde_json::to_value(value) {
+            ::core::result::Result::Ok(serde_json::Value::Object(map)) => map,
+            _ => unreachable!("a struct serializes to a json object"),
+        }
+    }
+}
+impl ::core::convert::TryFrom<serde_json::Map<String, serde_json::Value>> for Root {
+    type Error = serde_json::Error;
+    fn try_from(map: serde_json::Map<String, serde_json::Value>) -> ::core::result::Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::Object(map))
+    }
+}
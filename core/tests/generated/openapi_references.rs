@@ -0,0 +1,40 @@
+use serde::{Serialize, Deserialize};
+pub type Kind = String;
+pub type Tree = Vec<serde_json::Value>;
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Node {
+    pub next: Option<Box<Node>>,
+    pub shape: Shape,
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Owner {
+    pub id: isize,
+    pub pet: Option<Box<Pet>>,
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Pet {
+    pub friends: Option<Vec<Pet>>,
+    pub kind: Kind,
+    pub owner: Option<Owner>,
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Root {
+    #[serde(rename = "Kind")]
+    pub kind: Kind,
+    #[serde(rename = "Node")]
+    pub node: Node,
+    #[serde(rename = "Owner")]
+    pub owner: Owner,
+    #[serde(rename = "Pet")]
+    pub pet: Pet,
+    #[serde(rename = "Shape")]
+    pub shape: Shape,
+    #[serde(rename = "Tree")]
+    pub tree: Tree,
+}
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum Shape {
+    Pet(Pet),
+    Integer(isize),
+}
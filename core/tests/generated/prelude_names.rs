@@ -0,0 +1,49 @@
+use serde::{Serialize, Deserialize};
+#[derive(Serialize, Deserialize, Debug)]
+pub struct From {
+    pub a: isize,
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Ok {
+    pub b: bool,
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Root {
+    pub from: From,
+    pub ok: Ok,
+    pub result: Vec<Result>,
+}
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum Result {
+    String(String),
+    Integer(isize),
+}
+impl ::core::convert::From<String> for Result {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+impl ::core::convert::TryFrom<Result> for String {
+    type Error = Result;
+    fn try_from(value: Result) -> ::core::result::Result<Self, Self::Error> {
+        match value {
+            Result::String(inner) => ::core::result::Result::Ok(inner),
+            other => ::core::result::Result::Err(other),
+        }
+    }
+}
+impl ::core::convert::From<isize> for Result {
+    fn from(value: isize) -> Self {
+        Self::Integer(value)
+    }
+}
+impl ::core::convert::TryFrom<Result> for isize {
+    type Error = Result;
+    fn try_from(value: Result) -> ::core::result::Result<Self, Self::Error> {
+        match value {
+            Result::Integer(inner) => ::core::result::Result::Ok(inner),
+            other => ::core::result::Result::Err(other),
+        }
+    }
+}
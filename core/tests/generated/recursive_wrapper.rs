@@ -0,0 +1,29 @@
+use serde::{Serialize, Deserialize};
+use std::rc::Rc;
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Node {
+    pub next: Option<Rc<Node>>,
+    pub items: Vec<Node>,
+    pub v: node::V,
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Root {
+    #[serde(rename = "Node")]
+    pub node: Node,
+    #[serde(rename = "Shape")]
+    pub shape: Shape,
+}
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum Shape {
+    Node(Node),
+    String(String),
+}
+pub mod node {
+    use serde::{Serialize, Deserialize};
+    use std::rc::Rc;
+    #[derive(Serialize, Deserialize, Debug)]
+    pub struct V {
+        pub w: Rc<super::Node>,
+    }
+}
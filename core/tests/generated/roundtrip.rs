@@ -0,0 +1,42 @@
+use serde::{Serialize, Deserialize};
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Item {
+    pub n: isize,
+    pub price: Price,
+    pub tags: Vec<Option<String>>,
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Root {
+    pub id: String,
+    pub items: Vec<Item>,
+    pub values: Vec<Option<Value>>,
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ValueClass {
+    pub three: isize,
+}
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum Price {
+    Integer(isize),
+    Float(f64),
+}
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum Value {
+    String(String),
+    Integer(isize),
+    ValueClass(ValueClass),
+}
+#[cfg(test)]
+mod roundtrip_tests {
+    use super::*;
+    #[test]
+    fn roundtrip_root() {
+        let json = include_str!("input.json");
+        let parsed: Root = serde_json::from_str(json).unwrap();
+        let reserialized = serde_json::to_string(&parsed).unwrap();
+        let reparsed: Root = serde_json::from_str(&reserialized).unwrap();
+        assert_eq!(serde_json::to_string(&reparsed).unwrap(), reserialized);
+    }
+}
@@ -0,0 +1,128 @@
+//! the generated java is compiled and run with jackson when `JACKSON_CLASSPATH` has its jars
+//! (jackson-databind, jackson-core and jackson-annotations, separated like a java classpath).
+//! these are skipped without it.
+
+use jsoncodegen::{
+    codegen::{java, DirectorySink, JavaOptions},
+    schema::{extract_with, SchemaInferenceOptions},
+};
+use pretty_assertions::assert_eq;
+use serde_json::{json, Value};
+use std::{env, fs, path::PathBuf, process::Command};
+
+/// reads the json file of the first argument into the root class and prints it back.
+const MAIN: &str = r#"import com.fasterxml.jackson.databind.ObjectMapper;
+public class Main {
+    public static void main(String[] args) throws Exception {
+        ObjectMapper mapper = new ObjectMapper();
+        Root root = mapper.readValue(new java.io.File(args[0]), Root.class);
+        System.out.print(mapper.writeValueAsString(root));
+    }
+}
+"#;
+
+/// `input` read with the classes generated for `sample` and written back, or the error of the jvm.
+/// `None` without `JACKSON_CLASSPATH`.
+fn round_trip(
+    name: &str,
+    sample: &Value,
+    input: &Value,
+    options: &JavaOptions,
+) -> Option<Result<Value, String>> {
+    let Ok(classpath) = env::var("JACKSON_CLASSPATH") else {
+        eprintln!("skipped: JACKSON_CLASSPATH isn't set");
+        return None;
+    };
+    let dir = env::temp_dir().join(format!("jsoncodegen_java_{name}_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+
+    let schema = extract_with(
+        sample.clone(),
+        &SchemaInferenceOptions {
+            keep_distinct_objects: true,
+            ..Default::default()
+        },
+    );
+    java(schema, options, &mut DirectorySink::new(&dir)).unwrap();
+    fs::write(dir.join("Main.java"), MAIN).unwrap();
+    fs::write(dir.join("input.json"), input.to_string()).unwrap();
+
+    let sources: Vec<PathBuf> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "java"))
+        .collect();
+    let javac = Command::new("javac")
+        .arg("-cp")
+        .arg(&classpath)
+        .arg("-d")
+        .arg(&dir)
+        .args(&sources)
+        .output()
+        .unwrap();
+    assert!(
+        javac.status.success(),
+        "{}",
+        String::from_utf8_lossy(&javac.stderr)
+    );
+
+    let separator = match cfg!(windows) {
+        true => ";",
+        false => ":",
+    };
+    let run = Command::new("java")
+        .arg("-cp")
+        .arg(format!("{}{separator}{classpath}", dir.display()))
+        .arg("Main")
+        .arg(dir.join("input.json"))
+        .output()
+        .unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    Some(match run.status.success() {
+        true => Ok(serde_json::from_slice(&run.stdout).unwrap()),
+        false => Err(String::from_utf8_lossy(&run.stderr).into()),
+    })
+}
+
+/// a key that wasn't in the sample is ignored by default and rejected by `strict` classes.
+#[test]
+fn unknown_keys() {
+    let sample = json!({"id": 1, "name": "a", "tags": [{"k": "x"}]});
+    let input = json!({"id": 1, "name": "a", "tags": [{"k": "x", "extra": 2}], "extra": true});
+
+    let Some(lenient) = round_trip("lenient", &sample, &input, &JavaOptions::default()) else {
+        return;
+    };
+    assert_eq!(lenient, Ok(sample.clone()));
+
+    let strict = JavaOptions {
+        strict: true,
+        ..Default::default()
+    };
+    let err = round_trip("strict", &sample, &input, &strict)
+        .unwrap()
+        .unwrap_err();
+    assert!(err.contains("UnrecognizedPropertyException"), "{err}");
+    assert_eq!(
+        round_trip("strict_known", &sample, &sample, &strict),
+        Some(Ok(sample))
+    );
+}
+
+/// the objects of a union are told apart by their keys, even though each ignores unknown ones.
+#[test]
+fn union_of_objects_with_unknown_keys() {
+    let sample = json!({"v": [{"a": 1}, "s", {"b": 2}]});
+    let input = json!({"v": [{"a": 1, "extra": 0}, "s", {"b": 2, "extra": 0}]});
+
+    for options in [
+        JavaOptions::default(),
+        serde_json::from_value(json!({"unions": "sealed_interface"})).unwrap(),
+    ] {
+        let Some(round_trip) = round_trip("union", &sample, &input, &options) else {
+            return;
+        };
+        assert_eq!(round_trip, Ok(sample.clone()));
+    }
+}
@@ -0,0 +1,209 @@
+//! the generated code in `generated/` is compiled along with the tests,
+//! so these fail to build if the code generator starts emitting code that doesn't compile.
+
+use jsoncodegen::codegen::{rust, RecursiveWrapper, RustOptions};
+use pretty_assertions::assert_eq;
+
+#[allow(dead_code)]
+#[rustfmt::skip]
+#[path = "generated/prelude_names.rs"]
+mod prelude_names;
+
+#[allow(dead_code)]
+#[rustfmt::skip]
+#[path = "generated/map_conversion.rs"]
+mod map_conversion;
+
+#[allow(dead_code)]
+#[rustfmt::skip]
+#[path = "generated/openapi_references.rs"]
+mod openapi_references;
+
+#[allow(dead_code)]
+#[rustfmt::skip]
+#[path = "generated/int_or_string.rs"]
+mod int_or_string;
+
+#[allow(dead_code)]
+#[rustfmt::skip]
+#[path = "generated/duplicate_field_names.rs"]
+mod duplicate_field_names;
+
+#[allow(dead_code)]
+#[rustfmt::skip]
+#[path = "generated/recursive_wrapper.rs"]
+mod recursive_wrapper;
+
+/// its `roundtrip_tests` read `generated/input.json`, so the generated code also runs.
+#[allow(dead_code)]
+#[rustfmt::skip]
+#[path = "generated/roundtrip.rs"]
+mod roundtrip;
+
+fn codegen(json: &str, options: &RustOptions) -> String {
+    let schema = jsoncodegen::schema::extract(serde_json::from_str(json).unwrap());
+    let mut out = vec![];
+    rust(schema, options, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+/// types named after the keys `from`, `result` and `ok` shadow the prelude.
+#[test]
+fn prelude_names() {
+    let options = RustOptions {
+        variant_conversions: true,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        codegen(
+            r#"{"from": {"a": 1}, "result": [1, "x"], "ok": {"b": true}}"#,
+            &options
+        ),
+        include_str!("generated/prelude_names.rs")
+    );
+}
+
+#[test]
+fn map_conversion() {
+    let options = RustOptions {
+        generate_map_conversion: true,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        codegen(r#"{"from": {"a": 1}, "result": {"ok": true}}"#, &options),
+        include_str!("generated/map_conversion.rs")
+    );
+}
+
+/// keys that are the same rust identifier (eg: "self" and "self_") are different fields.
+#[test]
+fn duplicate_field_names() {
+    let json = r#"{"self": 1, "self_": "x", "Self": true, "userName": "a", "user_name": "b", "123foo": 1, "fooあ": 2, "type": {"Type": 1, "type": 2}}"#;
+    assert_eq!(
+        codegen(json, &RustOptions::default()),
+        include_str!("generated/duplicate_field_names.rs")
+    );
+
+    let root: duplicate_field_names::Root = serde_json::from_str(json).unwrap();
+    assert_eq!(
+        (root.self_, root.self_2, root.self_3.as_str()),
+        (true, 1, "x")
+    );
+    assert_eq!(
+        (root.user_name.as_str(), root.user_name2.as_str()),
+        ("a", "b")
+    );
+    assert_eq!((root.foo, root.foo2), (1, 2));
+    assert_eq!((root.r#type.r#type, root.r#type.type2), (1, 2));
+}
+
+/// integers that are sometimes strings parse either way.
+#[test]
+fn int_or_string() {
+    let options = RustOptions {
+        use_serde_with: true,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        codegen(
+            r#"[{"id": 42, "ids": [1, "2"]}, {"id": "42", "ids": [3]}]"#,
+            &options
+        ),
+        include_str!("generated/int_or_string.rs")
+    );
+
+    for json in [
+        r#"{"id": 42, "ids": [1, "2"]}"#,
+        r#"{"id": "42", "ids": ["1", 2]}"#,
+    ] {
+        let item: int_or_string::Item = serde_json::from_str(json).unwrap();
+        assert_eq!(item.id, 42);
+        assert_eq!(item.ids, [1, 2]);
+    }
+}
+
+/// `Rc` instead of `Box`, imported in every module that points to a recursive type.
+#[test]
+fn recursive_wrapper() {
+    let schema = "{Node:{next:&Node?,items:[&Node],v:{w:&Node}},Shape:&Node|str}"
+        .parse()
+        .unwrap();
+    let options = RustOptions {
+        recursive_wrapper: RecursiveWrapper::Rc,
+        nested_modules: true,
+        ..Default::default()
+    };
+    let mut out = vec![];
+    rust(schema, &options, &mut out).unwrap();
+
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        include_str!("generated/recursive_wrapper.rs")
+    );
+}
+
+/// unions are untagged enums, so the json of `input.json` parses back into them.
+#[test]
+fn roundtrip() {
+    let options = RustOptions {
+        generate_tests: true,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        codegen(include_str!("generated/input.json"), &options),
+        include_str!("generated/roundtrip.rs")
+    );
+}
+
+/// an openapi spec whose schemas refer to each other and to themselves.
+/// its keys are sorted, so that they are read in the same order without the `preserve_order` feature.
+#[test]
+fn openapi_references() {
+    let spec = serde_json::json!({
+        "openapi": "3.0.3",
+        "components": {
+            "schemas": {
+                "Kind": {"type": "string"},
+                "Node": {
+                    "type": "object",
+                    "required": ["shape"],
+                    "properties": {
+                        "next": {"$ref": "#/components/schemas/Node"},
+                        "shape": {"$ref": "#/components/schemas/Shape"}
+                    }
+                },
+                "Owner": {
+                    "type": "object",
+                    "required": ["id"],
+                    "properties": {
+                        "id": {"type": "integer"},
+                        "pet": {"$ref": "#/components/schemas/Pet"}
+                    }
+                },
+                "Pet": {
+                    "type": "object",
+                    "required": ["kind"],
+                    "properties": {
+                        "friends": {"type": "array", "items": {"$ref": "#/components/schemas/Pet"}},
+                        "kind": {"$ref": "#/components/schemas/Kind"},
+                        "owner": {"$ref": "#/components/schemas/Owner"}
+                    }
+                },
+                "Shape": {"oneOf": [{"$ref": "#/components/schemas/Pet"}, {"type": "integer"}]},
+                "Tree": {"type": "array", "items": {"$ref": "#/components/schemas/Tree"}}
+            }
+        }
+    });
+    let schema = jsoncodegen::openapi::from_openapi(&spec).unwrap();
+    let mut out = vec![];
+    rust(schema, &RustOptions::default(), &mut out).unwrap();
+
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        include_str!("generated/openapi_references.rs")
+    );
+}
@@ -0,0 +1,232 @@
+//! `#[derive(JsonSchemaSample)]`: the schema of the json a rust type (de)serializes as,
+//! in the same vocabulary as the inferred one. see `jsoncodegen::schema::JsonSchemaSample`
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    ext::IdentExt, parse_macro_input, punctuated::Punctuated, Attribute, Data, DeriveInput, Expr,
+    Fields, Lit, Meta, Token,
+};
+
+/// the serde attributes honored: `rename`, `rename_all`, `skip`, `flatten` and `untagged`.
+#[proc_macro_derive(JsonSchemaSample, attributes(serde))]
+pub fn derive_json_schema_sample(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match json_schema_sample(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn json_schema_sample(mut input: DeriveInput) -> syn::Result<TokenStream> {
+    let container = SerdeAttrs::parse(&input.attrs)?;
+
+    let body = match &input.data {
+        Data::Struct(data) => fields_type(&data.fields, &container)?,
+        Data::Enum(data) => {
+            let mut variants = vec![];
+            for variant in &data.variants {
+                let attrs = SerdeAttrs::parse(&variant.attrs)?;
+                if attrs.skip {
+                    continue;
+                }
+                let name = attrs.rename.unwrap_or_else(|| {
+                    rename(
+                        &variant.ident.unraw().to_string(),
+                        container.rename_all.as_deref(),
+                    )
+                });
+                let ty = fields_type(&variant.fields, &SerdeAttrs::default())?;
+                variants.push(match (container.untagged, &variant.fields) {
+                    (true, _) => ty,
+                    (false, Fields::Unit) => quote! { ::jsoncodegen::schema::FieldType::String },
+                    // externally tagged: `{"Variant": value}`
+                    (false, _) => quote! {
+                        ::jsoncodegen::schema::FieldType::Object(::std::vec![
+                            ::jsoncodegen::schema::Field {
+                                name: #name.into(),
+                                ty: #ty,
+                                description: None,
+                                example: None,
+                            }
+                        ])
+                    },
+                });
+            }
+            quote! {{
+                let mut agg = ::jsoncodegen::schema::FieldTypeAggregator::new();
+                #(agg.add(#variants);)*
+                agg.finalize()
+            }}
+        }
+        Data::Union(data) => {
+            return Err(syn::Error::new(
+                data.union_token.span,
+                "JsonSchemaSample can't be derived for unions",
+            ))
+        }
+    };
+
+    for param in input.generics.type_params_mut() {
+        param
+            .bounds
+            .push(syn::parse_quote!(::jsoncodegen::schema::JsonSchemaSample));
+    }
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::jsoncodegen::schema::JsonSchemaSample for #name #ty_generics #where_clause {
+            fn field_type() -> ::jsoncodegen::schema::FieldType {
+                #body
+            }
+        }
+    })
+}
+
+/// type of a struct or of the contents of an enum variant.
+fn fields_type(fields: &Fields, container: &SerdeAttrs) -> syn::Result<TokenStream> {
+    Ok(match fields {
+        Fields::Named(named) => {
+            let mut pushes = vec![];
+            for field in &named.named {
+                let attrs = SerdeAttrs::parse(&field.attrs)?;
+                if attrs.skip {
+                    continue;
+                }
+                let ty = &field.ty;
+                pushes.push(match attrs.flatten {
+                    true => quote! {
+                        if let ::jsoncodegen::schema::FieldType::Object(flattened) =
+                            <#ty as ::jsoncodegen::schema::JsonSchemaSample>::field_type()
+                        {
+                            fields.extend(flattened);
+                        }
+                    },
+                    false => {
+                        let ident = field.ident.as_ref().expect("named field").unraw();
+                        let name = attrs.rename.unwrap_or_else(|| {
+                            rename(&ident.to_string(), container.rename_all.as_deref())
+                        });
+                        quote! {
+                            fields.push(::jsoncodegen::schema::Field {
+                                name: #name.into(),
+                                ty: <#ty as ::jsoncodegen::schema::JsonSchemaSample>::field_type(),
+                                description: None,
+                                example: None,
+                            });
+                        }
+                    }
+                });
+            }
+            quote! {{
+                let mut fields: ::std::vec::Vec<::jsoncodegen::schema::Field> = ::std::vec::Vec::new();
+                #(#pushes)*
+                match fields.is_empty() {
+                    true => ::jsoncodegen::schema::FieldType::EmptyObject,
+                    false => ::jsoncodegen::schema::FieldType::Object(fields),
+                }
+            }}
+        }
+        // newtype
+        Fields::Unnamed(unnamed) if unnamed.unnamed.len() == 1 => {
+            let ty = &unnamed.unnamed[0].ty;
+            quote! { <#ty as ::jsoncodegen::schema::JsonSchemaSample>::field_type() }
+        }
+        Fields::Unnamed(unnamed) => {
+            let tys = unnamed.unnamed.iter().map(|field| &field.ty);
+            quote! {
+                ::jsoncodegen::schema::FieldType::Tuple(::std::vec![
+                    #(<#tys as ::jsoncodegen::schema::JsonSchemaSample>::field_type()),*
+                ])
+            }
+        }
+        Fields::Unit => quote! { ::jsoncodegen::schema::FieldType::Unknown },
+    })
+}
+
+#[derive(Default)]
+struct SerdeAttrs {
+    rename: Option<String>,
+    rename_all: Option<String>,
+    skip: bool,
+    flatten: bool,
+    untagged: bool,
+}
+
+impl SerdeAttrs {
+    /// the `#[serde(..)]` attributes that change the shape of the json. the others are ignored.
+    fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut serde = SerdeAttrs::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+            let metas = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?;
+            for meta in metas {
+                match &meta {
+                    Meta::Path(path) if path.is_ident("skip") => serde.skip = true,
+                    Meta::Path(path) if path.is_ident("flatten") => serde.flatten = true,
+                    Meta::Path(path) if path.is_ident("untagged") => serde.untagged = true,
+                    Meta::NameValue(meta) if meta.path.is_ident("rename") => {
+                        serde.rename = Some(string(&meta.value)?)
+                    }
+                    Meta::NameValue(meta) if meta.path.is_ident("rename_all") => {
+                        serde.rename_all = Some(string(&meta.value)?)
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(serde)
+    }
+}
+
+fn string(expr: &Expr) -> syn::Result<String> {
+    match expr {
+        Expr::Lit(lit) => match &lit.lit {
+            Lit::Str(text) => Ok(text.value()),
+            _ => Err(syn::Error::new_spanned(expr, "expected a string")),
+        },
+        _ => Err(syn::Error::new_spanned(expr, "expected a string")),
+    }
+}
+
+/// `name` (a snake_case field or PascalCase variant) with a serde `rename_all` rule applied.
+fn rename(name: &str, rule: Option<&str>) -> String {
+    let mut words: Vec<String> = vec![];
+    for part in name.split('_').filter(|part| !part.is_empty()) {
+        let mut word = String::new();
+        for c in part.chars() {
+            if c.is_uppercase() && !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            word.extend(c.to_lowercase());
+        }
+        words.push(word);
+    }
+
+    let capitalize = |word: &String| {
+        let mut chars = word.chars();
+        chars
+            .next()
+            .map(|c| c.to_uppercase().chain(chars).collect())
+            .unwrap_or_default()
+    };
+
+    match rule {
+        Some("lowercase") => name.to_lowercase(),
+        Some("UPPERCASE") => name.to_uppercase(),
+        Some("PascalCase") => words.iter().map(capitalize).collect(),
+        Some("camelCase") => words
+            .iter()
+            .enumerate()
+            .map(|(idx, word)| match idx {
+                0 => word.clone(),
+                _ => capitalize(word),
+            })
+            .collect(),
+        Some("snake_case") => words.join("_"),
+        Some("SCREAMING_SNAKE_CASE") => words.join("_").to_uppercase(),
+        Some("kebab-case") => words.join("-"),
+        Some("SCREAMING-KEBAB-CASE") => words.join("-").to_uppercase(),
+        _ => name.into(),
+    }
+}
@@ -1,4 +1,8 @@
-use jsoncodegen::{codegen, schema};
+use jsoncodegen::{
+    codegen, jsonc,
+    schema::{self, Field, FieldType, Schema},
+};
+use serde::Serialize;
 use serde_json::Value;
 use std::io::Cursor;
 use wasm_bindgen::prelude::*;
@@ -10,16 +14,154 @@ pub enum Lang {
 }
 
 #[wasm_bindgen]
-pub fn codegen(json: &str, lang: Lang) -> Result<String, JsValue> {
-    let json: Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
-    let schema = schema::extract(json);
+pub enum InputFormat {
+    Json,
+    /// comments and trailing commas allowed
+    Jsonc,
+    /// one document per line, merged as the elements of a root array
+    Ndjson,
+}
 
-    let mut out = Cursor::new(Vec::new());
-    match lang {
-        Lang::Java => codegen::java(schema, &mut out).map_err(|e| e.to_string())?,
-        Lang::Rust => codegen::rust(schema, &mut out).map_err(|e| e.to_string())?,
+/// `options` is a json object interpreted by the generator of the chosen language
+/// (eg: `{"package": "com.example"}` for java). defaults are used when it is absent.
+/// the input is parsed as plain json unless `input_format` says otherwise.
+#[wasm_bindgen]
+pub fn codegen(
+    json: &str,
+    lang: Lang,
+    options: Option<String>,
+    input_format: Option<InputFormat>,
+) -> Result<String, JsValue> {
+    let schema = match input_format {
+        None | Some(InputFormat::Json) => serde_json::from_str(json).map(extract),
+        Some(InputFormat::Jsonc) => jsonc::from_str(json).map(extract),
+        Some(InputFormat::Ndjson) => Schema::from_ndjson(json.as_bytes()).map(Ok),
     }
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())?;
+    let options: Value = match options {
+        Some(options) => serde_json::from_str(&options).map_err(|e| e.to_string())?,
+        None => Value::Object(Default::default()),
+    };
+
+    let lang = match lang {
+        Lang::Java => "java",
+        Lang::Rust => "rust",
+    };
+    let mut out = Cursor::new(Vec::new());
+    codegen::dispatch(lang, schema, options, &mut out).map_err(|e| e.to_string())?;
     let code = String::from_utf8(out.into_inner()).map_err(|e| e.to_string())?;
 
     Ok(code)
 }
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Validation {
+    valid: bool,
+    /// compact schema. eg: `{id:int,tags:[str]?}`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    schema: Option<String>,
+    /// classes/structs and unions that would be generated, including the root.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    type_count: Option<usize>,
+    /// fields whose values have more than one type. eg: `Field 'id' has conflicting types: int and str`
+    warnings: Vec<String>,
+    /// why the json could not be parsed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// schema info for feedback before generating any code.
+/// `{"valid": true, "schema": "{id:int}", "typeCount": 1, "warnings": []}`,
+/// or `{"valid": false, "error": "...", "warnings": []}` when the json can't be parsed.
+#[wasm_bindgen]
+pub fn validate_json(json: &str) -> Result<JsValue, JsValue> {
+    let schema = serde_json::from_str::<Value>(json)
+        .map_err(|e| e.to_string())
+        .and_then(|json| extract(json).map_err(|e| e.to_string()));
+    let validation = match schema {
+        Ok(schema) => {
+            let mut warnings = vec![];
+            match &schema {
+                Schema::Object(fields) => conflicts(fields, "", &mut warnings),
+                Schema::Array(ty) => conflicts_in(ty, "[]", &mut warnings),
+            }
+            let type_count = 1 + schema
+                .iter()
+                .filter(|ty| matches!(ty, FieldType::Object(_) | FieldType::Union(_)))
+                .count();
+            Validation {
+                valid: true,
+                schema: Some(schema.to_string()),
+                type_count: Some(type_count),
+                warnings,
+                error: None,
+            }
+        }
+        Err(error) => Validation {
+            valid: false,
+            schema: None,
+            type_count: None,
+            warnings: vec![],
+            error: Some(error),
+        },
+    };
+
+    Ok(validation.serialize(&serde_wasm_bindgen::Serializer::json_compatible())?)
+}
+
+/// schema of the json. `schema::extract` panics on a top level primitive.
+fn extract(json: Value) -> Result<Schema, codegen::CodegenError> {
+    match json.is_object() || json.is_array() {
+        true => Ok(schema::extract(json)),
+        false => Err(codegen::CodegenError::InvalidTopLevel),
+    }
+}
+
+/// a warning for every union nested in `fields`, with the dotted path of the field.
+fn conflicts(fields: &[Field], path: &str, warnings: &mut Vec<String>) {
+    for field in fields {
+        let path = match path.is_empty() {
+            true => field.name.clone(),
+            false => format!("{}.{}", path, field.name),
+        };
+        conflicts_in(&field.ty, &path, warnings);
+    }
+}
+
+fn conflicts_in(ty: &FieldType, path: &str, warnings: &mut Vec<String>) {
+    match ty {
+        FieldType::Object(fields) => conflicts(fields, path, warnings),
+        FieldType::Array(ty) => conflicts_in(ty, &format!("{}[]", path), warnings),
+        FieldType::Optional(ty) | FieldType::Map(ty) => conflicts_in(ty, path, warnings),
+        FieldType::Tuple(tys) => {
+            for (idx, ty) in tys.iter().enumerate() {
+                conflicts_in(ty, &format!("{}[{}]", path, idx), warnings);
+            }
+        }
+        FieldType::Union(tys) => {
+            let names: Vec<String> = tys.iter().map(type_name).collect();
+            warnings.push(format!(
+                "Field '{}' has conflicting types: {}",
+                path,
+                names.join(" and ")
+            ));
+            for ty in tys {
+                conflicts_in(ty, path, warnings);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn type_name(ty: &FieldType) -> String {
+    match ty {
+        FieldType::Object(_) | FieldType::EmptyObject => "object".into(),
+        FieldType::Array(_) => "array".into(),
+        FieldType::Map(_) => "map".into(),
+        FieldType::Tuple(_) => "tuple".into(),
+        FieldType::Optional(ty) => type_name(ty),
+        ty => ty.to_string(),
+    }
+}